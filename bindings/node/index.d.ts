@@ -19,6 +19,12 @@ export function wordPieceDecoder(prefix?: string = '##', cleanup?: bool = true):
 export const enum TruncationDirection {
   Left = 'Left',
   Right = 'Right',
+  Middle = 'Middle',
+}
+export const enum TruncationBoundary {
+  Token = 'Token',
+  Word = 'Word',
+  Sentence = 'Sentence',
 }
 export const enum TruncationStrategy {
   LongestFirst = 'LongestFirst',
@@ -107,6 +113,7 @@ export function templateProcessing(
   single: string,
   pair?: string | undefined | null,
   specialTokens?: Array<[string, number]> | undefined | null,
+  multi?: string | undefined | null,
 ): Processor
 export function sequenceProcessing(processors: Array<Processor>): Processor
 export const enum PaddingDirection {
@@ -120,16 +127,21 @@ export interface PaddingOptions {
   padId?: number
   padTypeId?: number
   padToken?: string
+  buckets?: Array<number>
 }
 export interface EncodeOptions {
   isPretokenized?: boolean
   addSpecialTokens?: boolean
+  /** The special tokens that can be matched in the input, all of them by default */
+  allowedSpecialTokens?: Array<string>
 }
 export interface TruncationOptions {
   maxLength?: number
   strategy?: TruncationStrategy
   direction?: string | TruncationDirection
   stride?: number
+  headRatio?: number
+  boundary?: TruncationBoundary
 }
 export interface AddedTokenOptions {
   singleWord?: boolean
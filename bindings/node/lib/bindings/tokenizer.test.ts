@@ -101,6 +101,18 @@ describe('Tokenizer', () => {
   //   expect(output.getTokens()).toEqual(['Hey', 'Ġthere', 'Ġdear', 'Ġfriend', '!'])
   // }, 10000)
 
+  it('reports the kind of error in its code', () => {
+    expect(() => Tokenizer.fromString('{"version": "2.0"}')).toThrow(
+      expect.objectContaining({
+        code: 'TokenizerError.UnsupportedVersion',
+        message: "Unknown tokenizer version '2.0'",
+      }),
+    )
+    expect(() => Tokenizer.fromString('{')).toThrow(
+      expect.objectContaining({ code: 'TokenizerError.Serialization' }),
+    )
+  })
+
   describe('addTokens', () => {
     it('accepts a list of string as new tokens when initial model is empty', () => {
       const model = BPE.empty()
@@ -174,6 +186,20 @@ describe('Tokenizer', () => {
       expect(encoded.getIds()).toEqual([0, 1, 2, 3])
     })
 
+    it('only matches the allowed special tokens', async () => {
+      tokenizer.addSpecialTokens(['[SEP]'])
+
+      let encoded = await tokenizer.encode('my name [SEP]')
+      expect(encoded.getTokens()).toEqual(['my', 'name', '[SEP]'])
+
+      // The empty model can't tokenize the special token as plain text
+      encoded = await tokenizer.encode('my name [SEP]', null, { allowedSpecialTokens: [] })
+      expect(encoded.getTokens()).toEqual(['my', 'name'])
+
+      encoded = await tokenizer.encode('my name [SEP]', null, { allowedSpecialTokens: ['[SEP]'] })
+      expect(encoded.getTokens()).toEqual(['my', 'name', '[SEP]'])
+    })
+
     it('returns an Encoding', async () => {
       const encoding = await tokenizer.encode('my name is john', 'pair')
 
@@ -1,6 +1,7 @@
 use crate::arc_rwlock_serde;
 use serde::{Deserialize, Serialize};
 extern crate tokenizers as tk;
+use crate::error::to_napi_error;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
@@ -19,7 +20,7 @@ pub struct Decoder {
 #[napi]
 impl Decoder {
   #[napi]
-  pub fn decode(&self, tokens: Vec<String>) -> Result<String> {
+  pub fn decode(&self, tokens: Vec<String>, env: Env) -> Result<String> {
     use tk::Decoder;
 
     self
@@ -29,7 +30,7 @@ impl Decoder {
       .read()
       .unwrap()
       .decode(tokens)
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+      .map_err(|e| to_napi_error(&env, e))
   }
 }
 
@@ -43,6 +44,19 @@ impl tk::Decoder for Decoder {
       .unwrap()
       .decode_chain(tokens)
   }
+
+  fn decode_chain_aligned(
+    &self,
+    tokens: Vec<tk::AlignedString>,
+  ) -> tk::Result<Vec<tk::AlignedString>> {
+    self
+      .decoder
+      .as_ref()
+      .ok_or("Uninitialized Decoder")?
+      .read()
+      .unwrap()
+      .decode_chain_aligned(tokens)
+  }
 }
 
 #[napi]
@@ -108,11 +122,11 @@ pub fn metaspace_decoder(
 }
 
 #[napi]
-pub fn replace_decoder(pattern: String, content: String) -> Result<Decoder> {
+pub fn replace_decoder(pattern: String, content: String, env: Env) -> Result<Decoder> {
   Ok(Decoder {
     decoder: Some(Arc::new(RwLock::new(
       tk::normalizers::replace::Replace::new(pattern, content)
-        .map_err(|e| Error::from_reason(e.to_string()))?
+        .map_err(|e| to_napi_error(&env, e))?
         .into(),
     ))),
   })
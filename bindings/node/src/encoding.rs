@@ -32,6 +32,7 @@ impl TryFrom<JsEncoding> for Encoding {
 pub enum JsTruncationDirection {
   Left,
   Right,
+  Middle,
 }
 
 impl From<JsTruncationDirection> for TruncationDirection {
@@ -39,6 +40,7 @@ impl From<JsTruncationDirection> for TruncationDirection {
     match value {
       JsTruncationDirection::Left => TruncationDirection::Left,
       JsTruncationDirection::Right => TruncationDirection::Right,
+      JsTruncationDirection::Middle => TruncationDirection::Middle,
     }
   }
 }
@@ -49,6 +51,7 @@ impl TryFrom<String> for JsTruncationDirection {
     match value.as_str() {
       "left" => Ok(JsTruncationDirection::Left),
       "right" => Ok(JsTruncationDirection::Right),
+      "middle" => Ok(JsTruncationDirection::Middle),
       s => Err(Error::from_reason(format!(
         "{s:?} is not a valid direction"
       ))),
@@ -218,6 +221,7 @@ impl JsEncoding {
       Some(Either::A(s)) => match s.as_str() {
         "left" => TruncationDirection::Left,
         "right" => TruncationDirection::Right,
+        "middle" => TruncationDirection::Middle,
         d => {
           return Err(Error::from_reason(format!(
             "{d} is not a valid truncation direction"
@@ -226,6 +230,11 @@ impl JsEncoding {
       },
       Some(Either::B(t)) => t.into(),
     };
+    if direction == TruncationDirection::Middle && stride > 0 {
+      return Err(Error::from_reason(
+        "stride can't be used when truncating the middle",
+      ));
+    }
     self
       .encoding
       .as_mut()
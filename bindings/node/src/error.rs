@@ -0,0 +1,37 @@
+extern crate tokenizers as tk;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsUnknown};
+
+/// The `code` of the JS errors, telling apart the kinds of errors of the tokenizers. They
+/// match the exceptions of the python bindings.
+fn error_code(error: &tk::Error) -> &'static str {
+  match error {
+    tk::Error::Io(_) => "TokenizerError.Io",
+    tk::Error::Model(_) => "TokenizerError.Model",
+    tk::Error::Parse { .. } => "TokenizerError.Parse",
+    tk::Error::Truncation(_) => "TokenizerError.Truncation",
+    tk::Error::Padding(_) => "TokenizerError.Padding",
+    tk::Error::Packing(_) => "TokenizerError.Packing",
+    tk::Error::AddedTokens(_) => "TokenizerError.AddedTokens",
+    tk::Error::Processor(_) => "TokenizerError.Processor",
+    tk::Error::Serialization(_) => "TokenizerError.Serialization",
+    tk::Error::UnsupportedVersion(_) => "TokenizerError.UnsupportedVersion",
+    _ => "TokenizerError.Other",
+  }
+}
+
+/// Convert an error of the tokenizers into a JS error, whose `code` gives its kind, like
+/// `TokenizerError.Truncation`
+pub(crate) fn to_napi_error(env: &Env, error: tk::Error) -> Error {
+  let code = error_code(&error);
+  let js_error = || -> Result<JsUnknown> {
+    let mut js_error = env.create_error(Error::from_reason(error.to_string()))?;
+    js_error.set_named_property("code", code)?;
+    Ok(js_error.into_unknown())
+  };
+  match js_error() {
+    Ok(js_error) => Error::from(js_error),
+    Err(e) => e,
+  }
+}
@@ -5,6 +5,7 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 mod arc_rwlock_serde;
 pub mod decoders;
 pub mod encoding;
+mod error;
 pub mod models;
 pub mod normalizers;
 pub mod pre_tokenizers;
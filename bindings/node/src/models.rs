@@ -1,4 +1,5 @@
 use crate::arc_rwlock_serde;
+use crate::error::to_napi_error;
 use crate::tasks::models::{BPEFromFilesTask, WordLevelFromFilesTask, WordPieceFromFilesTask};
 use crate::trainers::Trainer;
 use napi::bindgen_prelude::*;
@@ -44,13 +45,16 @@ impl Bpe {
   }
 
   #[napi(factory, ts_return_type = "Model")]
-  pub fn init(vocab: Vocab, merges: Merges, options: Option<BpeOptions>) -> Result<Model> {
+  pub fn init(
+    vocab: Vocab,
+    merges: Merges,
+    options: Option<BpeOptions>,
+    env: Env,
+  ) -> Result<Model> {
     let options = options.unwrap_or_default();
     let mut builder = tk::models::bpe::BPE::builder().vocab_and_merges(vocab, merges);
     builder = options.apply_to_bpe_builder(builder);
-    let model = builder
-      .build()
-      .map_err(|e| Error::from_reason(e.to_string()))?;
+    let model = builder.build().map_err(|e| to_napi_error(&env, e))?;
 
     Ok(Model {
       model: Some(Arc::new(RwLock::new(model.into()))),
@@ -87,6 +91,16 @@ impl tk::Model for Model {
       .tokenize(sequence)
   }
 
+  fn tokenize_ids(&self, sequence: &str) -> tk::Result<Vec<u32>> {
+    self
+      .model
+      .as_ref()
+      .ok_or("Uninitialized Model")?
+      .read()
+      .unwrap()
+      .tokenize_ids(sequence)
+  }
+
   fn token_to_id(&self, token: &str) -> Option<u32> {
     self.model.as_ref()?.read().unwrap().token_to_id(token)
   }
@@ -206,14 +220,12 @@ pub struct WordPiece {}
 #[napi]
 impl WordPiece {
   #[napi(factory, ts_return_type = "Model")]
-  pub fn init(vocab: Vocab, options: Option<WordPieceOptions>) -> Result<Model> {
+  pub fn init(vocab: Vocab, options: Option<WordPieceOptions>, env: Env) -> Result<Model> {
     let options = options.unwrap_or_default();
 
     let mut builder = tk::models::wordpiece::WordPiece::builder().vocab(vocab);
     builder = options.apply_to_wordpiece_builder(builder);
-    let model = builder
-      .build()
-      .map_err(|e| Error::from_reason(e.to_string()))?;
+    let model = builder.build().map_err(|e| to_napi_error(&env, e))?;
 
     Ok(Model {
       model: Some(Arc::new(RwLock::new(model.into()))),
@@ -263,13 +275,11 @@ pub struct WordLevel {}
 #[napi]
 impl WordLevel {
   #[napi(factory, ts_return_type = "Model")]
-  pub fn init(vocab: Vocab, options: Option<WordLevelOptions>) -> Result<Model> {
+  pub fn init(vocab: Vocab, options: Option<WordLevelOptions>, env: Env) -> Result<Model> {
     let options = options.unwrap_or_default();
     let mut builder = tk::models::wordlevel::WordLevel::builder().vocab(vocab);
     builder = options.apply_to_wordlevel_builder(builder);
-    let model = builder
-      .build()
-      .map_err(|e| Error::from_reason(e.to_string()))?;
+    let model = builder.build().map_err(|e| to_napi_error(&env, e))?;
 
     Ok(Model {
       model: Some(Arc::new(RwLock::new(model.into()))),
@@ -311,7 +321,11 @@ pub struct Unigram {}
 #[napi]
 impl Unigram {
   #[napi(factory, ts_return_type = "Model")]
-  pub fn init(vocab: Vec<(String, f64)>, options: Option<UnigramOptions>) -> Result<Model> {
+  pub fn init(
+    vocab: Vec<(String, f64)>,
+    options: Option<UnigramOptions>,
+    env: Env,
+  ) -> Result<Model> {
     let options = options.unwrap_or_default();
 
     let unigram = tk::models::unigram::Unigram::from(
@@ -319,7 +333,7 @@ impl Unigram {
       options.unk_id.map(|u| u as usize),
       options.byte_fallback.unwrap_or(false),
     )
-    .map_err(|e| Error::from_reason(e.to_string()))?;
+    .map_err(|e| to_napi_error(&env, e))?;
 
     Ok(Model {
       model: Some(Arc::new(RwLock::new(unigram.into()))),
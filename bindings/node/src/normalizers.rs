@@ -1,4 +1,5 @@
 use crate::arc_rwlock_serde;
+use crate::error::to_napi_error;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
@@ -18,14 +19,14 @@ pub struct Normalizer {
 #[napi]
 impl Normalizer {
   #[napi]
-  pub fn normalize_string(&self, sequence: String) -> Result<String> {
+  pub fn normalize_string(&self, sequence: String, env: Env) -> Result<String> {
     use tk::Normalizer;
 
     let mut normalized = NormalizedString::from(sequence);
 
     self
       .normalize(&mut normalized)
-      .map_err(|e| Error::from_reason(format!("{}", e)))?;
+      .map_err(|e| to_napi_error(&env, e))?;
 
     Ok(normalized.get().to_string())
   }
@@ -159,11 +160,11 @@ pub fn lowercase() -> Normalizer {
 }
 
 #[napi]
-pub fn replace(pattern: String, content: String) -> Result<Normalizer> {
+pub fn replace(pattern: String, content: String, env: Env) -> Result<Normalizer> {
   Ok(Normalizer {
     normalizer: Some(Arc::new(RwLock::new(
       tk::normalizers::replace::Replace::new(pattern, content)
-        .map_err(|e| Error::from_reason(e.to_string()))?
+        .map_err(|e| to_napi_error(&env, e))?
         .into(),
     ))),
   })
@@ -1,4 +1,5 @@
 use crate::arc_rwlock_serde;
+use crate::error::to_napi_error;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
@@ -80,7 +81,7 @@ impl PreTokenizer {
 
     self
       .pre_tokenize(&mut pretokenized)
-      .map_err(|e| Error::from_reason(format!("{}", e)))?;
+      .map_err(|e| to_napi_error(&env, e))?;
 
     pretokenized
       .get_splits(tk::OffsetReferential::Original, tk::OffsetType::Char)
@@ -178,6 +179,7 @@ pub fn split_pre_tokenizer(
   pattern: String,
   behavior: String,
   invert: Option<bool>,
+  env: Env,
 ) -> Result<PreTokenizer> {
   let behavior: JsSplitDelimiterBehavior = behavior.try_into()?;
   let invert = invert.unwrap_or(false);
@@ -185,7 +187,7 @@ pub fn split_pre_tokenizer(
   Ok(PreTokenizer {
     pretok: Some(Arc::new(RwLock::new(
       tk::pre_tokenizers::split::Split::new(pattern, behavior.into(), invert)
-        .map_err(|e| Error::from_reason(e.to_string()))?
+        .map_err(|e| to_napi_error(&env, e))?
         .into(),
     ))),
   })
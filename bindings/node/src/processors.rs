@@ -1,4 +1,5 @@
 use crate::arc_rwlock_serde;
+use crate::error::to_napi_error;
 use serde::{Deserialize, Serialize};
 extern crate tokenizers as tk;
 use napi::bindgen_prelude::*;
@@ -6,7 +7,7 @@ use napi_derive::napi;
 use std::sync::{Arc, RwLock};
 
 use tk::processors::PostProcessorWrapper;
-use tk::Encoding;
+use tk::{ChatEncoding, Encoding};
 
 #[derive(Clone, Serialize, Deserialize)]
 #[napi]
@@ -26,6 +27,16 @@ impl tk::PostProcessor for Processor {
       .added_tokens(is_pair)
   }
 
+  fn added_tokens_multi(&self, n_sequences: usize) -> tk::Result<usize> {
+    self
+      .processor
+      .as_ref()
+      .expect("Uninitialized PostProcessor")
+      .read()
+      .unwrap()
+      .added_tokens_multi(n_sequences)
+  }
+
   fn process_encodings(
     &self,
     encodings: Vec<Encoding>,
@@ -39,6 +50,40 @@ impl tk::PostProcessor for Processor {
       .unwrap()
       .process_encodings(encodings, add_special_tokens)
   }
+
+  fn added_tokens_chat(&self, roles: &[String]) -> tk::Result<usize> {
+    self
+      .processor
+      .as_ref()
+      .ok_or("Uninitialized PostProcessor")?
+      .read()
+      .unwrap()
+      .added_tokens_chat(roles)
+  }
+
+  fn chat_truncation(&self) -> tk::ChatTruncation {
+    self
+      .processor
+      .as_ref()
+      .expect("Uninitialized PostProcessor")
+      .read()
+      .unwrap()
+      .chat_truncation()
+  }
+
+  fn process_chat(
+    &self,
+    turns: Vec<(String, Encoding)>,
+    add_special_tokens: bool,
+  ) -> tk::Result<ChatEncoding> {
+    self
+      .processor
+      .as_ref()
+      .ok_or("Uninitialized PostProcessor")?
+      .read()
+      .unwrap()
+      .process_chat(turns, add_special_tokens)
+  }
 }
 
 #[napi]
@@ -87,6 +132,8 @@ pub fn template_processing(
   single: String,
   pair: Option<String>,
   special_tokens: Option<Vec<(String, u32)>>,
+  multi: Option<String>,
+  env: Env,
 ) -> Result<Processor> {
   let special_tokens = special_tokens.unwrap_or_default();
   let mut builder = tk::processors::template::TemplateProcessing::builder();
@@ -95,9 +142,10 @@ pub fn template_processing(
   if let Some(pair) = pair {
     builder.try_pair(pair).map_err(Error::from_reason)?;
   }
-  let processor = builder
-    .build()
-    .map_err(|e| Error::from_reason(e.to_string()))?;
+  if let Some(multi) = multi {
+    builder.try_multi(multi).map_err(Error::from_reason)?;
+  }
+  let processor = builder.build().map_err(|e| to_napi_error(&env, e.into()))?;
 
   Ok(Processor {
     processor: Some(Arc::new(RwLock::new(processor.into()))),
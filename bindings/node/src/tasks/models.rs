@@ -1,5 +1,6 @@
 extern crate tokenizers as tk;
 
+use crate::error::to_napi_error;
 use crate::models::Model;
 use napi::bindgen_prelude::*;
 use std::sync::{Arc, RwLock};
@@ -12,19 +13,21 @@ pub struct BPEFromFilesTask {
 }
 
 impl Task for BPEFromFilesTask {
-  type Output = BPE;
+  type Output = tk::Result<BPE>;
   type JsValue = Model;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    self
-      .builder
-      .take()
-      .ok_or(Error::from_reason("Empty builder".to_string()))?
-      .build()
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .builder
+        .take()
+        .ok_or(Error::from_reason("Empty builder".to_string()))?
+        .build(),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(Model {
       model: Some(Arc::new(RwLock::new(output.into()))),
     })
@@ -36,19 +39,21 @@ pub struct WordPieceFromFilesTask {
 }
 
 impl Task for WordPieceFromFilesTask {
-  type Output = WordPiece;
+  type Output = tk::Result<WordPiece>;
   type JsValue = Model;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    self
-      .builder
-      .take()
-      .ok_or(Error::from_reason("Empty builder".to_string()))?
-      .build()
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .builder
+        .take()
+        .ok_or(Error::from_reason("Empty builder".to_string()))?
+        .build(),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(Model {
       model: Some(Arc::new(RwLock::new(output.into()))),
     })
@@ -59,19 +64,21 @@ pub struct WordLevelFromFilesTask {
 }
 
 impl Task for WordLevelFromFilesTask {
-  type Output = WordLevel;
+  type Output = tk::Result<WordLevel>;
   type JsValue = Model;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    self
-      .builder
-      .take()
-      .ok_or(Error::from_reason("Empty builder".to_string()))?
-      .build()
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .builder
+        .take()
+        .ok_or(Error::from_reason("Empty builder".to_string()))?
+        .build(),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(Model {
       model: Some(Arc::new(RwLock::new(output.into()))),
     })
@@ -1,37 +1,42 @@
 extern crate tokenizers as tk;
 
 use crate::encoding::*;
+use crate::error::to_napi_error;
 use crate::tokenizer::Tokenizer;
 use napi::bindgen_prelude::*;
-use tk::tokenizer::{EncodeInput, Encoding};
+use tk::tokenizer::{EncodeInput, Encoding, SpecialTokensMatching};
 
 pub struct EncodeTask<'s> {
   pub tokenizer: Tokenizer,
   pub input: Option<EncodeInput<'s>>,
   pub add_special_tokens: bool,
+  pub special_tokens_matching: SpecialTokensMatching,
 }
 
 impl Task for EncodeTask<'static> {
-  type Output = Encoding;
+  type Output = tk::Result<Encoding>;
   type JsValue = JsEncoding;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    self
-      .tokenizer
-      .tokenizer
-      .read()
-      .unwrap()
-      .encode_char_offsets(
-        self
-          .input
-          .take()
-          .ok_or(Error::from_reason("No provided input"))?,
-        self.add_special_tokens,
-      )
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .tokenizer
+        .tokenizer
+        .read()
+        .unwrap()
+        .encode_char_offsets_with_matching(
+          self
+            .input
+            .take()
+            .ok_or(Error::from_reason("No provided input"))?,
+          self.add_special_tokens,
+          &self.special_tokens_matching,
+        ),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(JsEncoding {
       encoding: Some(output),
     })
@@ -45,20 +50,22 @@ pub struct DecodeTask {
 }
 
 impl Task for DecodeTask {
-  type Output = String;
+  type Output = tk::Result<String>;
   type JsValue = String;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    self
-      .tokenizer
-      .tokenizer
-      .read()
-      .unwrap()
-      .decode(&self.ids, self.skip_special_tokens)
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .tokenizer
+        .tokenizer
+        .read()
+        .unwrap()
+        .decode(&self.ids, self.skip_special_tokens),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(output)
   }
 }
@@ -66,29 +73,33 @@ pub struct EncodeBatchTask<'s> {
   pub tokenizer: Tokenizer,
   pub inputs: Option<Vec<EncodeInput<'s>>>,
   pub add_special_tokens: bool,
+  pub special_tokens_matching: SpecialTokensMatching,
 }
 
 impl Task for EncodeBatchTask<'static> {
-  type Output = Vec<Encoding>;
+  type Output = tk::Result<Vec<Encoding>>;
   type JsValue = Vec<JsEncoding>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    self
-      .tokenizer
-      .tokenizer
-      .read()
-      .unwrap()
-      .encode_batch_char_offsets(
-        self
-          .inputs
-          .take()
-          .ok_or(Error::from_reason("No provided input"))?,
-        self.add_special_tokens,
-      )
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .tokenizer
+        .tokenizer
+        .read()
+        .unwrap()
+        .encode_batch_char_offsets_with_matching(
+          self
+            .inputs
+            .take()
+            .ok_or(Error::from_reason("No provided input"))?,
+          self.add_special_tokens,
+          &self.special_tokens_matching,
+        ),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(
       output
         .into_iter()
@@ -107,21 +118,23 @@ pub struct DecodeBatchTask {
 }
 
 impl Task for DecodeBatchTask {
-  type Output = Vec<String>;
+  type Output = tk::Result<Vec<String>>;
   type JsValue = Vec<String>;
 
   fn compute(&mut self) -> Result<Self::Output> {
     let ids: Vec<_> = self.ids.iter().map(|s| s.as_slice()).collect();
-    self
-      .tokenizer
-      .tokenizer
-      .read()
-      .unwrap()
-      .decode_batch(&ids, self.skip_special_tokens)
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+    Ok(
+      self
+        .tokenizer
+        .tokenizer
+        .read()
+        .unwrap()
+        .decode_batch(&ids, self.skip_special_tokens),
+    )
   }
 
-  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let output = output.map_err(|e| to_napi_error(&env, e))?;
     Ok(output)
   }
 }
@@ -9,6 +9,7 @@ use crate::trainers::Trainer;
 use std::collections::HashMap;
 use tokenizers::Model as ModelTrait;
 
+use crate::error::to_napi_error;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use std::sync::{Arc, RwLock};
@@ -53,6 +54,8 @@ pub struct PaddingOptions {
   pub pad_id: Option<u32>,
   pub pad_type_id: Option<u32>,
   pub pad_token: Option<String>,
+  /// Pad each sequence to the smallest of these lengths that fits it
+  pub buckets: Option<Vec<u32>>,
 }
 
 impl TryFrom<PaddingOptions> for tk::PaddingParams {
@@ -74,9 +77,12 @@ impl TryFrom<PaddingOptions> for tk::PaddingParams {
       pad_type_id: value.pad_type_id.unwrap_or_default(),
       pad_token: value.pad_token.unwrap_or("[PAD]".to_string()),
       direction,
-      strategy: match value.max_length {
-        Some(length) => tk::PaddingStrategy::Fixed(length as usize),
-        None => tk::PaddingStrategy::BatchLongest,
+      strategy: match (value.max_length, value.buckets) {
+        (Some(length), _) => tk::PaddingStrategy::Fixed(length as usize),
+        (None, Some(buckets)) => {
+          tk::PaddingStrategy::Buckets(buckets.into_iter().map(|b| b as usize).collect())
+        }
+        (None, None) => tk::PaddingStrategy::BatchLongest,
       },
     })
   }
@@ -87,6 +93,8 @@ impl TryFrom<PaddingOptions> for tk::PaddingParams {
 pub struct EncodeOptions {
   pub is_pretokenized: Option<bool>,
   pub add_special_tokens: Option<bool>,
+  /// The special tokens that can be matched in the input, all of them by default
+  pub allowed_special_tokens: Option<Vec<String>>,
 }
 
 #[derive(Default)]
@@ -94,6 +102,7 @@ struct EncodeOptionsDef {
   // TODO
   // is_pretokenized: bool,
   add_special_tokens: bool,
+  special_tokens_matching: tk::SpecialTokensMatching,
 }
 
 impl From<EncodeOptions> for EncodeOptionsDef {
@@ -102,6 +111,11 @@ impl From<EncodeOptions> for EncodeOptionsDef {
       // TODO
       // is_pretokenized: value.is_pretokenized.unwrap_or(false),
       add_special_tokens: value.add_special_tokens.unwrap_or(true),
+      special_tokens_matching: match value.allowed_special_tokens {
+        None => tk::SpecialTokensMatching::All,
+        Some(allowed) if allowed.is_empty() => tk::SpecialTokensMatching::None,
+        Some(allowed) => tk::SpecialTokensMatching::Only(allowed.into_iter().collect()),
+      },
     }
   }
 }
@@ -113,6 +127,25 @@ pub struct TruncationOptions {
   pub strategy: Option<JsTruncationStrategy>,
   pub direction: Option<Either<String, JsTruncationDirection>>,
   pub stride: Option<u32>,
+  pub head_ratio: Option<f64>,
+  pub boundary: Option<JsTruncationBoundary>,
+}
+
+#[napi(string_enum, js_name = "TruncationBoundary")]
+pub enum JsTruncationBoundary {
+  Token,
+  Word,
+  Sentence,
+}
+
+impl From<JsTruncationBoundary> for tk::TruncationBoundary {
+  fn from(value: JsTruncationBoundary) -> Self {
+    match value {
+      JsTruncationBoundary::Token => tk::TruncationBoundary::Token,
+      JsTruncationBoundary::Word => tk::TruncationBoundary::Word,
+      JsTruncationBoundary::Sentence => tk::TruncationBoundary::Sentence,
+    }
+  }
 }
 
 impl TryFrom<TruncationOptions> for tk::TruncationParams {
@@ -133,6 +166,8 @@ impl TryFrom<TruncationOptions> for tk::TruncationParams {
       strategy: value.strategy.map(|s| s.into()).unwrap_or_default(),
       direction,
       stride: value.stride.unwrap_or_default() as usize,
+      head_ratio: value.head_ratio.map(|r| r as f32).unwrap_or(0.5),
+      boundary: value.boundary.map(|b| b.into()).unwrap_or_default(),
     })
   }
 }
@@ -244,14 +279,14 @@ impl Tokenizer {
   }
 
   #[napi]
-  pub fn save(&self, path: String, pretty: Option<bool>) -> Result<()> {
+  pub fn save(&self, path: String, pretty: Option<bool>, env: Env) -> Result<()> {
     let pretty = pretty.unwrap_or(false);
     self
       .tokenizer
       .read()
       .unwrap()
       .save(path, pretty)
-      .map_err(|e| Error::from_reason(format!("{}", e)))
+      .map_err(|e| to_napi_error(&env, e))
   }
 
   #[napi]
@@ -289,6 +324,7 @@ impl Tokenizer {
       tokenizer: (*self).clone(),
       input: Some(input),
       add_special_tokens: options.add_special_tokens,
+      special_tokens_matching: options.special_tokens_matching,
     })
   }
 
@@ -308,6 +344,7 @@ impl Tokenizer {
       tokenizer: (*self).clone(),
       inputs: Some(inputs),
       add_special_tokens: options.add_special_tokens,
+      special_tokens_matching: options.special_tokens_matching,
     })
   }
 
@@ -334,25 +371,23 @@ impl Tokenizer {
   }
 
   #[napi(factory)]
-  pub fn from_string(s: String) -> Result<Self> {
+  pub fn from_string(s: String, env: Env) -> Result<Self> {
     let tokenizer: tk::tokenizer::TokenizerImpl<
       Model,
       Normalizer,
       PreTokenizer,
       Processor,
       Decoder,
-    > = s
-      .parse()
-      .map_err(|e| Error::from_reason(format!("{}", e)))?;
+    > = s.parse().map_err(|e| to_napi_error(&env, e))?;
     Ok(Self {
       tokenizer: Arc::new(RwLock::new(tokenizer)),
     })
   }
 
   #[napi(factory)]
-  pub fn from_file(file: String) -> Result<Self> {
-    let tokenizer = tk::tokenizer::TokenizerImpl::from_file(file)
-      .map_err(|e| Error::from_reason(format!("Error loading from file{}", e)))?;
+  pub fn from_file(file: String, env: Env) -> Result<Self> {
+    let tokenizer =
+      tk::tokenizer::TokenizerImpl::from_file(file).map_err(|e| to_napi_error(&env, e))?;
     Ok(Self {
       tokenizer: Arc::new(RwLock::new(tokenizer)),
     })
@@ -454,7 +489,7 @@ impl Tokenizer {
   }
 
   #[napi]
-  pub fn train(&mut self, files: Vec<String>) -> Result<()> {
+  pub fn train(&mut self, files: Vec<String>, env: Env) -> Result<()> {
     let mut trainer: Trainer = self
       .tokenizer
       .read()
@@ -472,7 +507,7 @@ impl Tokenizer {
       .write()
       .unwrap()
       .train_from_files(&mut trainer, files)
-      .map_err(|e| Error::from_reason(format!("{}", e)))?;
+      .map_err(|e| to_napi_error(&env, e))?;
     Ok(())
   }
 
@@ -487,6 +522,7 @@ impl Tokenizer {
     encoding: &JsEncoding,
     pair: Option<&JsEncoding>,
     add_special_tokens: Option<bool>,
+    env: Env,
   ) -> Result<JsEncoding> {
     let add_special_tokens = add_special_tokens.unwrap_or(true);
 
@@ -504,7 +540,7 @@ impl Tokenizer {
           },
           add_special_tokens,
         )
-        .map_err(|e| Error::from_reason(format!("{}", e)))?
+        .map_err(|e| to_napi_error(&env, e))?
         .into(),
     )
   }
@@ -26,6 +26,7 @@ TextEncodeInput = Union[
 
       - A :obj:`Tuple` of :data:`~tokenizers.TextInputSequence`
       - Or a :obj:`List` of :data:`~tokenizers.TextInputSequence` of size 2
+    - More than two sequences, as a :obj:`List` of :data:`~tokenizers.TextInputSequence`
 """
 
 PreTokenizedEncodeInput = Union[
@@ -40,6 +41,7 @@ PreTokenizedEncodeInput = Union[
 
       - A :obj:`Tuple` of :data:`~tokenizers.PreTokenizedInputSequence`
       - Or a :obj:`List` of :data:`~tokenizers.PreTokenizedInputSequence` of size 2
+    - More than two sequences, as a :obj:`List` of :data:`~tokenizers.PreTokenizedInputSequence`
 """
 
 InputSequence = Union[TextInputSequence, PreTokenizedInputSequence]
@@ -77,12 +79,22 @@ class SplitDelimiterBehavior(Enum):
 
 from .tokenizers import (
     AddedToken,
+    AddedTokensError,
     Encoding,
+    ModelError,
     NormalizedString,
+    PackingError,
+    PaddingError,
+    ParseError,
     PreTokenizedString,
+    ProcessorError,
     Regex,
+    SerializationError,
     Token,
     Tokenizer,
+    TokenizersError,
+    TruncationError,
+    UnsupportedVersionError,
     decoders,
     models,
     normalizers,
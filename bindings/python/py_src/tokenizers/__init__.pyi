@@ -309,7 +309,8 @@ class Encoding:
                 The length of previous content to be included in each overflowing piece
 
             direction (:obj:`str`, defaults to :obj:`right`):
-                Truncate direction
+                Truncate direction. Can be one of ``left``, ``right`` or ``middle`` to keep
+                the head and the tail, in which case the stride can't be used.
         """
         pass
     @property
@@ -678,6 +679,45 @@ class Tokenizer:
             :obj:`int`: The number of tokens that were created in the vocabulary
         """
         pass
+    def count_tokens(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True):
+        """
+        Count the tokens the given sequence and pair are encoded into, padding excluded
+
+        Args:
+            sequence (:obj:`~tokenizers.InputSequence`):
+                The main input sequence we want to encode
+
+            pair (:obj:`~tokenizers.InputSequence`, `optional`):
+                An optional input sequence. The expected format is the same that for ``sequence``.
+
+            is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+                Whether the input is already pre-tokenized
+
+            add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether to add the special tokens
+
+        Returns:
+            :obj:`int`: The number of tokens
+        """
+        pass
+    def count_tokens_batch(self, input, is_pretokenized=False, add_special_tokens=True):
+        """
+        Count in parallel the tokens each input of the batch is encoded into, padding excluded
+
+        Args:
+            input (A :obj:`List`/:obj:`Tuple` of :obj:`~tokenizers.EncodeInput`):
+                A list of single sequences or pair sequences to encode
+
+            is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+                Whether the input is already pre-tokenized
+
+            add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether to add the special tokens
+
+        Returns:
+            :obj:`List[int]`: The number of tokens of each input
+        """
+        pass
     def decode(self, ids, skip_special_tokens=True):
         """
         Decode the given list of ids back to a string
@@ -710,6 +750,27 @@ class Tokenizer:
             :obj:`List[str]`: A list of decoded strings
         """
         pass
+    def decode_with_offsets(self, ids, skip_special_tokens=True):
+        """
+        Decode the given list of ids back to a string, along with the span of this string
+        produced by each id
+
+        When several ids produce a single character together (like the bytes of a character
+        with :class:`~tokenizers.decoders.ByteLevel`), they all get the span of this character.
+        The ids that don't produce anything, like the skipped special tokens, get empty spans.
+
+        Args:
+            ids (A :obj:`List/Tuple` of :obj:`int`):
+                The list of ids that we want to decode
+
+            skip_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether the special tokens should be removed from the decoded string
+
+        Returns:
+            :obj:`Tuple[str, List[Tuple[int, int]]]`: The decoded string, and the character
+            offsets of each id in it
+        """
+        pass
     @property
     def decoder(self):
         """
@@ -717,7 +778,14 @@ class Tokenizer:
         """
         pass
     def enable_padding(
-        self, direction="right", pad_id=0, pad_type_id=0, pad_token="[PAD]", length=None, pad_to_multiple_of=None
+        self,
+        direction="right",
+        pad_id=0,
+        pad_type_id=0,
+        pad_token="[PAD]",
+        length=None,
+        pad_to_multiple_of=None,
+        buckets=None,
     ):
         """
         Enable the padding
@@ -743,9 +811,15 @@ class Tokenizer:
             length (:obj:`int`, `optional`):
                 If specified, the length at which to pad. If not specified we pad using the size of
                 the longest sequence in a batch.
+
+            buckets (:obj:`List[int]`, `optional`):
+                If specified, each sequence is padded to the smallest of these lengths that fits it.
+                The sequences longer than all the buckets are not padded.
         """
         pass
-    def enable_truncation(self, max_length, stride=0, strategy="longest_first", direction="right"):
+    def enable_truncation(
+        self, max_length, stride=0, strategy="longest_first", direction="right", head_ratio=0.5, boundary="token"
+    ):
         """
         Enable truncation
 
@@ -765,7 +839,7 @@ class Tokenizer:
                 Truncate direction
         """
         pass
-    def encode(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True):
+    def encode(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True, allowed_special_tokens=None):
         """
         Encode the given sequence and pair. This method can process raw text sequences
         as well as already pre-tokenized sequences.
@@ -798,12 +872,18 @@ class Tokenizer:
             add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
                 Whether to add the special tokens
 
+            allowed_special_tokens (:obj:`List[str]`, `optional`):
+                The only special tokens that can be matched in the sequence and its pair, the
+                others being tokenized as plain text. Use an empty list when encoding some
+                untrusted text, so that it can't produce any special token. By default, all the
+                special tokens are matched.
+
         Returns:
             :class:`~tokenizers.Encoding`: The encoded result
 
         """
         pass
-    def encode_batch(self, input, is_pretokenized=False, add_special_tokens=True):
+    def encode_batch(self, input, is_pretokenized=False, add_special_tokens=True, allowed_special_tokens=None):
         """
         Encode the given batch of inputs. This method accept both raw text sequences
         as well as already pre-tokenized sequences.
@@ -833,11 +913,114 @@ class Tokenizer:
             add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
                 Whether to add the special tokens
 
+            allowed_special_tokens (:obj:`List[str]`, `optional`):
+                The only special tokens that can be matched in the inputs, the same for the
+                whole batch. The other special tokens are tokenized as plain text, and an empty
+                list disables the matching of all of them. By default, all the special tokens
+                are matched.
+
         Returns:
             A :obj:`List` of :class:`~tokenizers.Encoding`: The encoded batch
 
         """
         pass
+    def encode_chat(self, turns, is_pretokenized=False, add_special_tokens=True):
+        """
+        Encode a conversation, rendered by a post-processor supporting them, like
+        :class:`~tokenizers.processors.ChatProcessing`
+
+        Example:
+            Here is an example of the expected input::
+
+                encode_chat([
+                    ("user", "What is the capital of France?"),
+                    ("assistant", "Paris."),
+                ])
+
+        Args:
+            turns (A :obj:`List` of :obj:`Tuple[str, InputSequence]`):
+                The turns of the conversation, each with the role of its author
+
+            is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+                Whether the turns are already pre-tokenized
+
+            add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether to add the special tokens of the roles
+
+        Returns:
+            A :obj:`Tuple` with the :class:`~tokenizers.Encoding` of the conversation, and the
+            :obj:`List[int]` mask of its tokens belonging to the assistant turns
+        """
+        pass
+    def encode_ids(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True):
+        """
+        Encode the given sequence and pair into their ids only. This gives the same ids as
+        :meth:`~tokenizers.Tokenizer.encode`, but is faster as it does not build the tokens
+        and offsets of a full :class:`~tokenizers.Encoding`.
+
+        Args:
+            sequence (:obj:`~tokenizers.InputSequence`):
+                The main input sequence we want to encode
+
+            pair (:obj:`~tokenizers.InputSequence`, `optional`):
+                An optional input sequence. The expected format is the same that for ``sequence``.
+
+            is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+                Whether the input is already pre-tokenized
+
+            add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether to add the special tokens
+
+        Returns:
+            :obj:`List[int]`: The ids
+        """
+        pass
+    def encode_ids_batch(self, input, is_pretokenized=False, add_special_tokens=True):
+        """
+        Encode the given batch of inputs into their ids only, in parallel
+
+        Args:
+            input (A :obj:`List`/:obj:`Tuple` of :obj:`~tokenizers.EncodeInput`):
+                A list of single sequences or pair sequences to encode
+
+            is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+                Whether the input is already pre-tokenized
+
+            add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether to add the special tokens
+
+        Returns:
+            :obj:`List[List[int]]`: The ids of each input
+        """
+        pass
+    def encode_with_trace(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True):
+        """
+        Encode the given sequence and pair like :meth:`~tokenizers.Tokenizer.encode`, keeping
+        track of what each stage of the pipeline does. This is useful to understand why some
+        input gets encoded the way it does.
+
+        For each input sequence, the trace contains the splits around the added tokens, the
+        normalized string with its alignments, the splits of the pre-tokenizer, and the tokens
+        of the model. It also contains the tokens inserted by the post-processor, and the final
+        encoding. All the offsets are byte offsets.
+
+        Args:
+            sequence (:obj:`~tokenizers.InputSequence`):
+                The main input sequence we want to encode
+
+            pair (:obj:`~tokenizers.InputSequence`, `optional`):
+                An optional input sequence. The expected format is the same that for ``sequence``.
+
+            is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+                Whether the input is already pre-tokenized
+
+            add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+                Whether to add the special tokens
+
+        Returns:
+            :obj:`str`: The trace, as a JSON string
+        """
+        pass
     @staticmethod
     def from_buffer(buffer):
         """
@@ -1109,3 +1292,53 @@ class Tokenizer:
                 A dict with the current truncation parameters if truncation is enabled
         """
         pass
+
+class TokenizersError(Exception):
+    """
+    Base class of all the errors raised by the tokenizers
+    """
+
+class AddedTokensError(TokenizersError):
+    """
+    The added tokens are inconsistent
+    """
+
+class ModelError(TokenizersError):
+    """
+    A model could not be built, loaded or trained
+    """
+
+class PackingError(TokenizersError):
+    """
+    The encodings could not be packed
+    """
+
+class PaddingError(TokenizersError):
+    """
+    The encodings could not be padded, or the padding parameters are invalid
+    """
+
+class ParseError(TokenizersError):
+    """
+    A merges or tiktoken file is invalid
+    """
+
+class ProcessorError(TokenizersError):
+    """
+    The post-processor could not process the encodings, or its configuration is invalid
+    """
+
+class SerializationError(TokenizersError):
+    """
+    Serializing or deserializing some JSON failed
+    """
+
+class TruncationError(TokenizersError):
+    """
+    The truncation failed, or its parameters are invalid
+    """
+
+class UnsupportedVersionError(SerializationError):
+    """
+    The serialized tokenizer uses an unsupported version of the format
+    """
@@ -132,6 +132,79 @@ class ByteLevel(PostProcessor):
         """
         pass
 
+class ChatProcessing(PostProcessor):
+    """
+    Renders the turns of a conversation, wrapping each of them in the special tokens of
+    its role, like :obj:`<|user|> Hello <|end|>`.
+
+    The conversations are encoded with :meth:`~tokenizers.Tokenizer.encode_chat`, which
+    also gives the mask of the tokens belonging to the assistant turns::
+
+        ChatProcessing(
+            roles={
+                "user": {"prefix": ["<|user|>"], "suffix": ["<|end|>"]},
+                "assistant": {
+                    "prefix": ["<|assistant|>"],
+                    "suffix": ["<|end|>"],
+                    "type_id": 1,
+                    "assistant": True,
+                },
+            },
+            special_tokens=[("<|user|>", 1), ("<|assistant|>", 2), ("<|end|>", 3)],
+        )
+
+    Other inputs are processed as if there were no post-processor.
+
+    Args:
+        roles (:obj:`Dict[str, Dict]`):
+            The roles, by name. Each of them can specify the :obj:`prefix` and :obj:`suffix`
+            special tokens added around its turns, by their id in the :obj:`special_tokens`,
+            the :obj:`type_id` of its tokens, and whether they are marked in the
+            :obj:`assistant` mask (the prefix never is)
+
+        special_tokens (:obj:`Tokens`):
+            The list of special tokens used by the roles, like for
+            :class:`~tokenizers.processors.TemplateProcessing`
+
+        truncation (:obj:`str`, defaults to :obj:`longest_first`):
+            How the conversations that are too long get truncated. :obj:`longest_first`
+            removes the tokens from the longest turns first, which usually cuts the answers of
+            the assistant, while :obj:`oldest_first` drops whole turns, the oldest first
+    """
+
+    def __init__(self, roles, special_tokens=None, truncation="longest_first"):
+        pass
+    def num_special_tokens_to_add(self, is_pair):
+        """
+        Return the number of special tokens that would be added for single/pair sentences.
+
+        Args:
+            is_pair (:obj:`bool`):
+                Whether the input would be a pair of sequences
+
+        Returns:
+            :obj:`int`: The number of tokens to add
+        """
+        pass
+    def process(self, encoding, pair=None, add_special_tokens=True):
+        """
+        Post-process the given encodings, generating the final one
+
+        Args:
+            encoding (:class:`~tokenizers.Encoding`):
+                The encoding for the first sequence
+
+            pair (:class:`~tokenizers.Encoding`, `optional`):
+                The encoding for the pair sequence
+
+            add_special_tokens (:obj:`bool`):
+                Whether to add the special tokens
+
+        Return:
+            :class:`~tokenizers.Encoding`: The final encoding
+        """
+        pass
+
 class RobertaProcessing(PostProcessor):
     """
     This post-processor takes care of adding the special tokens needed by
@@ -258,6 +331,7 @@ class TemplateProcessing(PostProcessor):
         TemplateProcessing(
             single="[CLS] $0 [SEP]",
             pair="[CLS] $A [SEP] $B:1 [SEP]:1",
+            multi="[CLS] $A [SEP] $*:1 [SEP]:1",
             special_tokens=[("[CLS]", 1), ("[SEP]", 0)],
         )
 
@@ -268,6 +342,9 @@ class TemplateProcessing(PostProcessor):
         - Specifying the sequence, with default ``type_id == 0``: ``$A`` or ``$B``
         - Specifying the `type_id` with default ``sequence == A``: ``$0``, ``$1``, ``$2``, ...
         - Specifying both: ``$A:0``, ``$B:1``, ...
+        - Specifying any other sequence, by index: ``$C``, ``$D``, ... or ``$*`` for all the
+          sequences not referenced elsewhere in the template, each followed by the pieces
+          coming after it (until the next sequence)
 
     The same construct is used for special tokens: ``<identifier>(:<type_id>)?``.
 
@@ -283,6 +360,9 @@ class TemplateProcessing(PostProcessor):
         pair (:obj:`Template`):
             The template used when both sequences are specified
 
+        multi (:obj:`Template`, `optional`):
+            The template used when more than two sequences are specified
+
         special_tokens (:obj:`Tokens`):
             The list of special tokens used in each sequences
 
@@ -303,7 +383,7 @@ class TemplateProcessing(PostProcessor):
              the same length.
     """
 
-    def __init__(self, single, pair, special_tokens):
+    def __init__(self, single, pair, special_tokens, multi=None):
         pass
     def num_special_tokens_to_add(self, is_pair):
         """
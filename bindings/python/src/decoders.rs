@@ -18,7 +18,7 @@ use tk::decoders::strip::Strip;
 use tk::decoders::wordpiece::WordPiece;
 use tk::decoders::DecoderWrapper;
 use tk::normalizers::replace::Replace;
-use tk::Decoder;
+use tk::{AlignedString, Decoder};
 use tokenizers as tk;
 
 use super::error::ToPyResult;
@@ -67,6 +67,10 @@ impl Decoder for PyDecoder {
     fn decode_chain(&self, tokens: Vec<String>) -> tk::Result<Vec<String>> {
         self.decoder.decode_chain(tokens)
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> tk::Result<Vec<AlignedString>> {
+        self.decoder.decode_chain_aligned(tokens)
+    }
 }
 
 #[pymethods]
@@ -472,8 +476,9 @@ impl Decoder for CustomDecoder {
         Python::with_gil(|py| {
             let decoded = self
                 .inner
-                .call_method(py, "decode", (tokens,), None)?
-                .extract(py)?;
+                .call_method(py, "decode", (tokens,), None)
+                .and_then(|decoded| decoded.extract(py))
+                .map_err(tk::Error::other)?;
             Ok(decoded)
         })
     }
@@ -482,8 +487,9 @@ impl Decoder for CustomDecoder {
         Python::with_gil(|py| {
             let decoded = self
                 .inner
-                .call_method(py, "decode_chain", (tokens,), None)?
-                .extract(py)?;
+                .call_method(py, "decode_chain", (tokens,), None)
+                .and_then(|decoded| decoded.extract(py))
+                .map_err(tk::Error::other)?;
             Ok(decoded)
         })
     }
@@ -543,6 +549,13 @@ impl Decoder for PyDecoderWrapper {
             PyDecoderWrapper::Custom(inner) => inner.read().unwrap().decode_chain(tokens),
         }
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> tk::Result<Vec<AlignedString>> {
+        match self {
+            PyDecoderWrapper::Wrapped(inner) => inner.read().unwrap().decode_chain_aligned(tokens),
+            PyDecoderWrapper::Custom(inner) => inner.read().unwrap().decode_chain_aligned(tokens),
+        }
+    }
 }
 
 /// Decoders Module
@@ -439,19 +439,27 @@ impl PyEncoding {
     ///         The length of previous content to be included in each overflowing piece
     ///
     ///     direction (:obj:`str`, defaults to :obj:`right`):
-    ///         Truncate direction
+    ///         Truncate direction. Can be one of ``left``, ``right`` or ``middle`` to keep
+    ///         the head and the tail, in which case the stride can't be used.
     #[pyo3(signature = (max_length, stride = 0, direction = "right"))]
     #[pyo3(text_signature = "(self, max_length, stride=0, direction='right')")]
     fn truncate(&mut self, max_length: usize, stride: usize, direction: &str) -> PyResult<()> {
         let tdir = match direction {
             "left" => Ok(TruncationDirection::Left),
             "right" => Ok(TruncationDirection::Right),
+            "middle" => Ok(TruncationDirection::Middle),
             _ => Err(PyError(format!(
                 "Invalid truncation direction value : {}",
                 direction
             ))
             .into_pyerr::<exceptions::PyValueError>()),
         }?;
+        if tdir == TruncationDirection::Middle && stride > 0 {
+            return Err(
+                PyError("`stride` can't be used when truncating the middle".into())
+                    .into_pyerr::<exceptions::PyValueError>(),
+            );
+        }
 
         self.encoding.truncate(max_length, stride, tdir);
         Ok(())
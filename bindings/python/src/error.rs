@@ -1,8 +1,9 @@
+use pyo3::create_exception;
 use pyo3::exceptions;
 use pyo3::prelude::*;
 use pyo3::type_object::PyTypeInfo;
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use tokenizers::tokenizer::Result;
+use tokenizers::tokenizer::{Error, Result};
 
 #[derive(Debug)]
 pub struct PyError(pub String);
@@ -22,10 +23,111 @@ impl Display for PyError {
 }
 impl std::error::Error for PyError {}
 
+create_exception!(
+    tokenizers,
+    TokenizersError,
+    exceptions::PyException,
+    "Base class of all the errors raised by the tokenizers"
+);
+create_exception!(
+    tokenizers,
+    ModelError,
+    TokenizersError,
+    "A model could not be built, loaded or trained"
+);
+create_exception!(
+    tokenizers,
+    ParseError,
+    TokenizersError,
+    "A merges or tiktoken file is invalid"
+);
+create_exception!(
+    tokenizers,
+    TruncationError,
+    TokenizersError,
+    "The truncation failed, or its parameters are invalid"
+);
+create_exception!(
+    tokenizers,
+    PaddingError,
+    TokenizersError,
+    "The encodings could not be padded, or the padding parameters are invalid"
+);
+create_exception!(
+    tokenizers,
+    PackingError,
+    TokenizersError,
+    "The encodings could not be packed"
+);
+create_exception!(
+    tokenizers,
+    AddedTokensError,
+    TokenizersError,
+    "The added tokens are inconsistent"
+);
+create_exception!(
+    tokenizers,
+    ProcessorError,
+    TokenizersError,
+    "The post-processor could not process the encodings, or its configuration is invalid"
+);
+create_exception!(
+    tokenizers,
+    SerializationError,
+    TokenizersError,
+    "Serializing or deserializing some JSON failed"
+);
+create_exception!(
+    tokenizers,
+    UnsupportedVersionError,
+    SerializationError,
+    "The serialized tokenizer uses an unsupported version of the format"
+);
+
+/// Register the exceptions in the `tokenizers` module
+pub(crate) fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("TokenizersError", py.get_type::<TokenizersError>())?;
+    m.add("ModelError", py.get_type::<ModelError>())?;
+    m.add("ParseError", py.get_type::<ParseError>())?;
+    m.add("TruncationError", py.get_type::<TruncationError>())?;
+    m.add("PaddingError", py.get_type::<PaddingError>())?;
+    m.add("PackingError", py.get_type::<PackingError>())?;
+    m.add("AddedTokensError", py.get_type::<AddedTokensError>())?;
+    m.add("ProcessorError", py.get_type::<ProcessorError>())?;
+    m.add("SerializationError", py.get_type::<SerializationError>())?;
+    m.add(
+        "UnsupportedVersionError",
+        py.get_type::<UnsupportedVersionError>(),
+    )?;
+    Ok(())
+}
+
+fn into_pyerr(error: Error) -> PyErr {
+    let message = error.to_string();
+    match error {
+        Error::Io(error) => error.into(),
+        Error::Model(_) => ModelError::new_err(message),
+        Error::Parse { .. } => ParseError::new_err(message),
+        Error::Truncation(_) => TruncationError::new_err(message),
+        Error::Padding(_) => PaddingError::new_err(message),
+        Error::Packing(_) => PackingError::new_err(message),
+        Error::AddedTokens(_) => AddedTokensError::new_err(message),
+        Error::Processor(_) => ProcessorError::new_err(message),
+        Error::Serialization(_) => SerializationError::new_err(message),
+        Error::UnsupportedVersion(_) => UnsupportedVersionError::new_err(message),
+        // The errors raised by custom components written in python are raised again as is
+        Error::Other(error) => match error.downcast::<PyErr>() {
+            Ok(error) => *error,
+            Err(_) => TokenizersError::new_err(message),
+        },
+        _ => TokenizersError::new_err(message),
+    }
+}
+
 pub struct ToPyResult<T>(pub Result<T>);
 impl<T> From<ToPyResult<T>> for PyResult<T> {
     fn from(v: ToPyResult<T>) -> Self {
-        v.0.map_err(|e| exceptions::PyException::new_err(format!("{}", e)))
+        v.0.map_err(into_pyerr)
     }
 }
 impl<T> ToPyResult<T> {
@@ -47,7 +47,7 @@ extern "C" fn child_after_fork() {
 
 /// Tokenizers Module
 #[pymodule]
-pub fn tokenizers(_py: Python, m: &PyModule) -> PyResult<()> {
+pub fn tokenizers(py: Python, m: &PyModule) -> PyResult<()> {
     let _ = env_logger::try_init_from_env("TOKENIZERS_LOG");
 
     // Register the fork callback
@@ -72,6 +72,7 @@ pub fn tokenizers(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_wrapped(wrap_pymodule!(processors::processors))?;
     m.add_wrapped(wrap_pymodule!(normalizers::normalizers))?;
     m.add_wrapped(wrap_pymodule!(trainers::trainers))?;
+    error::register(py, m)?;
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     Ok(())
 }
@@ -50,6 +50,10 @@ impl Model for PyModel {
         self.model.read().unwrap().tokenize(tokens)
     }
 
+    fn tokenize_ids(&self, tokens: &str) -> tk::Result<Vec<u32>> {
+        self.model.read().unwrap().tokenize_ids(tokens)
+    }
+
     fn token_to_id(&self, token: &str) -> Option<u32> {
         self.model.read().unwrap().token_to_id(token)
     }
@@ -513,7 +513,9 @@ impl tk::tokenizer::Normalizer for CustomNormalizer {
         Python::with_gil(|py| {
             let normalized = PyNormalizedStringRefMut::new(normalized);
             let py_normalized = self.inner.as_ref(py);
-            py_normalized.call_method("normalize", (normalized.get(),), None)?;
+            py_normalized
+                .call_method("normalize", (normalized.get(),), None)
+                .map_err(tk::Error::other)?;
             Ok(())
         })
     }
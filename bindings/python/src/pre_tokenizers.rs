@@ -566,7 +566,9 @@ impl tk::tokenizer::PreTokenizer for CustomPreTokenizer {
         Python::with_gil(|py| {
             let pretok = PyPreTokenizedStringRefMut::new(sentence);
             let py_pretok = self.inner.as_ref(py);
-            py_pretok.call_method("pre_tokenize", (pretok.get(),), None)?;
+            py_pretok
+                .call_method("pre_tokenize", (pretok.get(),), None)
+                .map_err(tk::Error::other)?;
             Ok(())
         })
     }
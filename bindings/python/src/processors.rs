@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::Arc;
 
@@ -10,11 +11,12 @@ use crate::error::ToPyResult;
 use serde::{Deserialize, Serialize};
 use tk::processors::bert::BertProcessing;
 use tk::processors::byte_level::ByteLevel;
+use tk::processors::chat::{ChatProcessing, ChatRole};
 use tk::processors::roberta::RobertaProcessing;
 use tk::processors::sequence::Sequence;
 use tk::processors::template::{SpecialToken, Template};
 use tk::processors::PostProcessorWrapper;
-use tk::{Encoding, PostProcessor};
+use tk::{ChatEncoding, ChatTruncation, Encoding, PostProcessor};
 use tokenizers as tk;
 
 /// Base class for all post-processors
@@ -49,6 +51,7 @@ impl PyPostProcessor {
             PostProcessorWrapper::Template(_) => {
                 Py::new(py, (PyTemplateProcessing {}, base))?.into_py(py)
             }
+            PostProcessorWrapper::Chat(_) => Py::new(py, (PyChatProcessing {}, base))?.into_py(py),
             PostProcessorWrapper::Sequence(_) => Py::new(py, (PySequence {}, base))?.into_py(py),
         })
     }
@@ -59,6 +62,10 @@ impl PostProcessor for PyPostProcessor {
         self.processor.added_tokens(is_pair)
     }
 
+    fn added_tokens_multi(&self, n_sequences: usize) -> tk::Result<usize> {
+        self.processor.added_tokens_multi(n_sequences)
+    }
+
     fn process_encodings(
         &self,
         encodings: Vec<Encoding>,
@@ -67,6 +74,22 @@ impl PostProcessor for PyPostProcessor {
         self.processor
             .process_encodings(encodings, add_special_tokens)
     }
+
+    fn added_tokens_chat(&self, roles: &[String]) -> tk::Result<usize> {
+        self.processor.added_tokens_chat(roles)
+    }
+
+    fn chat_truncation(&self) -> ChatTruncation {
+        self.processor.chat_truncation()
+    }
+
+    fn process_chat(
+        &self,
+        turns: Vec<(String, Encoding)>,
+        add_special_tokens: bool,
+    ) -> tk::Result<ChatEncoding> {
+        self.processor.process_chat(turns, add_special_tokens)
+    }
 }
 
 #[pymethods]
@@ -337,6 +360,7 @@ impl FromPyObject<'_> for PyTemplate {
 ///     TemplateProcessing(
 ///         single="[CLS] $0 [SEP]",
 ///         pair="[CLS] $A [SEP] $B:1 [SEP]:1",
+///         multi="[CLS] $A [SEP] $*:1 [SEP]:1",
 ///         special_tokens=[("[CLS]", 1), ("[SEP]", 0)],
 ///     )
 ///
@@ -347,6 +371,9 @@ impl FromPyObject<'_> for PyTemplate {
 ///     - Specifying the sequence, with default ``type_id == 0``: ``$A`` or ``$B``
 ///     - Specifying the `type_id` with default ``sequence == A``: ``$0``, ``$1``, ``$2``, ...
 ///     - Specifying both: ``$A:0``, ``$B:1``, ...
+///     - Specifying any other sequence, by index: ``$C``, ``$D``, ... or ``$*`` for all the
+///       sequences not referenced elsewhere in the template, each followed by the pieces
+///       coming after it (until the next sequence)
 ///
 /// The same construct is used for special tokens: ``<identifier>(:<type_id>)?``.
 ///
@@ -362,6 +389,9 @@ impl FromPyObject<'_> for PyTemplate {
 ///     pair (:obj:`Template`):
 ///         The template used when both sequences are specified
 ///
+///     multi (:obj:`Template`, `optional`):
+///         The template used when more than two sequences are specified
+///
 ///     special_tokens (:obj:`Tokens`):
 ///         The list of special tokens used in each sequences
 ///
@@ -385,11 +415,12 @@ pub struct PyTemplateProcessing {}
 #[pymethods]
 impl PyTemplateProcessing {
     #[new]
-    #[pyo3(signature = (single = None, pair = None, special_tokens = None), text_signature = "(self, single, pair, special_tokens)")]
+    #[pyo3(signature = (single = None, pair = None, special_tokens = None, multi = None), text_signature = "(self, single, pair, special_tokens, multi=None)")]
     fn new(
         single: Option<PyTemplate>,
         pair: Option<PyTemplate>,
         special_tokens: Option<Vec<PySpecialToken>>,
+        multi: Option<PyTemplate>,
     ) -> PyResult<(Self, PyPostProcessor)> {
         let mut builder = tk::processors::template::TemplateProcessing::builder();
 
@@ -399,6 +430,9 @@ impl PyTemplateProcessing {
         if let Some(seq) = pair {
             builder.pair(seq.into());
         }
+        if let Some(seq) = multi {
+            builder.multi(seq.into());
+        }
         if let Some(sp) = special_tokens {
             builder.special_tokens(sp);
         }
@@ -413,6 +447,109 @@ impl PyTemplateProcessing {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct PyChatRole(ChatRole);
+
+impl FromPyObject<'_> for PyChatRole {
+    fn extract(ob: &PyAny) -> PyResult<Self> {
+        let d = ob.downcast::<PyDict>()?;
+        let mut role = ChatRole::default();
+        if let Some(prefix) = d.get_item("prefix") {
+            role.prefix = prefix.extract()?;
+        }
+        if let Some(suffix) = d.get_item("suffix") {
+            role.suffix = suffix.extract()?;
+        }
+        if let Some(type_id) = d.get_item("type_id") {
+            role.type_id = type_id.extract()?;
+        }
+        if let Some(assistant) = d.get_item("assistant") {
+            role.assistant = assistant.extract()?;
+        }
+        Ok(Self(role))
+    }
+}
+
+/// Renders the turns of a conversation, wrapping each of them in the special tokens of
+/// its role, like :obj:`<|user|> Hello <|end|>`.
+///
+/// The conversations are encoded with :meth:`~tokenizers.Tokenizer.encode_chat`, which
+/// also gives the mask of the tokens belonging to the assistant turns::
+///
+///     ChatProcessing(
+///         roles={
+///             "user": {"prefix": ["<|user|>"], "suffix": ["<|end|>"]},
+///             "assistant": {
+///                 "prefix": ["<|assistant|>"],
+///                 "suffix": ["<|end|>"],
+///                 "type_id": 1,
+///                 "assistant": True,
+///             },
+///         },
+///         special_tokens=[("<|user|>", 1), ("<|assistant|>", 2), ("<|end|>", 3)],
+///     )
+///
+/// Other inputs are processed as if there were no post-processor.
+///
+/// Args:
+///     roles (:obj:`Dict[str, Dict]`):
+///         The roles, by name. Each of them can specify the :obj:`prefix` and :obj:`suffix`
+///         special tokens added around its turns, by their id in the :obj:`special_tokens`,
+///         the :obj:`type_id` of its tokens, and whether they are marked in the
+///         :obj:`assistant` mask (the prefix never is)
+///
+///     special_tokens (:obj:`Tokens`):
+///         The list of special tokens used by the roles, like for
+///         :class:`~tokenizers.processors.TemplateProcessing`
+///
+///     truncation (:obj:`str`, defaults to :obj:`longest_first`):
+///         How the conversations that are too long get truncated. :obj:`longest_first`
+///         removes the tokens from the longest turns first, which usually cuts the answers of
+///         the assistant, while :obj:`oldest_first` drops whole turns, the oldest first
+#[pyclass(extends=PyPostProcessor, module = "tokenizers.processors", name = "ChatProcessing")]
+pub struct PyChatProcessing {}
+#[pymethods]
+impl PyChatProcessing {
+    #[new]
+    #[pyo3(signature = (roles, special_tokens = None, truncation = "longest_first"), text_signature = "(self, roles, special_tokens=None, truncation=\"longest_first\")")]
+    fn new(
+        roles: HashMap<String, PyChatRole>,
+        special_tokens: Option<Vec<PySpecialToken>>,
+        truncation: &str,
+    ) -> PyResult<(Self, PyPostProcessor)> {
+        let truncation = match truncation {
+            "longest_first" => ChatTruncation::LongestFirst,
+            "oldest_first" => ChatTruncation::OldestFirst,
+            _ => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown `truncation`: `{}`. Use one of `longest_first` or `oldest_first`",
+                    truncation
+                )))
+            }
+        };
+        let mut builder = ChatProcessing::builder();
+        builder.truncation(truncation);
+        for (name, role) in roles {
+            builder.role(name, role.0);
+        }
+        if let Some(sp) = special_tokens {
+            builder.special_tokens(sp);
+        }
+        let processor = builder
+            .build()
+            .map_err(|e| exceptions::PyValueError::new_err(e.to_string()))?;
+
+        Ok((
+            PyChatProcessing {},
+            PyPostProcessor::new(Arc::new(processor.into())),
+        ))
+    }
+
+    fn __getnewargs__<'p>(&self, py: Python<'p>) -> &'p PyTuple {
+        PyTuple::new(py, [PyDict::new(py)])
+    }
+}
+
 /// Sequence Processor
 ///
 /// Args:
@@ -448,6 +585,7 @@ impl PySequence {
 pub fn processors(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyPostProcessor>()?;
     m.add_class::<PyBertProcessing>()?;
+    m.add_class::<PyChatProcessing>()?;
     m.add_class::<PyRobertaProcessing>()?;
     m.add_class::<PyByteLevel>()?;
     m.add_class::<PyTemplateProcessing>()?;
@@ -461,6 +599,7 @@ mod test {
 
     use pyo3::prelude::*;
     use tk::processors::bert::BertProcessing;
+    use tk::processors::chat::{ChatProcessing, ChatRole};
     use tk::processors::PostProcessorWrapper;
 
     use crate::processors::PyPostProcessor;
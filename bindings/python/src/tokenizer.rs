@@ -1,4 +1,4 @@
-use std::collections::{hash_map::DefaultHasher, HashMap};
+use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 
 use numpy::{npyffi, PyArray1};
@@ -11,7 +11,7 @@ use pyo3::AsPyPointer;
 use tk::models::bpe::BPE;
 use tk::tokenizer::{
     Model, PaddingDirection, PaddingParams, PaddingStrategy, PostProcessor, TokenizerImpl,
-    TruncationDirection, TruncationParams, TruncationStrategy,
+    TruncationBoundary, TruncationDirection, TruncationParams, TruncationStrategy,
 };
 use tk::utils::iter::ResultShunt;
 use tokenizers as tk;
@@ -413,9 +413,17 @@ impl<'s> FromPyObject<'s> for TextEncodeInput<'s> {
                 let second = arr[1].extract::<TextInputSequence>()?;
                 return Ok(Self((first, second).into()));
             }
+            if arr.len() > 2 {
+                let sequences = arr
+                    .iter()
+                    .map(|s| Ok(s.extract::<TextInputSequence>()?.into()))
+                    .collect::<PyResult<Vec<_>>>()?;
+                return Ok(Self(tk::EncodeInput::Multi(sequences)));
+            }
         }
         Err(exceptions::PyTypeError::new_err(
-            "TextEncodeInput must be Union[TextInputSequence, Tuple[InputSequence, InputSequence]]",
+            "TextEncodeInput must be Union[TextInputSequence, Tuple[InputSequence, InputSequence], \
+            List[InputSequence]]",
         ))
     }
 }
@@ -440,10 +448,18 @@ impl<'s> FromPyObject<'s> for PreTokenizedEncodeInput<'s> {
                 let second = arr[1].extract::<PreTokenizedInputSequence>()?;
                 return Ok(Self((first, second).into()));
             }
+            if arr.len() > 2 {
+                let sequences = arr
+                    .iter()
+                    .map(|s| Ok(s.extract::<PreTokenizedInputSequence>()?.into()))
+                    .collect::<PyResult<Vec<_>>>()?;
+                return Ok(Self(tk::EncodeInput::Multi(sequences)));
+            }
         }
         Err(exceptions::PyTypeError::new_err(
             "PreTokenizedEncodeInput must be Union[PreTokenizedInputSequence, \
-            Tuple[PreTokenizedInputSequence, PreTokenizedInputSequence]]",
+            Tuple[PreTokenizedInputSequence, PreTokenizedInputSequence], \
+            List[PreTokenizedInputSequence]]",
         ))
     }
 }
@@ -453,6 +469,54 @@ impl<'s> From<PreTokenizedEncodeInput<'s>> for tk::tokenizer::EncodeInput<'s> {
     }
 }
 
+fn special_tokens_matching(allowed: Option<HashSet<String>>) -> tk::SpecialTokensMatching {
+    match allowed {
+        None => tk::SpecialTokensMatching::All,
+        Some(allowed) if allowed.is_empty() => tk::SpecialTokensMatching::None,
+        Some(allowed) => tk::SpecialTokensMatching::Only(allowed),
+    }
+}
+
+fn extract_encode_input<'s>(
+    sequence: &'s PyAny,
+    pair: Option<&'s PyAny>,
+    is_pretokenized: bool,
+) -> PyResult<tk::EncodeInput<'s>> {
+    let sequence: tk::InputSequence = if is_pretokenized {
+        sequence.extract::<PreTokenizedInputSequence>()?.into()
+    } else {
+        sequence.extract::<TextInputSequence>()?.into()
+    };
+    Ok(match pair {
+        Some(pair) => {
+            let pair: tk::InputSequence = if is_pretokenized {
+                pair.extract::<PreTokenizedInputSequence>()?.into()
+            } else {
+                pair.extract::<TextInputSequence>()?.into()
+            };
+            tk::EncodeInput::Dual(sequence, pair)
+        }
+        None => tk::EncodeInput::Single(sequence),
+    })
+}
+
+fn extract_encode_batch_input(
+    input: Vec<&PyAny>,
+    is_pretokenized: bool,
+) -> PyResult<Vec<tk::EncodeInput>> {
+    input
+        .into_iter()
+        .map(|o| {
+            let input: tk::EncodeInput = if is_pretokenized {
+                o.extract::<PreTokenizedEncodeInput>()?.into()
+            } else {
+                o.extract::<TextEncodeInput>()?.into()
+            };
+            Ok(input)
+        })
+        .collect()
+}
+
 type Tokenizer = TokenizerImpl<PyModel, PyNormalizer, PyPreTokenizer, PyPostProcessor, PyDecoder>;
 
 /// A :obj:`Tokenizer` works as a pipeline. It processes some raw text as input
@@ -708,10 +772,17 @@ impl PyTokenizer {
     ///         ``only_second``.
     ///
     ///     direction (:obj:`str`, defaults to :obj:`right`):
-    ///         Truncate direction
+    ///         Truncate direction. Can be one of ``left``, ``right`` or ``middle`` to keep
+    ///         the head and the tail.
+    ///
+    ///     head_ratio (:obj:`float`, defaults to :obj:`0.5`):
+    ///         The proportion of the kept tokens taken from the head, with ``direction="middle"``
+    ///
+    ///     boundary (:obj:`str`, defaults to :obj:`token`):
+    ///         Where the sequences can be cut. Can be one of ``token``, ``word`` or ``sentence``
     #[pyo3(signature = (max_length, **kwargs))]
     #[pyo3(
-        text_signature = "(self, max_length, stride=0, strategy='longest_first', direction='right')"
+        text_signature = "(self, max_length, stride=0, strategy='longest_first', direction='right', head_ratio=0.5, boundary='token')"
     )]
     fn enable_truncation(&mut self, max_length: usize, kwargs: Option<&PyDict>) -> PyResult<()> {
         let mut params = TruncationParams {
@@ -743,9 +814,25 @@ impl PyTokenizer {
                         params.direction = match value {
                             "left" => Ok(TruncationDirection::Left),
                             "right" => Ok(TruncationDirection::Right),
+                            "middle" => Ok(TruncationDirection::Middle),
                             _ => Err(PyError(format!(
                                 "Unknown `direction`: `{}`. Use \
-                                 one of `left` or `right`.",
+                                 one of `left`, `right` or `middle`.",
+                                value
+                            ))
+                            .into_pyerr::<exceptions::PyValueError>()),
+                        }?
+                    }
+                    "head_ratio" => params.head_ratio = value.extract()?,
+                    "boundary" => {
+                        let value: &str = value.extract()?;
+                        params.boundary = match value {
+                            "token" => Ok(TruncationBoundary::Token),
+                            "word" => Ok(TruncationBoundary::Word),
+                            "sentence" => Ok(TruncationBoundary::Sentence),
+                            _ => Err(PyError(format!(
+                                "Unknown `boundary`: `{}`. Use \
+                                 one of `token`, `word` or `sentence`.",
                                 value
                             ))
                             .into_pyerr::<exceptions::PyValueError>()),
@@ -786,6 +873,8 @@ impl PyTokenizer {
             dict.set_item("stride", params.stride)?;
             dict.set_item("strategy", params.strategy.as_ref())?;
             dict.set_item("direction", params.direction.as_ref())?;
+            dict.set_item("head_ratio", params.head_ratio)?;
+            dict.set_item("boundary", params.boundary.as_ref())?;
 
             Ok(Some(dict))
         })
@@ -814,9 +903,13 @@ impl PyTokenizer {
     ///     length (:obj:`int`, `optional`):
     ///         If specified, the length at which to pad. If not specified we pad using the size of
     ///         the longest sequence in a batch.
+    ///
+    ///     buckets (:obj:`List[int]`, `optional`):
+    ///         If specified, each sequence is padded to the smallest of these lengths that fits it.
+    ///         The sequences longer than all the buckets are not padded.
     #[pyo3(signature = (**kwargs))]
     #[pyo3(
-        text_signature = "(self, direction='right', pad_id=0, pad_type_id=0, pad_token='[PAD]', length=None, pad_to_multiple_of=None)"
+        text_signature = "(self, direction='right', pad_id=0, pad_type_id=0, pad_token='[PAD]', length=None, pad_to_multiple_of=None, buckets=None)"
     )]
     fn enable_padding(&mut self, kwargs: Option<&PyDict>) -> PyResult<()> {
         let mut params = PaddingParams::default();
@@ -864,6 +957,11 @@ impl PyTokenizer {
                             params.strategy = PaddingStrategy::BatchLongest;
                         }
                     }
+                    "buckets" => {
+                        if let Some(buckets) = value.extract()? {
+                            params.strategy = PaddingStrategy::Buckets(buckets);
+                        }
+                    }
                     _ => println!("Ignored unknown kwarg option {}", key),
                 }
             }
@@ -895,8 +993,15 @@ impl PyTokenizer {
             dict.set_item(
                 "length",
                 match params.strategy {
-                    tk::PaddingStrategy::BatchLongest => None,
                     tk::PaddingStrategy::Fixed(size) => Some(size),
+                    _ => None,
+                },
+            )?;
+            dict.set_item(
+                "buckets",
+                match &params.strategy {
+                    tk::PaddingStrategy::Buckets(buckets) => Some(buckets.clone()),
+                    _ => None,
                 },
             )?;
             dict.set_item("pad_to_multiple_of", params.pad_to_multiple_of)?;
@@ -940,12 +1045,18 @@ impl PyTokenizer {
     ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
     ///         Whether to add the special tokens
     ///
+    ///     allowed_special_tokens (:obj:`List[str]`, `optional`):
+    ///         The only special tokens that can be matched in the sequence and its pair, the
+    ///         others being tokenized as plain text. Use an empty list when encoding some
+    ///         untrusted text, so that it can't produce any special token. By default, all the
+    ///         special tokens are matched.
+    ///
     /// Returns:
     ///     :class:`~tokenizers.Encoding`: The encoded result
     ///
-    #[pyo3(signature = (sequence, pair = None, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(signature = (sequence, pair = None, is_pretokenized = false, add_special_tokens = true, allowed_special_tokens = None))]
     #[pyo3(
-        text_signature = "(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True)"
+        text_signature = "(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True, allowed_special_tokens=None)"
     )]
     fn encode(
         &self,
@@ -953,27 +1064,17 @@ impl PyTokenizer {
         pair: Option<&PyAny>,
         is_pretokenized: bool,
         add_special_tokens: bool,
+        allowed_special_tokens: Option<HashSet<String>>,
     ) -> PyResult<PyEncoding> {
-        let sequence: tk::InputSequence = if is_pretokenized {
-            sequence.extract::<PreTokenizedInputSequence>()?.into()
-        } else {
-            sequence.extract::<TextInputSequence>()?.into()
-        };
-        let input = match pair {
-            Some(pair) => {
-                let pair: tk::InputSequence = if is_pretokenized {
-                    pair.extract::<PreTokenizedInputSequence>()?.into()
-                } else {
-                    pair.extract::<TextInputSequence>()?.into()
-                };
-                tk::EncodeInput::Dual(sequence, pair)
-            }
-            None => tk::EncodeInput::Single(sequence),
-        };
+        let input = extract_encode_input(sequence, pair, is_pretokenized)?;
 
         ToPyResult(
             self.tokenizer
-                .encode_char_offsets(input, add_special_tokens)
+                .encode_char_offsets_with_matching(
+                    input,
+                    add_special_tokens,
+                    &special_tokens_matching(allowed_special_tokens),
+                )
                 .map(|e| e.into()),
         )
         .into()
@@ -1007,39 +1108,258 @@ impl PyTokenizer {
     ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
     ///         Whether to add the special tokens
     ///
+    ///     allowed_special_tokens (:obj:`List[str]`, `optional`):
+    ///         The only special tokens that can be matched in the inputs, the same for the
+    ///         whole batch. The other special tokens are tokenized as plain text, and an empty
+    ///         list disables the matching of all of them. By default, all the special tokens
+    ///         are matched.
+    ///
     /// Returns:
     ///     A :obj:`List` of :class:`~tokenizers.Encoding`: The encoded batch
     ///
-    #[pyo3(signature = (input, is_pretokenized = false, add_special_tokens = true))]
-    #[pyo3(text_signature = "(self, input, is_pretokenized=False, add_special_tokens=True)")]
+    #[pyo3(signature = (input, is_pretokenized = false, add_special_tokens = true, allowed_special_tokens = None))]
+    #[pyo3(
+        text_signature = "(self, input, is_pretokenized=False, add_special_tokens=True, allowed_special_tokens=None)"
+    )]
     fn encode_batch(
         &self,
         py: Python<'_>,
         input: Vec<&PyAny>,
         is_pretokenized: bool,
         add_special_tokens: bool,
+        allowed_special_tokens: Option<HashSet<String>>,
     ) -> PyResult<Vec<PyEncoding>> {
-        let input: Vec<tk::EncodeInput> = input
-            .into_iter()
-            .map(|o| {
-                let input: tk::EncodeInput = if is_pretokenized {
-                    o.extract::<PreTokenizedEncodeInput>()?.into()
-                } else {
-                    o.extract::<TextEncodeInput>()?.into()
-                };
-                Ok(input)
-            })
-            .collect::<PyResult<Vec<tk::EncodeInput>>>()?;
+        let input = extract_encode_batch_input(input, is_pretokenized)?;
+        let matching = special_tokens_matching(allowed_special_tokens);
         py.allow_threads(|| {
             ToPyResult(
                 self.tokenizer
-                    .encode_batch_char_offsets(input, add_special_tokens)
+                    .encode_batch_char_offsets_with_matching(input, add_special_tokens, &matching)
                     .map(|encodings| encodings.into_iter().map(|e| e.into()).collect()),
             )
             .into()
         })
     }
 
+    /// Encode the given sequence and pair like :meth:`~tokenizers.Tokenizer.encode`, keeping
+    /// track of what each stage of the pipeline does. This is useful to understand why some
+    /// input gets encoded the way it does.
+    ///
+    /// For each input sequence, the trace contains the splits around the added tokens, the
+    /// normalized string with its alignments, the splits of the pre-tokenizer, and the tokens
+    /// of the model. It also contains the tokens inserted by the post-processor, and the final
+    /// encoding. All the offsets are byte offsets.
+    ///
+    /// Args:
+    ///     sequence (:obj:`~tokenizers.InputSequence`):
+    ///         The main input sequence we want to encode
+    ///
+    ///     pair (:obj:`~tokenizers.InputSequence`, `optional`):
+    ///         An optional input sequence. The expected format is the same that for ``sequence``.
+    ///
+    ///     is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+    ///         Whether the input is already pre-tokenized
+    ///
+    ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether to add the special tokens
+    ///
+    /// Returns:
+    ///     :obj:`str`: The trace, as a JSON string
+    #[pyo3(signature = (sequence, pair = None, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(
+        text_signature = "(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True)"
+    )]
+    fn encode_with_trace(
+        &self,
+        sequence: &PyAny,
+        pair: Option<&PyAny>,
+        is_pretokenized: bool,
+        add_special_tokens: bool,
+    ) -> PyResult<String> {
+        let input = extract_encode_input(sequence, pair, is_pretokenized)?;
+        let trace = self.tokenizer.encode_with_trace(input, add_special_tokens);
+        let trace = ToPyResult(trace).into_py()?;
+        serde_json::to_string(&trace).map_err(|e| {
+            exceptions::PyException::new_err(format!("Error while serializing the trace: {}", e))
+        })
+    }
+
+    /// Encode a conversation, rendered by a post-processor supporting them, like
+    /// :class:`~tokenizers.processors.ChatProcessing`
+    ///
+    /// Example:
+    ///     Here is an example of the expected input::
+    ///
+    ///         encode_chat([
+    ///             ("user", "What is the capital of France?"),
+    ///             ("assistant", "Paris."),
+    ///         ])
+    ///
+    /// Args:
+    ///     turns (A :obj:`List` of :obj:`Tuple[str, InputSequence]`):
+    ///         The turns of the conversation, each with the role of its author
+    ///
+    ///     is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+    ///         Whether the turns are already pre-tokenized
+    ///
+    ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether to add the special tokens of the roles
+    ///
+    /// Returns:
+    ///     A :obj:`Tuple` with the :class:`~tokenizers.Encoding` of the conversation, and the
+    ///     :obj:`List[int]` mask of its tokens belonging to the assistant turns
+    #[pyo3(signature = (turns, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(text_signature = "(self, turns, is_pretokenized=False, add_special_tokens=True)")]
+    fn encode_chat(
+        &self,
+        turns: Vec<(String, &PyAny)>,
+        is_pretokenized: bool,
+        add_special_tokens: bool,
+    ) -> PyResult<(PyEncoding, Vec<u32>)> {
+        let turns = turns
+            .into_iter()
+            .map(|(role, sequence)| {
+                let sequence: tk::InputSequence = if is_pretokenized {
+                    sequence.extract::<PreTokenizedInputSequence>()?.into()
+                } else {
+                    sequence.extract::<TextInputSequence>()?.into()
+                };
+                Ok((role, sequence))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        ToPyResult(
+            self.tokenizer
+                .encode_chat_char_offsets(turns, add_special_tokens)
+                .map(|chat| (chat.encoding.into(), chat.assistant_mask)),
+        )
+        .into()
+    }
+
+    /// Encode the given sequence and pair into their ids only. This gives the same ids as
+    /// :meth:`~tokenizers.Tokenizer.encode`, but is faster as it does not build the tokens
+    /// and offsets of a full :class:`~tokenizers.Encoding`.
+    ///
+    /// Args:
+    ///     sequence (:obj:`~tokenizers.InputSequence`):
+    ///         The main input sequence we want to encode
+    ///
+    ///     pair (:obj:`~tokenizers.InputSequence`, `optional`):
+    ///         An optional input sequence. The expected format is the same that for ``sequence``.
+    ///
+    ///     is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+    ///         Whether the input is already pre-tokenized
+    ///
+    ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether to add the special tokens
+    ///
+    /// Returns:
+    ///     :obj:`List[int]`: The ids
+    #[pyo3(signature = (sequence, pair = None, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(
+        text_signature = "(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True)"
+    )]
+    fn encode_ids(
+        &self,
+        sequence: &PyAny,
+        pair: Option<&PyAny>,
+        is_pretokenized: bool,
+        add_special_tokens: bool,
+    ) -> PyResult<Vec<u32>> {
+        let input = extract_encode_input(sequence, pair, is_pretokenized)?;
+        ToPyResult(self.tokenizer.encode_ids(input, add_special_tokens)).into()
+    }
+
+    /// Encode the given batch of inputs into their ids only, in parallel
+    ///
+    /// Args:
+    ///     input (A :obj:`List`/:obj:`Tuple` of :obj:`~tokenizers.EncodeInput`):
+    ///         A list of single sequences or pair sequences to encode
+    ///
+    ///     is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+    ///         Whether the input is already pre-tokenized
+    ///
+    ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether to add the special tokens
+    ///
+    /// Returns:
+    ///     :obj:`List[List[int]]`: The ids of each input
+    #[pyo3(signature = (input, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(text_signature = "(self, input, is_pretokenized=False, add_special_tokens=True)")]
+    fn encode_ids_batch(
+        &self,
+        py: Python<'_>,
+        input: Vec<&PyAny>,
+        is_pretokenized: bool,
+        add_special_tokens: bool,
+    ) -> PyResult<Vec<Vec<u32>>> {
+        let input = extract_encode_batch_input(input, is_pretokenized)?;
+        py.allow_threads(|| {
+            ToPyResult(self.tokenizer.encode_ids_batch(input, add_special_tokens)).into()
+        })
+    }
+
+    /// Count the tokens the given sequence and pair are encoded into, padding excluded
+    ///
+    /// Args:
+    ///     sequence (:obj:`~tokenizers.InputSequence`):
+    ///         The main input sequence we want to encode
+    ///
+    ///     pair (:obj:`~tokenizers.InputSequence`, `optional`):
+    ///         An optional input sequence. The expected format is the same that for ``sequence``.
+    ///
+    ///     is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+    ///         Whether the input is already pre-tokenized
+    ///
+    ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether to add the special tokens
+    ///
+    /// Returns:
+    ///     :obj:`int`: The number of tokens
+    #[pyo3(signature = (sequence, pair = None, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(
+        text_signature = "(self, sequence, pair=None, is_pretokenized=False, add_special_tokens=True)"
+    )]
+    fn count_tokens(
+        &self,
+        sequence: &PyAny,
+        pair: Option<&PyAny>,
+        is_pretokenized: bool,
+        add_special_tokens: bool,
+    ) -> PyResult<usize> {
+        let input = extract_encode_input(sequence, pair, is_pretokenized)?;
+        ToPyResult(self.tokenizer.count_tokens(input, add_special_tokens)).into()
+    }
+
+    /// Count in parallel the tokens each input of the batch is encoded into, padding excluded
+    ///
+    /// Args:
+    ///     input (A :obj:`List`/:obj:`Tuple` of :obj:`~tokenizers.EncodeInput`):
+    ///         A list of single sequences or pair sequences to encode
+    ///
+    ///     is_pretokenized (:obj:`bool`, defaults to :obj:`False`):
+    ///         Whether the input is already pre-tokenized
+    ///
+    ///     add_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether to add the special tokens
+    ///
+    /// Returns:
+    ///     :obj:`List[int]`: The number of tokens of each input
+    #[pyo3(signature = (input, is_pretokenized = false, add_special_tokens = true))]
+    #[pyo3(text_signature = "(self, input, is_pretokenized=False, add_special_tokens=True)")]
+    fn count_tokens_batch(
+        &self,
+        py: Python<'_>,
+        input: Vec<&PyAny>,
+        is_pretokenized: bool,
+        add_special_tokens: bool,
+    ) -> PyResult<Vec<usize>> {
+        let input = extract_encode_batch_input(input, is_pretokenized)?;
+        py.allow_threads(|| {
+            ToPyResult(self.tokenizer.count_tokens_batch(input, add_special_tokens)).into()
+        })
+    }
+
     /// Decode the given list of ids back to a string
     ///
     /// This is used to decode anything coming back from a Language Model
@@ -1059,6 +1379,48 @@ impl PyTokenizer {
         ToPyResult(self.tokenizer.decode(&ids, skip_special_tokens)).into()
     }
 
+    /// Decode the given list of ids back to a string, along with the span of this string
+    /// produced by each id
+    ///
+    /// When several ids produce a single character together (like the bytes of a character
+    /// with :class:`~tokenizers.decoders.ByteLevel`), they all get the span of this character.
+    /// The ids that don't produce anything, like the skipped special tokens, get empty spans.
+    ///
+    /// Args:
+    ///     ids (A :obj:`List/Tuple` of :obj:`int`):
+    ///         The list of ids that we want to decode
+    ///
+    ///     skip_special_tokens (:obj:`bool`, defaults to :obj:`True`):
+    ///         Whether the special tokens should be removed from the decoded string
+    ///
+    /// Returns:
+    ///     :obj:`Tuple[str, List[Tuple[int, int]]]`: The decoded string, and the character
+    ///     offsets of each id in it
+    #[pyo3(signature = (ids, skip_special_tokens = true))]
+    #[pyo3(text_signature = "(self, ids, skip_special_tokens=True)")]
+    fn decode_with_offsets(
+        &self,
+        ids: Vec<u32>,
+        skip_special_tokens: bool,
+    ) -> PyResult<(String, Vec<(usize, usize)>)> {
+        let decoded = self
+            .tokenizer
+            .decode_with_offsets(&ids, skip_special_tokens);
+        let (decoded, offsets) = ToPyResult(decoded).into_py()?;
+
+        // Python works with chars, so we convert the byte offsets
+        let mut chars = vec![0; decoded.len() + 1];
+        for (i, (b, c)) in decoded.char_indices().enumerate() {
+            chars[b..b + c.len_utf8()].fill(i);
+        }
+        chars[decoded.len()] = decoded.chars().count();
+        let offsets = offsets
+            .into_iter()
+            .map(|(start, end)| (chars[start], chars[end]))
+            .collect();
+        Ok((decoded, offsets))
+    }
+
     /// Decode a batch of ids back to their corresponding string
     ///
     /// Args:
@@ -1264,10 +1626,12 @@ impl PyTokenizer {
 
         py.allow_threads(|| {
             ResultShunt::process(buffered_iter, |iter| {
-                self.tokenizer
-                    .train(&mut trainer, MaybeSizedIterator::new(iter, length))
-                    .map(|_| {})
-                    .map_err(|e| exceptions::PyException::new_err(e.to_string()))
+                ToPyResult(
+                    self.tokenizer
+                        .train(&mut trainer, MaybeSizedIterator::new(iter, length))
+                        .map(|_| {}),
+                )
+                .into_py()
             })?
         })
     }
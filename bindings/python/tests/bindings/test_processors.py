@@ -3,12 +3,13 @@ import pickle
 
 import pytest
 
-from tokenizers import Tokenizer
+from tokenizers import ProcessorError, Tokenizer
 from tokenizers.models import BPE
 from tokenizers.pre_tokenizers import ByteLevel as ByteLevelPreTokenizer
 from tokenizers.processors import (
     BertProcessing,
     ByteLevel,
+    ChatProcessing,
     PostProcessor,
     RobertaProcessing,
     Sequence,
@@ -182,6 +183,63 @@ class TestTemplateProcessing:
         template = tokenizer.encode("my name is john", "pair")
         assert original.ids == template.ids
 
+    def test_multi(self):
+        tokenizer = Tokenizer(BPE())
+        tokenizer.add_special_tokens(["[SEP]", "[CLS]"])
+        tokenizer.add_tokens(["my", "name", "is", "john", "pair"])
+        tokenizer.post_processor = TemplateProcessing(
+            single="[CLS] $0 [SEP]",
+            pair="[CLS] $A [SEP] $B:1 [SEP]:1",
+            multi="[CLS] $A [SEP] $*:1 [SEP]:1",
+            special_tokens=[("[CLS]", 1), ("[SEP]", 0)],
+        )
+
+        [output] = tokenizer.encode_batch([["my name", "is", "john"]])
+        assert output.tokens == ["[CLS]", "my", "name", "[SEP]", "is", "[SEP]", "john", "[SEP]"]
+        assert output.type_ids == [0, 0, 0, 0, 1, 1, 1, 1]
+        assert tokenizer.post_processor.num_special_tokens_to_add(True) == 3
+
+
+class TestChatProcessing:
+    def get_chat(self):
+        return ChatProcessing(
+            roles={
+                "user": {"prefix": ["<user>"], "suffix": ["<end>"]},
+                "assistant": {
+                    "prefix": ["<assistant>"],
+                    "suffix": ["<end>"],
+                    "type_id": 1,
+                    "assistant": True,
+                },
+            },
+            special_tokens=[("<user>", 0), ("<assistant>", 1), ("<end>", 2)],
+        )
+
+    def test_instantiate(self):
+        chat = self.get_chat()
+        assert isinstance(chat, PostProcessor)
+        assert isinstance(chat, ChatProcessing)
+        assert isinstance(pickle.loads(pickle.dumps(chat)), ChatProcessing)
+
+        with pytest.raises(Exception, match="Missing SpecialToken\\(s\\) with id\\(s\\)"):
+            ChatProcessing(roles={"user": {"prefix": ["<user>"]}})
+        with pytest.raises(ValueError, match="Unknown `truncation`"):
+            ChatProcessing(roles={}, truncation="newest_first")
+
+    def test_encode_chat(self):
+        tokenizer = Tokenizer(BPE())
+        tokenizer.add_special_tokens(["<user>", "<assistant>", "<end>"])
+        tokenizer.add_tokens(["my", "name", "is", "john"])
+        tokenizer.post_processor = self.get_chat()
+
+        encoding, mask = tokenizer.encode_chat([("user", "my name"), ("assistant", "john")])
+        assert encoding.ids == [0, 3, 4, 2, 1, 6, 2]
+        assert encoding.type_ids == [0, 0, 0, 0, 1, 1, 1]
+        assert mask == [0, 0, 0, 0, 0, 1, 1]
+
+        with pytest.raises(ProcessorError, match="unknown role `system`"):
+            tokenizer.encode_chat([("system", "my name")])
+
 
 class TestSequenceProcessing:
     def test_sequence_processing(self):
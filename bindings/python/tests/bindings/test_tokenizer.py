@@ -1,13 +1,22 @@
+import json
 import pickle
 
 import numpy as np
 import pytest
 
-from tokenizers import AddedToken, Encoding, Tokenizer
+from tokenizers import (
+    AddedToken,
+    Encoding,
+    SerializationError,
+    Tokenizer,
+    TokenizersError,
+    UnsupportedVersionError,
+    decoders,
+)
 from tokenizers.implementations import BertWordPieceTokenizer
-from tokenizers.models import BPE, Model, WordPiece, Unigram
+from tokenizers.models import BPE, Model, WordLevel, WordPiece, Unigram
 from tokenizers.normalizers import Lowercase
-from tokenizers.pre_tokenizers import ByteLevel
+from tokenizers.pre_tokenizers import ByteLevel, Whitespace
 from tokenizers.processors import BertProcessing, RobertaProcessing
 
 from ..utils import bert_files, data_dir, multiprocessing_with_parallelism, roberta_files
@@ -301,6 +310,54 @@ class TestTokenizer:
         output_without_specials = tokenizer.encode("My name is John", add_special_tokens=False)
         assert output_without_specials.tokens == ["ĠMy", "Ġname", "Ġis", "ĠJohn"]
 
+    def test_encode_allowed_special_tokens(self):
+        vocab = {"[CLS]": 0, "[SEP]": 1, "[": 2, "CLS": 3, "SEP": 4, "]": 5, "hi": 6, "[UNK]": 7}
+        tokenizer = Tokenizer(WordLevel(vocab, unk_token="[UNK]"))
+        tokenizer.pre_tokenizer = Whitespace()
+        tokenizer.add_special_tokens(["[CLS]", "[SEP]"])
+
+        assert tokenizer.encode("[CLS] hi [SEP]").ids == [0, 6, 1]
+        assert tokenizer.encode("[CLS] hi [SEP]", allowed_special_tokens=[]).ids == [2, 3, 5, 6, 2, 4, 5]
+        assert tokenizer.encode("[CLS] hi [SEP]", allowed_special_tokens=["[SEP]"]).ids == [2, 3, 5, 6, 1]
+
+        output = tokenizer.encode_batch(["[CLS] hi", "hi [SEP]"], allowed_special_tokens=[])
+        assert [o.ids for o in output] == [[2, 3, 5, 6], [6, 2, 4, 5]]
+
+    def test_encode_ids(self, roberta_files):
+        tokenizer = Tokenizer(BPE(roberta_files["vocab"], roberta_files["merges"]))
+        tokenizer.pre_tokenizer = ByteLevel()
+        tokenizer.post_processor = RobertaProcessing(("</s>", 2), ("<s>", 0))
+        tokenizer.enable_padding(length=10)
+
+        output = tokenizer.encode("My name is John", "pair")
+        assert tokenizer.encode_ids("My name is John", "pair") == output.ids
+        assert tokenizer.count_tokens("My name is John", "pair") == sum(output.attention_mask)
+
+        output = tokenizer.encode_batch(["My name is John", "pair"])
+        assert tokenizer.encode_ids_batch(["My name is John", "pair"]) == [o.ids for o in output]
+        assert tokenizer.count_tokens_batch(["My name is John", "pair"]) == [sum(o.attention_mask) for o in output]
+
+    def test_errors(self):
+        tokenizer = Tokenizer(BPE())
+        data = json.loads(tokenizer.to_str())
+
+        data["version"] = "2.0"
+        with pytest.raises(UnsupportedVersionError, match="Unknown tokenizer version '2.0'"):
+            Tokenizer.from_str(json.dumps(data))
+
+        with pytest.raises(SerializationError):
+            Tokenizer.from_str("{")
+
+        # The errors of the custom components are raised again as is
+        class Failing:
+            def decode_chain(self, tokens):
+                raise KeyError("failing")
+
+        tokenizer.decoder = decoders.Decoder.custom(Failing())
+        with pytest.raises(KeyError, match="failing"):
+            tokenizer.decode([])
+        assert issubclass(UnsupportedVersionError, TokenizersError)
+
     def test_truncation(self):
         tokenizer = Tokenizer(BPE())
         tokenizer.add_tokens(["my", "name", "is", "john", "pair"])
@@ -326,6 +383,13 @@ class TestTokenizer:
         output = tokenizer.encode("my name is john", "pair")
         assert output.tokens == ["john", "pair"]
 
+        # Middle truncation keeps the head and the tail
+        tokenizer.enable_truncation(2, direction="middle")
+        output = tokenizer.encode("my name is john")
+        assert output.tokens == ["my", "john"]
+        assert tokenizer.truncation["head_ratio"] == 0.5
+        assert tokenizer.truncation["boundary"] == "token"
+
     def test_padding(self):
         tokenizer = Tokenizer(BPE())
         tokenizer.add_tokens(["my", "name", "is", "john", "pair"])
@@ -350,6 +414,14 @@ class TestTokenizer:
         padding = tokenizer.padding
         tokenizer.enable_padding(**padding)
 
+        # Can pad to the smallest bucket that fits
+        tokenizer.enable_padding(buckets=[2, 4])
+        output = tokenizer.encode_batch(["my", "my name is", "my name is john pair"])
+        assert [len(encoding) for encoding in output] == [2, 4, 5]
+        assert tokenizer.padding["buckets"] == [2, 4]
+        tokenizer.enable_padding(**tokenizer.padding)
+        assert tokenizer.padding["buckets"] == [2, 4]
+
     def test_decode(self):
         tokenizer = Tokenizer(BPE())
         tokenizer.add_tokens(["my", "name", "is", "john", "pair"])
@@ -362,6 +434,30 @@ class TestTokenizer:
         output = tokenizer.decode_batch([[0, 1, 2, 3], [4]])
         assert output == ["my name is john", "pair"]
 
+    def test_decode_with_offsets(self):
+        tokenizer = Tokenizer(BPE())
+        tokenizer.add_tokens(["Ġcaf", "Ã", "©", "!"])
+        tokenizer.decoder = decoders.ByteLevel()
+
+        # The two bytes of `é` get the same span
+        output, offsets = tokenizer.decode_with_offsets([0, 1, 2, 3])
+        assert output == tokenizer.decode([0, 1, 2, 3])
+        assert output == " café!"
+        assert offsets == [(0, 4), (4, 5), (4, 5), (5, 6)]
+
+    def test_encode_with_trace(self):
+        tokenizer = Tokenizer(WordLevel({"my": 0, "name": 1, "[UNK]": 2}, unk_token="[UNK]"))
+        tokenizer.normalizer = Lowercase()
+        tokenizer.pre_tokenizer = Whitespace()
+        tokenizer.add_special_tokens(["[CLS]"])
+        tokenizer.post_processor = BertProcessing(("[CLS]", 3), ("[CLS]", 3))
+
+        trace = json.loads(tokenizer.encode_with_trace("My NAME"))
+        assert trace["sequences"][0]["normalized"] == "my name"
+        assert [split["normalized"] for split in trace["sequences"][0]["pre_tokenized"]] == ["my", "name"]
+        assert [insertion["index"] for insertion in trace["insertions"]] == [0, 3]
+        assert trace["encoding"]["ids"] == tokenizer.encode("My NAME").ids
+
     def test_get_vocab(self):
         tokenizer = Tokenizer(BPE())
         tokenizer.add_tokens(["my", "name", "is", "john", "pair"])
@@ -14,7 +14,7 @@ use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
 use tokenizers::processors::bert::BertProcessing;
 use tokenizers::{decoders, EncodeInput, Model, TokenizerImpl};
 
-use common::{iter_bench_encode, iter_bench_encode_batch, iter_bench_train};
+use common::{iter_bench_encode, iter_bench_encode_batch, iter_bench_encode_ids, iter_bench_train};
 use tokenizers::decoders::DecoderWrapper;
 use tokenizers::pre_tokenizers::whitespace::Whitespace;
 use tokenizers::processors::PostProcessorWrapper;
@@ -64,6 +64,10 @@ pub fn bench_bert(c: &mut Criterion) {
         b.iter_custom(|iters| iter_bench_encode(iters, &tokenizer, &lines))
     });
 
+    c.bench_function("WordPiece BERT encode ids", |b| {
+        b.iter_custom(|iters| iter_bench_encode_ids(iters, &tokenizer, &lines))
+    });
+
     c.bench_function("WordPiece BERT encode batch", |b| {
         b.iter_custom(|iters| iter_bench_encode_batch(iters, &tokenizer, &batches))
     });
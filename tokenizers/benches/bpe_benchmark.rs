@@ -15,7 +15,7 @@ use tokenizers::pre_tokenizers::whitespace::Whitespace;
 use tokenizers::tokenizer::{AddedToken, EncodeInput};
 use tokenizers::Tokenizer;
 
-use common::{iter_bench_encode, iter_bench_encode_batch, iter_bench_train};
+use common::{iter_bench_encode, iter_bench_encode_batch, iter_bench_encode_ids, iter_bench_train};
 use std::ops::Deref;
 
 static BATCH_SIZE: usize = 1_000;
@@ -49,6 +49,10 @@ fn bench_gpt2(c: &mut Criterion) {
         b.iter_custom(|iters| iter_bench_encode(iters, tokenizer.deref(), &lines))
     });
 
+    c.bench_function("BPE GPT2 encode ids", |b| {
+        b.iter_custom(|iters| iter_bench_encode_ids(iters, tokenizer.deref(), &lines))
+    });
+
     c.bench_function("BPE GPT2 encode batch", |b| {
         b.iter_custom(|iters| iter_bench_encode_batch(iters, tokenizer.deref(), &batches))
     });
@@ -2,11 +2,13 @@
 //! This is the CLI binary for the Tokenizers project
 //!
 
-use clap::{Parser, Subcommand};
-use std::io::{self, BufRead, Write};
-use tokenizers::models::bpe::BPE;
-use tokenizers::pre_tokenizers::byte_level::ByteLevel;
-use tokenizers::tokenizer::{AddedToken, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use tokenizers::models::TrainerWrapper;
+use tokenizers::tokenizer::{Encoding, Offsets, Result, RoundTripReport, Severity};
+use tokenizers::utils::analysis::{AnalysisConfig, Analyzer, Comparison};
 use tokenizers::Tokenizer;
 
 /// Generate custom Tokenizers or use existing ones
@@ -19,24 +21,222 @@ struct Args {
 
 #[derive(Subcommand, Debug)]
 enum Command {
+    /// Interactively encode the text entered on stdin
     Shell {
-        /// Path to the vocab.json file
-        vocab: String,
-        /// Path to the merges.txt file
-        merges: String,
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+    },
+    /// Encode each line of the input, and output one JSON object per line
+    Encode {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+        /// Files to encode. Reads from stdin if none is provided
+        files: Vec<String>,
+        /// Whether to add the special tokens from the post-processor
+        #[arg(long)]
+        add_special_tokens: bool,
+    },
+    /// Encode each line of the input, and output the JSON trace of each stage of the pipeline
+    Trace {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+        /// Files to encode. Reads from stdin if none is provided
+        files: Vec<String>,
+        /// Whether to add the special tokens from the post-processor
+        #[arg(long)]
+        add_special_tokens: bool,
+    },
+    /// Decode each line of the input, expected to be a JSON list of ids (or an object with
+    /// an `ids` field), and output one JSON object per line
+    Decode {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+        /// Files to decode. Reads from stdin if none is provided
+        files: Vec<String>,
+        /// Whether to skip the special tokens while decoding
+        #[arg(long)]
+        skip_special_tokens: bool,
+    },
+    /// Train the model of a tokenizer on the given files
+    Train {
+        /// Path to the tokenizer.json file providing the pipeline to train
+        tokenizer: String,
+        /// Path to the JSON trainer configuration, like `{"BpeTrainer": {...}}`
+        #[arg(long)]
+        config: String,
+        /// Where to save the trained tokenizer.json
+        #[arg(long)]
+        output: String,
+        /// Files used for training
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Print the components of the pipeline and the vocabulary size
+    Info {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+    },
+    /// Check the consistency of a tokenizer, and print the problems found. Fails when any of
+    /// them is an error
+    Validate {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+    },
+    /// Check which lines of the input survive a round-trip through the tokenizer, and output
+    /// a JSON report of the mismatches, classified by cause
+    RoundTrip {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+        /// Files to check. Reads from stdin if none is provided
+        files: Vec<String>,
+        /// Number of examples kept for each cause
+        #[arg(long, default_value_t = 5)]
+        max_examples: usize,
+        /// Number of lines checked at once
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+    },
+    /// Compute the statistics of some tokenizers over the input, like the number of tokens per
+    /// word or the unused tokens, and output them as JSON
+    Analyze {
+        /// Paths to the tokenizer.json files to compare
+        #[arg(long = "tokenizer", required = true)]
+        tokenizers: Vec<String>,
+        /// Files to analyze. Reads from stdin if none is provided
+        files: Vec<String>,
+        /// Whether to break the statistics down by Unicode script
+        #[arg(long)]
+        by_script: bool,
+        /// Number of tokens listed as never used, and as the longest ones
+        #[arg(long, default_value_t = 20)]
+        max_tokens: usize,
+        /// Number of lines encoded at once
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+        /// Print the tokenizers side by side instead of the JSON output
+        #[arg(long)]
+        table: bool,
+    },
+    /// Encode a large file in parallel, and output one JSON object per line
+    Batch {
+        /// Path to the tokenizer.json file
+        tokenizer: String,
+        /// The file to encode, one sequence per line
+        input: String,
+        /// Where to write the output. Writes to stdout if not provided
+        #[arg(long)]
+        output: Option<String>,
+        /// Number of lines sent to `encode_batch` at once
+        #[arg(long, default_value_t = 10_000)]
+        batch_size: usize,
+        /// Whether to add the special tokens from the post-processor
+        #[arg(long)]
+        add_special_tokens: bool,
+    },
+    /// Convert a tokenizer from another format into a tokenizer.json file
+    Convert {
+        /// The file to convert
+        input: String,
+        /// Where to save the tokenizer.json
+        #[arg(long)]
+        output: String,
+        /// The format of the input. Guessed from its extension if not provided
+        #[arg(long, value_enum)]
+        format: Option<ConvertFormat>,
+        /// The regex splitting the input before the BPE of a tiktoken file (its `pat_str`)
+        #[arg(long, required_if_eq("format", "tiktoken"))]
+        pattern: Option<String>,
+        /// A special token of a tiktoken file, with its id, like `<|endoftext|>=100257`
+        #[arg(long = "special-token", value_parser = parse_special_token)]
+        special_tokens: Vec<(String, u32)>,
+        /// Whether to pretty print the tokenizer.json
+        #[arg(long)]
+        pretty: bool,
     },
 }
 
-fn shell(vocab: &str, merges: &str) -> Result<()> {
-    let bpe = BPE::from_file(vocab, merges).build()?;
-    let mut tokenizer = Tokenizer::new(bpe);
-    tokenizer
-        .with_pre_tokenizer(ByteLevel::default())
-        .with_decoder(ByteLevel::default());
+/// The formats that can be converted into a tokenizer.json
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ConvertFormat {
+    /// A `.tiktoken` file, with the base64 encoded tokens and their rank
+    Tiktoken,
+    /// A SentencePiece `.model` file
+    Sentencepiece,
+}
 
-    tokenizer.add_tokens(&[AddedToken::from(String::from("ing"), false).single_word(false)]);
-    tokenizer
-        .add_special_tokens(&[AddedToken::from(String::from("[ENT]"), true).single_word(true)]);
+fn parse_special_token(value: &str) -> std::result::Result<(String, u32), String> {
+    let (token, id) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("Expected `<token>=<id>`, got `{}`", value))?;
+    let id = id
+        .parse()
+        .map_err(|_| format!("Invalid id `{}` for special token `{}`", id, token))?;
+    Ok((token.to_string(), id))
+}
+
+/// What gets written for each encoded line
+#[derive(Serialize)]
+struct EncodeOutput<'a> {
+    ids: &'a [u32],
+    tokens: &'a [String],
+    offsets: &'a [Offsets],
+    type_ids: &'a [u32],
+    attention_mask: &'a [u32],
+}
+
+impl<'a> From<&'a Encoding> for EncodeOutput<'a> {
+    fn from(encoding: &'a Encoding) -> Self {
+        Self {
+            ids: encoding.get_ids(),
+            tokens: encoding.get_tokens(),
+            offsets: encoding.get_offsets(),
+            type_ids: encoding.get_type_ids(),
+            attention_mask: encoding.get_attention_mask(),
+        }
+    }
+}
+
+/// What we accept for each line to decode
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DecodeInput {
+    Ids(Vec<u32>),
+    Object { ids: Vec<u32> },
+}
+
+impl DecodeInput {
+    fn ids(&self) -> &[u32] {
+        match self {
+            Self::Ids(ids) | Self::Object { ids } => ids,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DecodeOutput {
+    text: String,
+}
+
+/// Iterate over the lines of all the given files, or stdin if there are none.
+fn input_lines(files: &[String]) -> Result<Box<dyn Iterator<Item = io::Result<String>>>> {
+    if files.is_empty() {
+        return Ok(Box::new(BufReader::new(io::stdin()).lines()));
+    }
+    let readers = files
+        .iter()
+        .map(|file| Ok(BufReader::new(File::open(file)?)))
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(Box::new(readers.into_iter().flat_map(|r| r.lines())))
+}
+
+fn write_json_line<T: Serialize, W: Write>(out: &mut W, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+fn shell(tokenizer: &str) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
 
     let stdin = io::stdin();
     let mut handle = stdin.lock();
@@ -47,7 +247,9 @@ fn shell(vocab: &str, merges: &str) -> Result<()> {
 
         print!("\nEnter some text to tokenize:\n>  ");
         io::stdout().flush()?;
-        handle.read_line(&mut buffer)?;
+        if handle.read_line(&mut buffer)? == 0 {
+            return Ok(());
+        }
         let buffer = buffer.trim_end();
 
         let timer = std::time::Instant::now();
@@ -57,17 +259,337 @@ fn shell(vocab: &str, merges: &str) -> Result<()> {
         println!("Tokens:\t\t{:?}", encoded.get_tokens());
         println!("IDs:\t\t{:?}", encoded.get_ids());
         println!("Offsets:\t{:?}", encoded.get_offsets());
-        println!(
-            "Decoded:\t{}",
-            tokenizer.decode(encoded.get_ids(), true).unwrap()
-        );
+        println!("Decoded:\t{}", tokenizer.decode(encoded.get_ids(), true)?);
         println!("Tokenized in {:?}", elapsed);
     }
 }
 
+fn encode(tokenizer: &str, files: &[String], add_special_tokens: bool) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    for line in input_lines(files)? {
+        let encoding = tokenizer.encode(line?, add_special_tokens)?;
+        write_json_line(&mut out, &EncodeOutput::from(&encoding))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn trace(tokenizer: &str, files: &[String], add_special_tokens: bool) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    for line in input_lines(files)? {
+        let trace = tokenizer.encode_with_trace(line?, add_special_tokens)?;
+        write_json_line(&mut out, &trace)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn decode(tokenizer: &str, files: &[String], skip_special_tokens: bool) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    for line in input_lines(files)? {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let input: DecodeInput = serde_json::from_str(&line)?;
+        let text = tokenizer.decode(input.ids(), skip_special_tokens)?;
+        write_json_line(&mut out, &DecodeOutput { text })?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn train(tokenizer: &str, config: &str, output: &str, files: Vec<String>) -> Result<()> {
+    let mut tokenizer = Tokenizer::from_file(tokenizer)?;
+    let mut trainer: TrainerWrapper = serde_json::from_reader(BufReader::new(File::open(config)?))?;
+    tokenizer
+        .train_from_files(&mut trainer, files)?
+        .save(output, false)?;
+    Ok(())
+}
+
+fn info(tokenizer: &str) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+
+    // Each component is serialized with its `type`, which is all we need to describe it
+    let serialized = serde_json::to_value(&tokenizer)?;
+    let component = |name: &str| match serialized.get(name) {
+        Some(serde_json::Value::Null) | None => "None".to_string(),
+        Some(value) => value
+            .get("type")
+            .and_then(|t| t.as_str())
+            .unwrap_or("Unknown")
+            .to_string(),
+    };
+
+    println!("Normalizer:\t{}", component("normalizer"));
+    println!("PreTokenizer:\t{}", component("pre_tokenizer"));
+    println!("Model:\t\t{}", component("model"));
+    println!("PostProcessor:\t{}", component("post_processor"));
+    println!("Decoder:\t{}", component("decoder"));
+    println!("Vocab size:\t{}", tokenizer.get_vocab_size(false));
+    println!(
+        "Vocab size (with added tokens):\t{}",
+        tokenizer.get_vocab_size(true)
+    );
+    println!(
+        "Added tokens:\t{}",
+        tokenizer.get_added_tokens_decoder().len()
+    );
+    println!("Truncation:\t{:?}", tokenizer.get_truncation());
+    println!("Padding:\t{:?}", tokenizer.get_padding());
+    Ok(())
+}
+
+fn validate(tokenizer: &str) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+    let diagnostics = tokenizer.validate();
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!("Found {} error(s) in the tokenizer", errors).into());
+    }
+    Ok(())
+}
+
+fn round_trip(
+    tokenizer: &str,
+    files: &[String],
+    max_examples: usize,
+    batch_size: usize,
+) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+    let mut lines = input_lines(files)?;
+    let batch_size = batch_size.max(1);
+    let mut report = RoundTripReport::default();
+    loop {
+        let chunk = lines
+            .by_ref()
+            .take(batch_size)
+            .collect::<io::Result<Vec<_>>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        report.merge(
+            tokenizer.check_round_trip(&chunk, max_examples)?,
+            max_examples,
+        );
+    }
+
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, &report)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn analyze(
+    tokenizers: &[String],
+    files: &[String],
+    config: AnalysisConfig,
+    batch_size: usize,
+    table: bool,
+) -> Result<()> {
+    let tokenizers = tokenizers
+        .iter()
+        .map(|path| Ok((path, Tokenizer::from_file(path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut analyzers = tokenizers
+        .iter()
+        .map(|(path, tokenizer)| Analyzer::new(path.as_str(), tokenizer, config.clone()))
+        .collect::<Vec<_>>();
+
+    let mut lines = input_lines(files)?;
+    let batch_size = batch_size.max(1);
+    loop {
+        let chunk = lines
+            .by_ref()
+            .take(batch_size)
+            .collect::<io::Result<Vec<_>>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        for analyzer in &mut analyzers {
+            analyzer.update(&chunk)?;
+        }
+    }
+
+    let comparison = Comparison {
+        tokenizers: analyzers.into_iter().map(Analyzer::finish).collect(),
+    };
+    let mut out = io::stdout().lock();
+    if table {
+        write!(out, "{}", comparison)?;
+    } else {
+        serde_json::to_writer_pretty(&mut out, &comparison)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn batch(
+    tokenizer: &str,
+    input: &str,
+    output: Option<&str>,
+    batch_size: usize,
+    add_special_tokens: bool,
+) -> Result<()> {
+    let tokenizer = Tokenizer::from_file(tokenizer)?;
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let mut lines = BufReader::new(File::open(input)?).lines();
+    let batch_size = batch_size.max(1);
+    loop {
+        let chunk = lines
+            .by_ref()
+            .take(batch_size)
+            .collect::<io::Result<Vec<_>>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        for encoding in tokenizer.encode_batch(chunk, add_special_tokens)? {
+            write_json_line(&mut out, &EncodeOutput::from(&encoding))?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn convert(
+    input: &str,
+    output: &str,
+    format: Option<ConvertFormat>,
+    pattern: Option<&str>,
+    special_tokens: &[(String, u32)],
+    pretty: bool,
+) -> Result<()> {
+    let format = match format {
+        Some(format) => format,
+        None => match std::path::Path::new(input)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("tiktoken") => ConvertFormat::Tiktoken,
+            Some("model") => ConvertFormat::Sentencepiece,
+            _ => {
+                return Err(format!(
+                    "Can't guess the format of `{}`, please provide `--format`",
+                    input
+                )
+                .into())
+            }
+        },
+    };
+
+    let tokenizer = match format {
+        ConvertFormat::Tiktoken => {
+            let pattern = pattern.ok_or("A `--pattern` is required to convert a tiktoken file")?;
+            let special_tokens = special_tokens
+                .iter()
+                .map(|(token, id)| (token.as_str(), *id))
+                .collect::<Vec<_>>();
+            Tokenizer::from_tiktoken(input, pattern, &special_tokens)?
+        }
+        ConvertFormat::Sentencepiece => {
+            if !special_tokens.is_empty() || pattern.is_some() {
+                return Err(
+                    "`--pattern` and `--special-token` are only used with tiktoken files".into(),
+                );
+            }
+            Tokenizer::from_sentencepiece(input)?
+        }
+    };
+    tokenizer.save(output, pretty)
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
     match args.command {
-        Command::Shell { vocab, merges } => shell(&vocab, &merges),
+        Command::Shell { tokenizer } => shell(&tokenizer),
+        Command::Encode {
+            tokenizer,
+            files,
+            add_special_tokens,
+        } => encode(&tokenizer, &files, add_special_tokens),
+        Command::Trace {
+            tokenizer,
+            files,
+            add_special_tokens,
+        } => trace(&tokenizer, &files, add_special_tokens),
+        Command::Decode {
+            tokenizer,
+            files,
+            skip_special_tokens,
+        } => decode(&tokenizer, &files, skip_special_tokens),
+        Command::Train {
+            tokenizer,
+            config,
+            output,
+            files,
+        } => train(&tokenizer, &config, &output, files),
+        Command::Info { tokenizer } => info(&tokenizer),
+        Command::Validate { tokenizer } => validate(&tokenizer),
+        Command::RoundTrip {
+            tokenizer,
+            files,
+            max_examples,
+            batch_size,
+        } => round_trip(&tokenizer, &files, max_examples, batch_size),
+        Command::Analyze {
+            tokenizers,
+            files,
+            by_script,
+            max_tokens,
+            batch_size,
+            table,
+        } => analyze(
+            &tokenizers,
+            &files,
+            AnalysisConfig {
+                by_script,
+                max_tokens,
+            },
+            batch_size,
+            table,
+        ),
+        Command::Batch {
+            tokenizer,
+            input,
+            output,
+            batch_size,
+            add_special_tokens,
+        } => batch(
+            &tokenizer,
+            &input,
+            output.as_deref(),
+            batch_size,
+            add_special_tokens,
+        ),
+        Command::Convert {
+            input,
+            output,
+            format,
+            pattern,
+            special_tokens,
+            pretty,
+        } => convert(
+            &input,
+            &output,
+            format,
+            pattern.as_deref(),
+            &special_tokens,
+            pretty,
+        ),
     }
 }
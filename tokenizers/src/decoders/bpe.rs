@@ -1,4 +1,4 @@
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 
 use serde::{Deserialize, Serialize};
 
@@ -35,4 +35,16 @@ impl Decoder for BPEDecoder {
             })
             .collect())
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        let n = tokens.len().saturating_sub(1);
+        Ok(tokens
+            .iter()
+            .enumerate()
+            .map(|(i, token)| {
+                let replacement = if i == n { "" } else { " " };
+                token.map(|token| token.replace(&self.suffix, replacement))
+            })
+            .collect())
+    }
 }
@@ -1,7 +1,8 @@
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 use monostate::MustBe;
 
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
 #[derive(Deserialize, Clone, Debug, Serialize, Default)]
 /// ByteFallback is a simple trick which converts tokens looking like `<0x61>`
@@ -21,22 +22,35 @@ impl ByteFallback {
     }
 }
 
+/// The byte represented by the given token, if it looks like `<0x61>`
+fn token_byte(token: &str) -> Option<u8> {
+    if token.len() == 6 && token.starts_with("<0x") && token.ends_with('>') {
+        u8::from_str_radix(&token[3..5], 16).ok()
+    } else {
+        None
+    }
+}
+
+/// Convert the given bytes, each with the tokens it comes from, like `decode_chain` does
+fn aligned_bytes(bytes: &[(u8, Range<usize>)]) -> Vec<AlignedString> {
+    let raw = bytes.iter().map(|(b, _)| *b).collect::<Vec<_>>();
+    if std::str::from_utf8(&raw).is_ok() {
+        vec![AlignedString::from_utf8_lossy(bytes)]
+    } else {
+        bytes
+            .iter()
+            .map(|(_, sources)| AlignedString::with_sources("�", sources.clone()))
+            .collect()
+    }
+}
+
 impl Decoder for ByteFallback {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
         let mut new_tokens: Vec<String> = vec![];
         let mut previous_byte_tokens: Vec<u8> = vec![];
 
         for token in tokens {
-            let bytes = if token.len() == 6 && token.starts_with("<0x") && token.ends_with('>') {
-                if let Ok(byte) = u8::from_str_radix(&token[3..5], 16) {
-                    Some(byte)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-            if let Some(bytes) = bytes {
+            if let Some(bytes) = token_byte(&token) {
                 previous_byte_tokens.push(bytes);
             } else {
                 if !previous_byte_tokens.is_empty() {
@@ -64,6 +78,24 @@ impl Decoder for ByteFallback {
 
         Ok(new_tokens)
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        let mut new_tokens = vec![];
+        let mut previous_byte_tokens = vec![];
+
+        for token in tokens {
+            if let Some(byte) = token_byte(&token.get()) {
+                previous_byte_tokens.push((byte, token.sources()));
+            } else {
+                new_tokens.extend(aligned_bytes(&previous_byte_tokens));
+                previous_byte_tokens.clear();
+                new_tokens.push(token);
+            }
+        }
+        new_tokens.extend(aligned_bytes(&previous_byte_tokens));
+
+        Ok(new_tokens)
+    }
 }
 
 #[cfg(test)]
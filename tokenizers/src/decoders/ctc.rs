@@ -1,5 +1,5 @@
 use crate::decoders::wordpiece;
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -60,6 +60,34 @@ impl Decoder for CTC {
             })
             .collect())
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        Ok(tokens
+            .into_iter()
+            // The duplicates come from all the merged tokens
+            .coalesce(|previous, token| {
+                if previous.get() == token.get() {
+                    let text = previous.get();
+                    let mut merged = previous;
+                    merged.extend(token);
+                    Ok(AlignedString::with_sources(text, merged.sources()))
+                } else {
+                    Err((previous, token))
+                }
+            })
+            .map(|token| {
+                token.map(|token| {
+                    let mut replaced = token.replace(&self.pad_token, "");
+                    if self.cleanup {
+                        replaced =
+                            wordpiece::cleanup(&replaced).replace(&self.word_delimiter_token, " ");
+                    }
+                    replaced
+                })
+            })
+            .filter(|token| !token.is_empty())
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 use monostate::MustBe;
 use serde::{Deserialize, Serialize};
 
@@ -26,6 +26,14 @@ impl Decoder for Fuse {
         let new_string = tokens.join("");
         Ok(vec![new_string])
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        let mut new_string = AlignedString::default();
+        for token in tokens {
+            new_string.extend(token);
+        }
+        Ok(vec![new_string])
+    }
 }
 
 #[cfg(test)]
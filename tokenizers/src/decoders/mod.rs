@@ -22,7 +22,7 @@ use crate::decoders::wordpiece::WordPiece;
 use crate::normalizers::replace::Replace;
 use crate::pre_tokenizers::byte_level::ByteLevel;
 use crate::pre_tokenizers::metaspace::Metaspace;
-use crate::{Decoder, Result};
+use crate::{AlignedString, Decoder, Result};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
@@ -54,6 +54,21 @@ impl Decoder for DecoderWrapper {
             Self::Fuse(bf) => bf.decode_chain(tokens),
         }
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        match self {
+            Self::BPE(bpe) => bpe.decode_chain_aligned(tokens),
+            Self::ByteLevel(bl) => bl.decode_chain_aligned(tokens),
+            Self::Metaspace(ms) => ms.decode_chain_aligned(tokens),
+            Self::WordPiece(wp) => wp.decode_chain_aligned(tokens),
+            Self::CTC(ctc) => ctc.decode_chain_aligned(tokens),
+            Self::Sequence(seq) => seq.decode_chain_aligned(tokens),
+            Self::Replace(seq) => seq.decode_chain_aligned(tokens),
+            Self::ByteFallback(bf) => bf.decode_chain_aligned(tokens),
+            Self::Strip(bf) => bf.decode_chain_aligned(tokens),
+            Self::Fuse(bf) => bf.decode_chain_aligned(tokens),
+        }
+    }
 }
 
 impl_enum_from!(BPEDecoder, DecoderWrapper, BPE);
@@ -1,5 +1,5 @@
 use crate::decoders::DecoderWrapper;
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 use crate::utils::macro_rules_attribute;
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +13,10 @@ impl Sequence {
     pub fn new(decoders: Vec<DecoderWrapper>) -> Self {
         Self { decoders }
     }
+
+    pub fn get_decoders(&self) -> &[DecoderWrapper] {
+        &self.decoders
+    }
 }
 
 impl Decoder for Sequence {
@@ -22,6 +26,13 @@ impl Decoder for Sequence {
         }
         Ok(tokens)
     }
+
+    fn decode_chain_aligned(&self, mut tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        for decoder in &self.decoders {
+            tokens = decoder.decode_chain_aligned(tokens)?;
+        }
+        Ok(tokens)
+    }
 }
 
 #[cfg(test)]
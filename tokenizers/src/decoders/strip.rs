@@ -1,4 +1,4 @@
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 
 use serde::{Deserialize, Serialize};
 
@@ -57,6 +57,34 @@ impl Decoder for Strip {
             })
             .collect())
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        Ok(tokens
+            .into_iter()
+            .map(|token| {
+                let chars = token.chars().collect::<Vec<_>>();
+                let start_cut = chars
+                    .iter()
+                    .take(self.start)
+                    .take_while(|(c, _)| *c == self.content)
+                    .count();
+                let stop_cut = chars.len()
+                    - chars[start_cut..]
+                        .iter()
+                        .rev()
+                        .take(self.stop)
+                        .take_while(|(c, _)| *c == self.content)
+                        .count();
+
+                if start_cut == stop_cut {
+                    // Keep track of the tokens, even when nothing is left
+                    AlignedString::with_sources("", token.sources())
+                } else {
+                    AlignedString::from_chars(chars[start_cut..stop_cut].iter().cloned())
+                }
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
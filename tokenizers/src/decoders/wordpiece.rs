@@ -1,4 +1,4 @@
-use crate::tokenizer::{Decoder, Result};
+use crate::tokenizer::{AlignedString, Decoder, Result};
 
 use serde::{Deserialize, Serialize};
 
@@ -63,6 +63,30 @@ impl Decoder for WordPiece {
             })
             .collect::<Result<_>>()
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        Ok(tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut token)| {
+                if i != 0 {
+                    if token.get().starts_with(&self.prefix) {
+                        token = token.map(|token| token.replacen(&self.prefix, "", 1));
+                    } else {
+                        // The space comes from the token it separates from the previous one
+                        let first = token.chunks().first().map(|(_, sources)| sources.clone());
+                        let mut spaced = AlignedString::with_sources(" ", first.unwrap_or(0..0));
+                        spaced.extend(token);
+                        token = spaced;
+                    }
+                }
+                if self.cleanup {
+                    token = token.map(cleanup);
+                }
+                token
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
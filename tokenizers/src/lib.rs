@@ -150,4 +150,4 @@ pub use utils::parallelism;
 
 // Re-export for from_pretrained
 #[cfg(feature = "http")]
-pub use utils::from_pretrained::FromPretrainedParameters;
+pub use utils::from_pretrained::{FromPretrainedParameters, PretrainedError};
@@ -3,6 +3,7 @@ use std::{iter, mem};
 
 mod model;
 mod serialization;
+mod tiktoken;
 pub mod trainer;
 mod word;
 
@@ -33,6 +34,16 @@ pub enum Error {
     /// Dropout not between 0 and 1.
     #[error("Dropout should be between 0 and 1")]
     InvalidDropout,
+    /// When the tiktoken file is in the wrong format. This error holds the line number of
+    /// the line that caused the error.
+    #[error("Tiktoken file invalid at line {0}")]
+    BadTiktoken(usize),
+    /// If a token of a tiktoken file can't be built by merging two other tokens
+    #[error("Token `{0}` can't be built by merging two tokens of lower rank")]
+    UnmergeableToken(String),
+    /// If a token can't be represented as bytes while exporting to tiktoken
+    #[error("Token `{0}` is not a byte-level token")]
+    NotByteLevel(String),
 }
 
 /// Provides access to the `FirstLastIterator` to any Iterator
@@ -1,6 +1,6 @@
 use super::{super::OrderedVocabIter, trainer::BpeTrainer, Error, Pair, Word};
 use crate::tokenizer::{Model, Result, Token};
-use crate::utils::cache::{Cache, DEFAULT_CACHE_CAPACITY};
+use crate::utils::cache::{Cache, CachePolicy, CacheStats, DEFAULT_CACHE_CAPACITY};
 use crate::utils::iter::ResultShunt;
 use serde_json::Value;
 use std::borrow::Cow;
@@ -22,6 +22,7 @@ struct Config {
     vocab: Vocab,
     merges: Merges,
     cache_capacity: usize,
+    cache_policy: CachePolicy,
     dropout: Option<f32>,
     unk_token: Option<String>,
     continuing_subword_prefix: Option<String>,
@@ -43,6 +44,7 @@ impl Default for BpeBuilder {
                 vocab: HashMap::new(),
                 merges: vec![],
                 cache_capacity: DEFAULT_CACHE_CAPACITY,
+                cache_policy: CachePolicy::default(),
                 dropout: None,
                 unk_token: None,
                 continuing_subword_prefix: None,
@@ -82,6 +84,13 @@ impl BpeBuilder {
         self
     }
 
+    /// Set the policy used to evict entries from the cache once it is full.
+    #[must_use]
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.config.cache_policy = policy;
+        self
+    }
+
     /// Use [dropout](https://arxiv.org/abs/1910.13267) with the model.
     #[must_use]
     pub fn dropout(mut self, dropout: f32) -> Self {
@@ -148,7 +157,7 @@ impl BpeBuilder {
             .collect();
         let cache = match self.config.cache_capacity {
             0 => None,
-            capacity => Some(Cache::new(capacity)),
+            capacity => Some(Cache::with_policy(capacity, self.config.cache_policy)),
         };
 
         let vocab = self.config.vocab;
@@ -321,7 +330,7 @@ impl BPE {
                     }
                 }
             }
-            _ => return Err(Box::new(Error::BadVocabulary)),
+            _ => return Err(Error::BadVocabulary.into()),
         };
 
         // Read merges file
@@ -341,6 +350,11 @@ impl BPE {
         }
     }
 
+    /// Get the hit, miss and eviction counters of the cache, if caching is enabled.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
     pub fn get_vocab(&self) -> Vocab {
         self.vocab.clone()
     }
@@ -458,6 +472,19 @@ impl BPE {
             Ok(ret)
         }
     }
+
+    fn tokenize_ids_with_cache(&self, sequence: &str) -> Result<Vec<u32>> {
+        if let Some(ref hit) = self.cache.as_ref().and_then(|c| c.get(sequence)) {
+            Ok(hit.get_chars())
+        } else {
+            let word = self.merge_word(sequence)?;
+            let ret = word.get_chars();
+            if let Some(ref cache) = self.cache {
+                cache.set(sequence.to_owned(), word);
+            }
+            Ok(ret)
+        }
+    }
 }
 
 impl Model for BPE {
@@ -484,6 +511,18 @@ impl Model for BPE {
         }
     }
 
+    fn tokenize_ids(&self, sequence: &str) -> Result<Vec<u32>> {
+        if sequence.is_empty() {
+            return Ok(vec![]);
+        }
+
+        if self.dropout.is_none() {
+            self.tokenize_ids_with_cache(sequence)
+        } else {
+            Ok(self.merge_word(sequence)?.get_chars())
+        }
+    }
+
     fn token_to_id(&self, token: &str) -> Option<u32> {
         self.vocab.get(token).copied()
     }
@@ -544,6 +583,7 @@ impl Model for BPE {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::ModelError;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -625,6 +665,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cache_stats() {
+        let vocab: Vocab = [("a".into(), 0), ("b".into(), 1), ("ab".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let merges: Merges = vec![("a".to_string(), "b".to_string())];
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab.clone(), merges.clone())
+            .cache_capacity(1)
+            .cache_policy(CachePolicy::Clock)
+            .build()
+            .unwrap();
+
+        bpe.tokenize("ab").unwrap();
+        bpe.tokenize("ab").unwrap();
+        bpe.tokenize("ba").unwrap();
+        bpe.tokenize("ba").unwrap();
+        assert_eq!(
+            bpe.cache_stats(),
+            Some(CacheStats {
+                hits: 2,
+                misses: 2,
+                evictions: 1
+            })
+        );
+
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .cache_capacity(0)
+            .build()
+            .unwrap();
+        bpe.tokenize("ab").unwrap();
+        assert_eq!(bpe.cache_stats(), None);
+    }
+
     #[test]
     // Test tokenization. With dropout set to 0 tokenization is deterministic,
     // so we know exactly what the result should be.
@@ -788,9 +864,9 @@ mod tests {
         .build()
         {
             Ok(_) => unreachable!(),
-            Err(err) => match err.downcast_ref::<Error>() {
-                Some(Error::MergeTokenOutOfVocabulary(token)) => {
-                    assert_eq!(*token, String::from("d"))
+            Err(err) => match err {
+                crate::Error::Model(ModelError::BPE(Error::MergeTokenOutOfVocabulary(token))) => {
+                    assert_eq!(token, String::from("d"))
                 }
                 _ => unreachable!(),
             },
@@ -819,8 +895,10 @@ mod tests {
         .build()
         {
             Ok(_) => unreachable!(),
-            Err(err) => match err.downcast_ref::<Error>() {
-                Some(Error::BadMerges(line)) => assert_eq!(*line, 2),
+            Err(err) => match err {
+                crate::Error::Parse { file, line } => {
+                    assert_eq!((file, line), (crate::ParsedFile::Merges, 2))
+                }
                 _ => unreachable!(),
             },
         }
@@ -0,0 +1,283 @@
+//! Support for the `.tiktoken` vocabulary format, where each line holds the base64 encoded
+//! bytes of a token, followed by its rank. There is no merges file: the merges are
+//! reconstructed from the ranks.
+use super::{Error, Merges, Vocab, BPE};
+use crate::pre_tokenizers::byte_level::{BYTES_CHAR, CHAR_BYTES};
+use crate::tokenizer::Result;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim_end_matches('=').as_bytes();
+    if encoded.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    for chunk in encoded.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|a| a == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        bytes.extend((0..chunk.len() - 1).map(|i| (n >> (16 - 8 * i)) as u8));
+    }
+    Some(bytes)
+}
+
+/// Represent the given bytes with the `ByteLevel` alphabet
+fn bytes_to_token(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| BYTES_CHAR[b]).collect()
+}
+
+/// Find the two parts a token is made of, by running the BPE algorithm on its bytes with
+/// only the tokens of lower rank.
+fn split_token<'a>(bytes: &'a [u8], rank: u32, ranks: &HashMap<&[u8], u32>) -> Vec<&'a [u8]> {
+    let mut parts = (0..bytes.len())
+        .map(|i| &bytes[i..i + 1])
+        .collect::<Vec<_>>();
+    let mut boundaries = (0..=bytes.len()).collect::<Vec<_>>();
+    while parts.len() > 1 {
+        let best = (0..parts.len() - 1)
+            .filter_map(|i| {
+                ranks
+                    .get(&bytes[boundaries[i]..boundaries[i + 2]])
+                    .filter(|r| **r < rank)
+                    .map(|r| (*r, i))
+            })
+            .min();
+        match best {
+            Some((_, i)) => {
+                boundaries.remove(i + 1);
+                parts.remove(i + 1);
+                parts[i] = &bytes[boundaries[i]..boundaries[i + 1]];
+            }
+            None => break,
+        }
+    }
+    parts
+}
+
+/// Build the vocab and merges of a byte-level `BPE` from the ranks of a tiktoken file.
+fn ranks_to_vocab_and_merges(ranks: &HashMap<Vec<u8>, u32>) -> Result<(Vocab, Merges)> {
+    let ranks_ref = ranks
+        .iter()
+        .map(|(bytes, rank)| (bytes.as_slice(), *rank))
+        .collect::<HashMap<_, _>>();
+
+    let mut sorted = ranks.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|(_, rank)| **rank);
+
+    let mut merges = vec![];
+    for (bytes, rank) in &sorted {
+        if bytes.len() < 2 {
+            continue;
+        }
+        match split_token(bytes, **rank, &ranks_ref).as_slice() {
+            [left, right] => merges.push((bytes_to_token(left), bytes_to_token(right))),
+            _ => return Err(Error::UnmergeableToken(bytes_to_token(bytes)).into()),
+        }
+    }
+
+    let vocab = sorted
+        .into_iter()
+        .map(|(bytes, rank)| (bytes_to_token(bytes), *rank))
+        .collect();
+    Ok((vocab, merges))
+}
+
+impl BPE {
+    /// Read the given `.tiktoken` file, and build the vocab and merges of the equivalent
+    /// byte-level BPE. The ranks are used as ids.
+    ///
+    /// Just like with tiktoken, this model is expected to be used with a `ByteLevel`
+    /// pre-tokenizer and decoder, after splitting the input with the split pattern of the
+    /// original model.
+    pub fn read_tiktoken(file: &str) -> Result<(Vocab, Merges)> {
+        let file = BufReader::new(File::open(file)?);
+        let mut ranks = HashMap::new();
+        for (i, line) in file.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (token, rank) = line.split_once(' ').ok_or(Error::BadTiktoken(i + 1))?;
+            let token = base64_decode(token).ok_or(Error::BadTiktoken(i + 1))?;
+            let rank = rank
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| Error::BadTiktoken(i + 1))?;
+            ranks.insert(token, rank);
+        }
+        ranks_to_vocab_and_merges(&ranks)
+    }
+
+    /// Save the vocabulary of this model as a `.tiktoken` file. Only the tokens accepted by
+    /// `filter` are saved, and they must all be byte-level tokens.
+    ///
+    /// The merges are not saved as tiktoken uses the ranks (ids here) instead, so this is
+    /// only faithful if the merges are ordered like the ids of the tokens they produce,
+    /// which is the case for the models trained by the `BpeTrainer` with a `ByteLevel`
+    /// alphabet, or read from a tiktoken file.
+    pub fn save_tiktoken<P, F>(&self, path: P, filter: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: Fn(&str) -> bool,
+    {
+        let mut tokens = self
+            .get_vocab()
+            .into_iter()
+            .filter(|(token, _)| filter(token))
+            .collect::<Vec<_>>();
+        tokens.sort_by_key(|(_, id)| *id);
+
+        let mut file = BufWriter::new(File::create(path)?);
+        for (token, id) in tokens {
+            let bytes = token
+                .chars()
+                .map(|c| CHAR_BYTES.get(&c).copied())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| Error::NotByteLevel(token.clone()))?;
+            writeln!(file, "{} {}", base64_encode(&bytes), id)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn base64() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\xff\xfe\x00", "//4A"),
+        ] {
+            assert_eq!(base64_encode(bytes), encoded);
+            assert_eq!(base64_decode(encoded).unwrap(), bytes);
+        }
+        assert_eq!(base64_decode("Z"), None);
+        assert_eq!(base64_decode("Z!=="), None);
+    }
+
+    #[test]
+    fn merges_from_ranks() {
+        let ranks = [
+            (&b"a"[..], 0),
+            (b"b", 1),
+            (b" ", 2),
+            (b"ab", 3),
+            (b" ab", 4),
+        ]
+        .iter()
+        .map(|(b, r)| (b.to_vec(), *r))
+        .collect();
+        let (vocab, merges) = ranks_to_vocab_and_merges(&ranks).unwrap();
+        assert_eq!(vocab["Ġab"], 4);
+        assert_eq!(
+            merges,
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("Ġ".to_string(), "ab".to_string())
+            ]
+        );
+
+        // "abc" can't be built from two other tokens
+        let ranks = [(&b"a"[..], 0), (b"b", 1), (b"c", 2), (b"abc", 3)]
+            .iter()
+            .map(|(b, r)| (b.to_vec(), *r))
+            .collect();
+        assert!(ranks_to_vocab_and_merges(&ranks).is_err());
+    }
+
+    #[test]
+    fn read_and_save() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"YQ== 0\nYg== 1\nIA== 2\nYWI= 3\nIGFi 4\n")
+            .unwrap();
+        let path = file.path().to_str().unwrap();
+
+        let (vocab, merges) = BPE::read_tiktoken(path).unwrap();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap();
+        let tokens = crate::Model::tokenize(&bpe, "ĠabĠa").unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.id).collect::<Vec<_>>(),
+            vec![4, 2, 0]
+        );
+
+        let saved = NamedTempFile::new().unwrap();
+        bpe.save_tiktoken(saved.path(), |_| true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(saved.path()).unwrap(),
+            "YQ== 0\nYg== 1\nIA== 2\nYWI= 3\nIGFi 4\n"
+        );
+
+        let mut bad = NamedTempFile::new().unwrap();
+        bad.write_all(b"YQ== 0\nYg==\n").unwrap();
+        let err = BPE::read_tiktoken(bad.path().to_str().unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "Tiktoken file invalid at line 2");
+    }
+
+    #[test]
+    fn tokenizer_round_trip() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"YQ== 0\nYg== 1\nIA== 2\nYWI= 3\nIGFi 4\n")
+            .unwrap();
+        let tokenizer =
+            crate::Tokenizer::from_tiktoken(file.path(), r" ?[a-z]+|\s+", &[("<|endoftext|>", 10)])
+                .unwrap();
+
+        let encoding = tokenizer.encode("ab ab<|endoftext|>", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[3, 4, 10]);
+        assert_eq!(encoding.get_offsets(), &[(0, 2), (2, 5), (5, 18)]);
+        assert_eq!(tokenizer.decode(encoding.get_ids(), true).unwrap(), "ab ab");
+
+        // The special tokens are not exported
+        let saved = NamedTempFile::new().unwrap();
+        tokenizer.save_tiktoken(saved.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(saved.path()).unwrap(),
+            "YQ== 0\nYg== 1\nIA== 2\nYWI= 3\nIGFi 4\n"
+        );
+
+        assert!(matches!(
+            crate::Tokenizer::from_tiktoken(file.path(), r"\w+", &[("<|x|>", 3)]),
+            Err(crate::Error::AddedTokens(
+                crate::AddedTokensError::IdAlreadyUsed { id: 3, .. }
+            ))
+        ));
+    }
+}
@@ -4,6 +4,7 @@ use super::{Pair, WithFirstLastIterator, Word, BPE};
 use crate::parallelism::*;
 use crate::tokenizer::{AddedToken, Result, Trainer};
 use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::utils::word_counts::{count_words, WordCountsConfig};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
@@ -45,6 +46,7 @@ struct Config {
     continuing_subword_prefix: Option<String>,
     end_of_word_suffix: Option<String>,
     max_token_length: Option<usize>,
+    word_counts: WordCountsConfig,
 }
 
 /// A `BpeTrainerBuilder` can be used to create a `BpeTrainer` with a custom
@@ -66,6 +68,7 @@ impl Default for BpeTrainerBuilder {
                 continuing_subword_prefix: None,
                 end_of_word_suffix: None,
                 max_token_length: None,
+                word_counts: WordCountsConfig::default(),
             },
         }
     }
@@ -139,6 +142,13 @@ impl BpeTrainerBuilder {
         self
     }
 
+    /// Set how the words are counted before training, to bound the memory used
+    #[must_use]
+    pub fn word_counts(mut self, config: WordCountsConfig) -> Self {
+        self.config.word_counts = config;
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> BpeTrainer {
         BpeTrainer {
@@ -151,6 +161,7 @@ impl BpeTrainerBuilder {
             continuing_subword_prefix: self.config.continuing_subword_prefix,
             end_of_word_suffix: self.config.end_of_word_suffix,
             max_token_length: self.config.max_token_length,
+            word_counts: self.config.word_counts,
             words: HashMap::new(),
         }
     }
@@ -194,6 +205,9 @@ pub struct BpeTrainer {
     pub end_of_word_suffix: Option<String>,
     /// An optional parameter to limit the max length of any single token
     pub max_token_length: Option<usize>,
+    /// How the words are counted, and pruned, before training
+    #[serde(default)]
+    pub word_counts: WordCountsConfig,
 
     words: HashMap<String, u32>,
 }
@@ -632,28 +646,7 @@ impl Trainer for BpeTrainer {
         S: AsRef<str> + Send,
         F: Fn(&str) -> Result<Vec<String>> + Sync,
     {
-        let words: Result<HashMap<String, u32>> = iterator
-            .maybe_par_bridge()
-            .map(|sequence| {
-                let words = process(sequence.as_ref())?;
-                let mut map = HashMap::new();
-                for word in words {
-                    map.entry(word).and_modify(|c| *c += 1).or_insert(1);
-                }
-                Ok(map)
-            })
-            .reduce(
-                || Ok(HashMap::new()),
-                |acc, ws| {
-                    let mut acc = acc?;
-                    for (k, v) in ws? {
-                        acc.entry(k).and_modify(|c| *c += v).or_insert(v);
-                    }
-                    Ok(acc)
-                },
-            );
-
-        self.words = words?;
+        self.words = count_words(iterator, process, &self.word_counts)?;
         Ok(())
     }
 }
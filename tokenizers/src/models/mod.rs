@@ -11,9 +11,10 @@ use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize, Serializer};
 
 use crate::models::bpe::{BpeTrainer, BPE};
-use crate::models::unigram::{Unigram, UnigramTrainer};
+use crate::models::unigram::{Unigram, UnigramError, UnigramTrainer, UnigramTrainerError};
 use crate::models::wordlevel::{WordLevel, WordLevelTrainer};
 use crate::models::wordpiece::{WordPiece, WordPieceTrainer};
+use crate::utils::sentencepiece::SentencePieceError;
 use crate::{AddedToken, Model, Result, Token, Trainer};
 
 /// Wraps a vocab mapping (ID -> token) to a struct that will be serialized in order
@@ -57,6 +58,34 @@ impl<'a> Serialize for OrderedVocabIter<'a> {
     }
 }
 
+/// The errors of the models and their trainers
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum ModelError {
+    #[error(transparent)]
+    BPE(#[from] bpe::Error),
+    #[error(transparent)]
+    WordPiece(#[from] wordpiece::Error),
+    #[error(transparent)]
+    WordLevel(#[from] wordlevel::Error),
+    #[error(transparent)]
+    Unigram(#[from] UnigramError),
+    #[error(transparent)]
+    UnigramTrainer(#[from] UnigramTrainerError),
+    #[error(transparent)]
+    SentencePiece(#[from] SentencePieceError),
+    /// The files of a pretrained tokenizer could not be found
+    #[cfg(feature = "http")]
+    #[error(transparent)]
+    Pretrained(#[from] crate::utils::from_pretrained::PretrainedError),
+    /// A trainer was given a model of another type
+    #[error("{trainer} can only train a {model}")]
+    IncompatibleTrainer {
+        trainer: &'static str,
+        model: &'static str,
+    },
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum ModelWrapper {
@@ -73,6 +102,18 @@ impl_enum_from!(WordPiece, ModelWrapper, WordPiece);
 impl_enum_from!(BPE, ModelWrapper, BPE);
 impl_enum_from!(Unigram, ModelWrapper, Unigram);
 
+impl ModelWrapper {
+    /// The id of the token used for the unknown parts of the input, if any
+    pub fn get_unk_id(&self) -> Option<u32> {
+        match self {
+            Self::BPE(t) => t.unk_token.as_ref().and_then(|unk| t.token_to_id(unk)),
+            Self::WordPiece(t) => t.token_to_id(&t.unk_token),
+            Self::WordLevel(t) => t.token_to_id(&t.unk_token),
+            Self::Unigram(t) => t.unk_id().map(|id| id as u32),
+        }
+    }
+}
+
 impl Model for ModelWrapper {
     type Trainer = TrainerWrapper;
 
@@ -85,6 +126,15 @@ impl Model for ModelWrapper {
         }
     }
 
+    fn tokenize_ids(&self, tokens: &str) -> Result<Vec<u32>> {
+        match self {
+            Self::WordLevel(t) => t.tokenize_ids(tokens),
+            Self::WordPiece(t) => t.tokenize_ids(tokens),
+            Self::BPE(t) => t.tokenize_ids(tokens),
+            Self::Unigram(t) => t.tokenize_ids(tokens),
+        }
+    }
+
     fn token_to_id(&self, token: &str) -> Option<u32> {
         match self {
             Self::WordLevel(t) => t.token_to_id(token),
@@ -138,6 +188,15 @@ impl Model for ModelWrapper {
             Self::Unigram(t) => t.get_trainer().into(),
         }
     }
+
+    fn tokenize_nbest(&self, sequence: &str, n: usize) -> Result<Vec<(Vec<Token>, f64)>> {
+        match self {
+            Self::WordLevel(t) => t.tokenize_nbest(sequence, n),
+            Self::WordPiece(t) => t.tokenize_nbest(sequence, n),
+            Self::BPE(t) => t.tokenize_nbest(sequence, n),
+            Self::Unigram(t) => t.tokenize_nbest(sequence, n),
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -164,19 +223,35 @@ impl Trainer for TrainerWrapper {
         match self {
             Self::BpeTrainer(t) => match model {
                 ModelWrapper::BPE(bpe) => t.train(bpe),
-                _ => Err("BpeTrainer can only train a BPE".into()),
+                _ => Err(ModelError::IncompatibleTrainer {
+                    trainer: "BpeTrainer",
+                    model: "BPE",
+                }
+                .into()),
             },
             Self::WordPieceTrainer(t) => match model {
                 ModelWrapper::WordPiece(wp) => t.train(wp),
-                _ => Err("WordPieceTrainer can only train a WordPiece".into()),
+                _ => Err(ModelError::IncompatibleTrainer {
+                    trainer: "WordPieceTrainer",
+                    model: "WordPiece",
+                }
+                .into()),
             },
             Self::WordLevelTrainer(t) => match model {
                 ModelWrapper::WordLevel(wl) => t.train(wl),
-                _ => Err("WordLevelTrainer can only train a WordLevel".into()),
+                _ => Err(ModelError::IncompatibleTrainer {
+                    trainer: "WordLevelTrainer",
+                    model: "WordLevel",
+                }
+                .into()),
             },
             Self::UnigramTrainer(t) => match model {
                 ModelWrapper::Unigram(u) => t.train(u),
-                _ => Err("UnigramTrainer can only train a Unigram".into()),
+                _ => Err(ModelError::IncompatibleTrainer {
+                    trainer: "UnigramTrainer",
+                    model: "Unigram",
+                }
+                .into()),
             },
         }
     }
@@ -5,7 +5,7 @@ use std::cmp::{min, Ordering};
 use std::collections::BinaryHeap;
 use std::rc::Rc;
 
-type NodeRef = Rc<RefCell<Node>>;
+pub(super) type NodeRef = Rc<RefCell<Node>>;
 type HypothesisRef = Rc<RefCell<Hypothesis>>;
 type Agenda = BinaryHeap<Hypothesis>;
 
@@ -92,7 +92,7 @@ pub struct Node {
     length: usize,
     prev: Option<NodeRef>,
     backtrace_score: f64,
-    score: f64,
+    pub(super) score: f64,
 }
 
 impl PartialEq for Node {
@@ -390,7 +390,7 @@ impl<'a> Lattice<'a> {
                     let rid = rnode.borrow().node_id;
                     alpha[rid] = log_sum_exp(
                         alpha[rid],
-                        theta * (lnode.borrow().score + alpha[lid]),
+                        theta * lnode.borrow().score + alpha[lid],
                         *lnode == self.end_nodes[pos][0],
                     );
                 }
@@ -626,6 +626,25 @@ mod tests {
         assert!(lattice.nbest_tokens(0).is_empty());
         assert_eq!(lattice.nbest_tokens(1), vec![vec!["ABC"]]);
     }
+    #[test]
+    fn test_sample() {
+        let mut lattice = Lattice::from("ABC", 1, 2);
+        lattice.insert(0, 1, -1.0, 3); // A
+        lattice.insert(1, 1, -1.0, 4); // B
+        lattice.insert(2, 1, -1.0, 5); // C
+        lattice.insert(0, 3, -1.0, 6); // ABC
+
+        // Each path is sampled with a probability proportional to exp(theta * score)
+        let theta = 0.5_f64;
+        let p_abc = (-theta).exp();
+        let p_a_b_c = (theta * -3.0_f64).exp();
+        let n = 10_000;
+        let sampled = (0..n)
+            .filter(|_| lattice.sample_token(theta) == ["ABC"])
+            .count();
+        assert_approx_eq!(sampled as f64 / n as f64, p_abc / (p_abc + p_a_b_c), 0.02);
+    }
+
     #[test]
     fn test_log_sum_exp() {
         let mut x = 0.0;
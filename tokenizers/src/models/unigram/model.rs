@@ -1,11 +1,13 @@
 use super::{
-    lattice::Lattice,
+    lattice::{Lattice, NodeRef},
     trainer::UnigramTrainer,
     trie::{Trie, TrieBuilder},
 };
 use crate::tokenizer::{Model, Result, Token};
-use crate::utils::cache::Cache;
+use crate::utils::cache::{Cache, CachePolicy, CacheStats};
 
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs::read_to_string;
@@ -28,10 +30,19 @@ pub struct Unigram {
     fuse_unk: bool,
     is_optimized: bool,
     byte_fallback: bool,
+    /// Smoothing parameter for [subword regularization](https://arxiv.org/abs/1804.10959).
+    /// When set, each tokenization is sampled instead of being the best one.
+    alpha: Option<f64>,
+    /// The number of best segmentations to sample from when `alpha` is set. `None` samples
+    /// from all the possible segmentations, and `Some(0)` or `Some(1)` disables sampling.
+    nbest_size: Option<usize>,
 }
 impl PartialEq for Unigram {
     fn eq(&self, other: &Self) -> bool {
-        self.unk_id == other.unk_id && self.vocab == other.vocab
+        self.unk_id == other.unk_id
+            && self.vocab == other.vocab
+            && self.alpha == other.alpha
+            && self.nbest_size == other.nbest_size
     }
 }
 
@@ -52,6 +63,8 @@ impl Clone for Unigram {
             fuse_unk: self.fuse_unk,
             is_optimized: self.is_optimized,
             byte_fallback: self.byte_fallback,
+            alpha: self.alpha,
+            nbest_size: self.nbest_size,
         }
     }
 }
@@ -62,6 +75,8 @@ impl std::fmt::Debug for Unigram {
             .field("vocab", &self.vocab.len())
             .field("unk_id", &self.unk_id)
             .field("byte_fallback", &self.byte_fallback)
+            .field("alpha", &self.alpha)
+            .field("nbest_size", &self.nbest_size)
             .finish()
     }
 }
@@ -76,6 +91,8 @@ pub enum UnigramError {
     UnkIdNotInVocabulary,
     #[error("Encountered an unknown token but `unk_id` is missing")]
     MissingUnkId,
+    #[error("The `alpha` used for sampling must be a positive number")]
+    InvalidAlpha,
 }
 
 impl Default for Unigram {
@@ -103,10 +120,10 @@ impl Unigram {
 
         if let Some(unk_id) = unk_id {
             if vocab.is_empty() {
-                return Err(Box::new(UnigramError::EmptyVocabulary));
+                return Err(UnigramError::EmptyVocabulary.into());
             }
             if unk_id >= vocab.len() {
-                return Err(Box::new(UnigramError::UnkIdNotInVocabulary));
+                return Err(UnigramError::UnkIdNotInVocabulary.into());
             }
         }
         let bos_id = n + 1;
@@ -137,9 +154,57 @@ impl Unigram {
             cache: Cache::default(),
             is_optimized,
             byte_fallback,
+            alpha: None,
+            nbest_size: None,
         })
     }
 
+    /// Enable sentencepiece-style subword regularization, sampling each tokenization with
+    /// the given smoothing `alpha` from either the `nbest_size` best segmentations, or all of
+    /// them if `nbest_size` is `None`. Use `alpha = None` to go back to the best tokenization.
+    pub fn with_sampling(mut self, alpha: Option<f64>, nbest_size: Option<usize>) -> Result<Self> {
+        if let Some(alpha) = alpha {
+            if !alpha.is_finite() || alpha <= 0.0 {
+                return Err(UnigramError::InvalidAlpha.into());
+            }
+        }
+        self.alpha = alpha;
+        self.nbest_size = nbest_size;
+        Ok(self)
+    }
+
+    /// Replace the cache with a new one, using the given capacity and eviction policy. A
+    /// `capacity` of 0 disables caching.
+    pub fn with_cache(mut self, capacity: usize, policy: CachePolicy) -> Self {
+        self.cache = Cache::with_policy(capacity, policy);
+        self
+    }
+
+    /// The capacity of the cache
+    pub fn cache_capacity(&self) -> usize {
+        self.cache.capacity
+    }
+
+    /// The policy used to evict entries from the cache
+    pub fn cache_policy(&self) -> CachePolicy {
+        self.cache.policy
+    }
+
+    /// Get the hit, miss and eviction counters of the cache.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Reset the cache.
+    pub fn clear_cache(&self) {
+        self.cache.clear()
+    }
+
+    /// Whether the tokenization is sampled, instead of always being the best one
+    fn is_sampling(&self) -> bool {
+        self.alpha.is_some() && !matches!(self.nbest_size, Some(0) | Some(1))
+    }
+
     #[cfg(test)]
     pub(super) fn set_fuse_unk(&mut self, fuse_unk: bool) {
         self.fuse_unk = fuse_unk;
@@ -153,6 +218,18 @@ impl Unigram {
     pub fn byte_fallback(&self) -> bool {
         self.byte_fallback
     }
+    /// The smoothing parameter used for sampling, if enabled
+    pub fn alpha(&self) -> Option<f64> {
+        self.alpha
+    }
+    /// The number of best segmentations sampled from, `None` meaning all of them
+    pub fn nbest_size(&self) -> Option<usize> {
+        self.nbest_size
+    }
+    /// The id of the token used for the unknown parts of the input, if any
+    pub fn unk_id(&self) -> Option<usize> {
+        self.unk_id
+    }
     pub(super) fn len(&self) -> usize {
         self.vocab.len()
     }
@@ -222,6 +299,10 @@ impl Unigram {
         if sentence.is_empty() {
             return Ok(vec![]);
         }
+        if self.is_sampling() {
+            // Sampled results must never be cached
+            return self.encode_sampled(sentence);
+        }
         if let Some(result) = self.cache.get(sentence) {
             Ok(result.to_vec())
         } else {
@@ -336,10 +417,41 @@ impl Unigram {
     fn encode_unoptimized(&self, sentence: &str) -> Result<Vec<String>> {
         let mut lattice = Lattice::from(sentence, self.bos_id, self.eos_id);
         self.populate_nodes(&mut lattice);
+        let path = lattice.viterbi();
+        self.path_to_pieces(&lattice, &path)
+    }
+
+    fn encode_sampled(&self, sentence: &str) -> Result<Vec<String>> {
+        let alpha = self.alpha.unwrap_or(1.0);
+        let mut lattice = Lattice::from(sentence, self.bos_id, self.eos_id);
+        self.populate_nodes(&mut lattice);
+        let path = match self.nbest_size {
+            None => lattice.sample(alpha),
+            Some(n) => {
+                // https://github.com/google/sentencepiece/blob/d48247191a6d50e469ed1a4a36e877befffd1851/src/unigram_model.cc#L521
+                let mut nbests = lattice.nbest(n);
+                let scores = nbests
+                    .iter()
+                    .map(|path| alpha * path.iter().map(|n| n.borrow().score).sum::<f64>())
+                    .collect::<Vec<_>>();
+                let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let probs = scores.iter().map(|s| (s - max).exp()).collect::<Vec<_>>();
+                match WeightedIndex::new(&probs) {
+                    Ok(dist) => nbests.swap_remove(dist.sample(&mut thread_rng())),
+                    Err(_) => nbests.into_iter().next().unwrap_or_default(),
+                }
+            }
+        };
+        self.path_to_pieces(&lattice, &path)
+    }
+
+    /// Converts a path of the lattice to the matching pieces, fusing the unknown tokens if
+    /// needed.
+    fn path_to_pieces(&self, lattice: &Lattice, path: &[NodeRef]) -> Result<Vec<String>> {
         if self.fuse_unk {
             let mut results = vec![];
             let mut token = String::new();
-            for node in lattice.viterbi().iter() {
+            for node in path.iter() {
                 let item = lattice.piece(&node.borrow());
                 if node.borrow().id == self.unk_id.ok_or(UnigramError::MissingUnkId)? {
                     token.push_str(&item);
@@ -356,7 +468,10 @@ impl Unigram {
             }
             Ok(results)
         } else {
-            Ok(lattice.tokens())
+            Ok(path
+                .iter()
+                .map(|node| lattice.piece(&node.borrow()))
+                .collect())
         }
     }
 
@@ -413,6 +528,56 @@ impl Model for Unigram {
 
     fn tokenize(&self, sentence: &str) -> Result<Vec<Token>> {
         let str_tokens = self.encode(sentence)?;
+        self.pieces_to_tokens(str_tokens)
+    }
+
+    fn tokenize_nbest(&self, sentence: &str, n: usize) -> Result<Vec<(Vec<Token>, f64)>> {
+        if sentence.is_empty() {
+            return Ok(vec![(vec![], 0.0)]);
+        }
+        let mut lattice = Lattice::from(sentence, self.bos_id, self.eos_id);
+        self.populate_nodes(&mut lattice);
+        lattice
+            .nbest(n)
+            .into_iter()
+            .map(|path| {
+                let score = path.iter().map(|node| node.borrow().score).sum();
+                let pieces = self.path_to_pieces(&lattice, &path)?;
+                Ok((self.pieces_to_tokens(pieces)?, score))
+            })
+            .collect()
+    }
+
+    fn token_to_id(&self, token: &str) -> Option<u32> {
+        self.token_to_ids.get(token).copied()
+    }
+
+    fn id_to_token(&self, id: u32) -> Option<String> {
+        self.vocab.get(id as usize).map(|item| item.0.clone())
+    }
+
+    fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
+        let name = match name {
+            Some(name) => format!("{}-unigram.json", name),
+            None => "unigram.json".to_string(),
+        };
+        let mut fullpath = PathBuf::new();
+        fullpath.push(folder);
+        fullpath.push(name);
+        let string = serde_json::to_string_pretty(self)?;
+        std::fs::write(&fullpath, string)?;
+        Ok(vec![fullpath])
+    }
+
+    fn get_trainer(&self) -> Self::Trainer {
+        UnigramTrainer::default()
+    }
+}
+
+impl Unigram {
+    /// Converts the pieces of a tokenization to the matching `Token`s, using the byte
+    /// fallback when relevant.
+    fn pieces_to_tokens(&self, str_tokens: Vec<String>) -> Result<Vec<Token>> {
         let mut offset = 0;
         let mut tokens = Vec::with_capacity(str_tokens.len());
         for string in str_tokens {
@@ -446,31 +611,6 @@ impl Model for Unigram {
         }
         Ok(tokens)
     }
-
-    fn token_to_id(&self, token: &str) -> Option<u32> {
-        self.token_to_ids.get(token).copied()
-    }
-
-    fn id_to_token(&self, id: u32) -> Option<String> {
-        self.vocab.get(id as usize).map(|item| item.0.clone())
-    }
-
-    fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
-        let name = match name {
-            Some(name) => format!("{}-unigram.json", name),
-            None => "unigram.json".to_string(),
-        };
-        let mut fullpath = PathBuf::new();
-        fullpath.push(folder);
-        fullpath.push(name);
-        let string = serde_json::to_string_pretty(self)?;
-        std::fs::write(&fullpath, string)?;
-        Ok(vec![fullpath])
-    }
-
-    fn get_trainer(&self) -> Self::Trainer {
-        UnigramTrainer::default()
-    }
 }
 
 #[cfg(test)]
@@ -632,4 +772,48 @@ mod tests {
         let tokens = unigram.tokenize("?é").unwrap();
         assert_eq!(tokens[0].id, 0);
     }
+
+    #[test]
+    fn test_sampling() {
+        let sentencepieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -1.0),
+            ("b".to_string(), -1.0),
+            ("ab".to_string(), -1.5),
+        ];
+        let model = Unigram::from(sentencepieces, Some(0), false).unwrap();
+        assert_eq!(model.encode("ab").unwrap(), vec!["ab"]);
+
+        let segmentations = |model: &Unigram| {
+            let mut seen = std::collections::HashSet::new();
+            for _ in 0..200 {
+                let result = model.encode("ab").unwrap();
+                assert_eq!(result.concat(), "ab");
+                seen.insert(result);
+            }
+            seen
+        };
+
+        // Sampling from the whole lattice
+        let sampling = model.clone().with_sampling(Some(1.0), None).unwrap();
+        assert_eq!(segmentations(&sampling).len(), 2);
+
+        // Sampling from the n-best
+        let sampling = model.clone().with_sampling(Some(1.0), Some(2)).unwrap();
+        assert_eq!(segmentations(&sampling).len(), 2);
+
+        // A nbest_size of 1 is the same as not sampling
+        let sampling = model.clone().with_sampling(Some(1.0), Some(1)).unwrap();
+        assert_eq!(segmentations(&sampling).len(), 1);
+
+        // A huge alpha always picks the best one
+        let sampling = model.clone().with_sampling(Some(1000.0), None).unwrap();
+        assert_eq!(
+            segmentations(&sampling).into_iter().collect::<Vec<_>>(),
+            vec![vec!["ab".to_string()]]
+        );
+
+        assert!(model.clone().with_sampling(Some(0.0), None).is_err());
+        assert!(model.with_sampling(Some(f64::NAN), None).is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use super::model::Unigram;
+use crate::utils::cache::{CachePolicy, DEFAULT_CACHE_CAPACITY};
 use serde::{
     de::{Error, MapAccess, Visitor},
     ser::SerializeStruct,
@@ -10,12 +11,33 @@ impl Serialize for Unigram {
     where
         S: Serializer,
     {
-        let mut model = serializer.serialize_struct("Unigram", 3)?;
+        let mut model = serializer.serialize_struct("Unigram", 8)?;
 
         model.serialize_field("type", "Unigram")?;
         model.serialize_field("unk_id", &self.unk_id)?;
         model.serialize_field("vocab", &self.vocab)?;
         model.serialize_field("byte_fallback", &self.byte_fallback())?;
+        // The sampling and cache parameters are only written when they are not the defaults
+        if let Some(alpha) = self.alpha() {
+            model.serialize_field("alpha", &alpha)?;
+        } else {
+            model.skip_field("alpha")?;
+        }
+        if let Some(nbest_size) = self.nbest_size() {
+            model.serialize_field("nbest_size", &nbest_size)?;
+        } else {
+            model.skip_field("nbest_size")?;
+        }
+        if self.cache_capacity() != DEFAULT_CACHE_CAPACITY {
+            model.serialize_field("cache_capacity", &self.cache_capacity())?;
+        } else {
+            model.skip_field("cache_capacity")?;
+        }
+        if self.cache_policy() != CachePolicy::default() {
+            model.serialize_field("cache_policy", &self.cache_policy())?;
+        } else {
+            model.skip_field("cache_policy")?;
+        }
 
         model.end()
     }
@@ -28,7 +50,16 @@ impl<'de> Deserialize<'de> for Unigram {
     {
         deserializer.deserialize_struct(
             "Unigram",
-            &["type", "vocab", "unk_id", "byte_fallback"],
+            &[
+                "type",
+                "vocab",
+                "unk_id",
+                "byte_fallback",
+                "alpha",
+                "nbest_size",
+                "cache_capacity",
+                "cache_policy",
+            ],
             UnigramVisitor,
         )
     }
@@ -49,12 +80,20 @@ impl<'de> Visitor<'de> for UnigramVisitor {
         let mut vocab: Option<Vec<(String, f64)>> = None;
         let mut unk_id: Option<usize> = None;
         let mut byte_fallback: bool = false;
+        let mut alpha: Option<f64> = None;
+        let mut nbest_size: Option<usize> = None;
+        let mut cache_capacity = DEFAULT_CACHE_CAPACITY;
+        let mut cache_policy = CachePolicy::default();
         while let Some(key) = map.next_key::<String>()? {
             match key.as_ref() {
                 "unk_id" => {
                     unk_id = map.next_value()?;
                 }
                 "byte_fallback" => byte_fallback = map.next_value()?,
+                "alpha" => alpha = map.next_value()?,
+                "nbest_size" => nbest_size = map.next_value()?,
+                "cache_capacity" => cache_capacity = map.next_value()?,
+                "cache_policy" => cache_policy = map.next_value()?,
                 "vocab" => vocab = Some(map.next_value()?),
                 "type" => match map.next_value()? {
                     "Unigram" => {}
@@ -70,7 +109,10 @@ impl<'de> Visitor<'de> for UnigramVisitor {
         }
         match (vocab, unk_id, byte_fallback) {
             (Some(vocab), unk_id, byte_fallback) => Ok(Unigram::from(vocab, unk_id, byte_fallback)
-                .map_err(|err| Error::custom(format!("Unable to load vocab {:?}", err)))?),
+                .map_err(|err| Error::custom(format!("Unable to load vocab {:?}", err)))?
+                .with_sampling(alpha, nbest_size)
+                .map_err(|err| Error::custom(format!("Invalid sampling parameters {:?}", err)))?
+                .with_cache(cache_capacity, cache_policy)),
             (None, _, _) => Err(Error::custom("Missing vocab")),
         }
     }
@@ -102,6 +144,69 @@ mod test {
         assert_eq!(model, reconstructed);
     }
 
+    #[test]
+    fn test_serialization_sampling() {
+        let vocab = vec![("<unk>".to_string(), 0.0), ("a".to_string(), -0.5)];
+        let model = Unigram::from(vocab, Some(0), false)
+            .unwrap()
+            .with_sampling(Some(0.1), Some(64))
+            .unwrap();
+
+        let data = serde_json::to_string(&model).unwrap();
+        assert_eq!(
+            data,
+            r#"{"type":"Unigram","unk_id":0,"vocab":[["<unk>",0.0],["a",-0.5]],"byte_fallback":false,"alpha":0.1,"nbest_size":64}"#
+        );
+        let reconstructed = serde_json::from_str(&data).unwrap();
+        assert_eq!(model, reconstructed);
+
+        // Older versions without sampling still load
+        let data = r#"{"type":"Unigram","unk_id":0,"vocab":[["<unk>",0.0],["a",-0.5]],"byte_fallback":false}"#;
+        let reconstructed: Unigram = serde_json::from_str(data).unwrap();
+        assert_eq!(reconstructed.alpha(), None);
+        assert_eq!(reconstructed.nbest_size(), None);
+
+        let data =
+            r#"{"type":"Unigram","unk_id":0,"vocab":[["<unk>",0.0],["a",-0.5]],"alpha":-1.0}"#;
+        assert!(serde_json::from_str::<Unigram>(data).is_err());
+    }
+
+    #[test]
+    fn test_tokenizer_serialization_sampling() {
+        let vocab = vec![("<unk>".to_string(), 0.0), ("a".to_string(), -0.5)];
+        let model = Unigram::from(vocab, Some(0), false)
+            .unwrap()
+            .with_sampling(Some(0.1), Some(64))
+            .unwrap();
+        let tokenizer = crate::Tokenizer::new(model);
+
+        let reconstructed: crate::Tokenizer = tokenizer.to_string(false).unwrap().parse().unwrap();
+        match reconstructed.get_model() {
+            crate::models::ModelWrapper::Unigram(unigram) => {
+                assert_eq!(unigram.alpha(), Some(0.1));
+                assert_eq!(unigram.nbest_size(), Some(64));
+            }
+            _ => panic!("Expected a Unigram"),
+        }
+    }
+
+    #[test]
+    fn test_serialization_cache() {
+        let vocab = vec![("<unk>".to_string(), 0.0), ("a".to_string(), -0.5)];
+        let model = Unigram::from(vocab, Some(0), false)
+            .unwrap()
+            .with_cache(42, CachePolicy::Clock);
+
+        let data = serde_json::to_string(&model).unwrap();
+        assert_eq!(
+            data,
+            r#"{"type":"Unigram","unk_id":0,"vocab":[["<unk>",0.0],["a",-0.5]],"byte_fallback":false,"cache_capacity":42,"cache_policy":"Clock"}"#
+        );
+        let reconstructed: Unigram = serde_json::from_str(&data).unwrap();
+        assert_eq!(reconstructed.cache_capacity(), 42);
+        assert_eq!(reconstructed.cache_policy(), CachePolicy::Clock);
+    }
+
     #[test]
     fn test_serialization_no_unk_id() {
         let vocab = vec![("a".to_string(), -0.5)];
@@ -2,6 +2,7 @@ use crate::models::unigram::{lattice::Lattice, model::Unigram};
 use crate::tokenizer::{AddedToken, Result, Trainer};
 use crate::utils::parallelism::*;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::utils::word_counts::{count_words, WordCountsConfig};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
@@ -67,6 +68,9 @@ pub struct UnigramTrainer {
     pub max_piece_length: usize,
     #[builder(default = "1_000_000")]
     seed_size: usize,
+    #[builder(default)]
+    #[serde(default)]
+    pub word_counts: WordCountsConfig,
     #[builder(default = "HashMap::new()")]
     words: HashMap<String, u32>,
 }
@@ -565,7 +569,7 @@ impl UnigramTrainer {
         self.update_progress(&progress, expected_updates, "EM training");
         let required_chars = self.required_chars(&sentences);
         if required_chars.len() as u32 > self.vocab_size {
-            return Err(Box::new(UnigramTrainerError::VocabularyTooSmall));
+            return Err(UnigramTrainerError::VocabularyTooSmall.into());
         }
         let mut new_model = Unigram::from(pieces.clone(), Some(0), false)?;
         loop {
@@ -605,7 +609,11 @@ impl UnigramTrainer {
         self.finalize_progress(&progress, expected_updates);
 
         // Finally, adjusts the size of sentencepices to be |vocab_size|.
-        *model = self.finalize(new_model, required_chars)?;
+        // The sampling parameters and the cache are the ones of the model being trained
+        *model = self
+            .finalize(new_model, required_chars)?
+            .with_sampling(model.alpha(), model.nbest_size())?
+            .with_cache(model.cache_capacity(), model.cache_policy());
 
         Ok(self.special_tokens.clone())
     }
@@ -631,28 +639,7 @@ impl Trainer for UnigramTrainer {
         S: AsRef<str> + Send,
         F: Fn(&str) -> Result<Vec<String>> + Sync,
     {
-        let words: Result<HashMap<String, u32>> = iterator
-            .maybe_par_bridge()
-            .map(|sequence| {
-                let words = process(sequence.as_ref())?;
-                let mut map = HashMap::new();
-                for word in words {
-                    map.entry(word).and_modify(|c| *c += 1).or_insert(1);
-                }
-                Ok(map)
-            })
-            .reduce(
-                || Ok(HashMap::new()),
-                |acc, ws| {
-                    let mut acc = acc?;
-                    for (k, v) in ws? {
-                        acc.entry(k).and_modify(|c| *c += v).or_insert(v);
-                    }
-                    Ok(acc)
-                },
-            );
-
-        self.words = words?;
+        self.words = count_words(iterator, process, &self.word_counts)?;
         Ok(())
     }
 }
@@ -660,6 +647,7 @@ impl Trainer for UnigramTrainer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::cache::CachePolicy;
     use assert_approx_eq::assert_approx_eq;
     use std::iter::FromIterator;
 
@@ -812,6 +800,27 @@ mod tests {
         assert_eq!(pieces.next(), Some(&("[CLS]".into(), 0.0)));
     }
 
+    #[test]
+    fn test_keeps_sampling_and_cache() {
+        let trainer = UnigramTrainerBuilder::default()
+            .show_progress(false)
+            .build()
+            .unwrap();
+
+        let mut unigram = Unigram::default()
+            .with_sampling(Some(0.1), Some(64))
+            .unwrap()
+            .with_cache(42, CachePolicy::Clock);
+        trainer
+            .do_train(vec![("The".into(), 12), ("are".into(), 11)], &mut unigram)
+            .unwrap();
+
+        assert_eq!(unigram.alpha(), Some(0.1));
+        assert_eq!(unigram.nbest_size(), Some(64));
+        assert_eq!(unigram.cache_capacity(), 42);
+        assert_eq!(unigram.cache_policy(), CachePolicy::Clock);
+    }
+
     #[test]
     fn test_to_log_prob() {
         let mut a = vec![("".to_string(), 1.0), ("".to_string(), 2.0)];
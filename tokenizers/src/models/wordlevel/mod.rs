@@ -133,7 +133,7 @@ impl WordLevel {
                     }
                 }
             }
-            _ => return Err(Box::new(Error::BadVocabulary)),
+            _ => return Err(Error::BadVocabulary.into()),
         };
         Ok(vocab)
     }
@@ -172,10 +172,18 @@ impl Model for WordLevel {
                 offsets: (0, token.len()),
             }])
         } else {
-            Err(Box::new(Error::MissingUnkToken))
+            Err(Error::MissingUnkToken.into())
         }
     }
 
+    fn tokenize_ids(&self, token: &str) -> Result<Vec<u32>> {
+        self.vocab
+            .get(token)
+            .or_else(|| self.vocab.get(&self.unk_token))
+            .map(|id| vec![*id])
+            .ok_or_else(|| Error::MissingUnkToken.into())
+    }
+
     fn token_to_id(&self, token: &str) -> Option<u32> {
         self.vocab.get(token).copied()
     }
@@ -218,6 +226,7 @@ impl Model for WordLevel {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::ModelError;
 
     #[test]
     fn test_tokenize_unk() {
@@ -245,6 +254,9 @@ mod tests {
         assert_eq!(tokens, vec![Token::new(0u32, "a".into(), (0, 1)),]);
 
         let error = wordlevel.tokenize("c").err().unwrap();
-        assert!(error.is::<Error>());
+        assert!(matches!(
+            error,
+            crate::Error::Model(ModelError::WordLevel(Error::MissingUnkToken))
+        ));
     }
 }
@@ -1,6 +1,7 @@
 use super::WordPiece;
 use crate::models::bpe::{BpeTrainer, BpeTrainerBuilder, BPE};
 use crate::tokenizer::{AddedToken, Result, Trainer};
+use crate::utils::word_counts::WordCountsConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
@@ -80,6 +81,13 @@ impl WordPieceTrainerBuilder {
         self
     }
 
+    /// Set how the words are counted before training
+    #[must_use]
+    pub fn word_counts(mut self, config: WordCountsConfig) -> Self {
+        self.bpe_trainer_builder = self.bpe_trainer_builder.word_counts(config);
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> WordPieceTrainer {
         let bpe_trainer = self.bpe_trainer_builder.build();
@@ -158,6 +166,14 @@ impl WordPieceTrainer {
         self.bpe_trainer.end_of_word_suffix = suffix;
     }
 
+    pub fn word_counts(&self) -> &WordCountsConfig {
+        &self.bpe_trainer.word_counts
+    }
+
+    pub fn set_word_counts(&mut self, config: WordCountsConfig) {
+        self.bpe_trainer.word_counts = config;
+    }
+
     pub fn builder() -> WordPieceTrainerBuilder {
         WordPieceTrainerBuilder::default()
     }
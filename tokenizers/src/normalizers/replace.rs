@@ -1,5 +1,5 @@
 use crate::tokenizer::pattern::Pattern;
-use crate::tokenizer::Decoder;
+use crate::tokenizer::{AlignedString, Decoder};
 use crate::tokenizer::{NormalizedString, Normalizer, Result};
 use crate::utils::SysRegex;
 use serde::{Deserialize, Serialize};
@@ -33,7 +33,7 @@ struct ReplaceDeserializer {
 }
 
 impl std::convert::TryFrom<ReplaceDeserializer> for Replace {
-    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Error = crate::Error;
 
     fn try_from(v: ReplaceDeserializer) -> Result<Self> {
         Self::new(v.pattern, v.content)
@@ -85,22 +85,33 @@ impl Normalizer for Replace {
     }
 }
 
+impl Replace {
+    fn decode_token(&self, token: &str) -> Result<String> {
+        let mut new_token = "".to_string();
+
+        for ((start, stop), is_match) in (&self.regex).find_matches(token)? {
+            if is_match {
+                new_token.push_str(&self.content);
+            } else {
+                new_token.push_str(&token[start..stop]);
+            }
+        }
+        Ok(new_token)
+    }
+}
+
 impl Decoder for Replace {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
         tokens
             .into_iter()
-            .map(|token| -> Result<String> {
-                let mut new_token = "".to_string();
-
-                for ((start, stop), is_match) in (&self.regex).find_matches(&token)? {
-                    if is_match {
-                        new_token.push_str(&self.content);
-                    } else {
-                        new_token.push_str(&token[start..stop]);
-                    }
-                }
-                Ok(new_token)
-            })
+            .map(|token| self.decode_token(&token))
+            .collect()
+    }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        tokens
+            .iter()
+            .map(|token| token.try_map(|token| self.decode_token(token)))
             .collect()
     }
 }
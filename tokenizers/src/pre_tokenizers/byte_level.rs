@@ -4,7 +4,7 @@ use crate::utils::SysRegex;
 use serde::{Deserialize, Serialize};
 
 use crate::tokenizer::{
-    Decoder, Encoding, PostProcessor, PreTokenizedString, PreTokenizer, Result,
+    AlignedString, Decoder, Encoding, PostProcessor, PreTokenizedString, PreTokenizer, Result,
     SplitDelimiterBehavior,
 };
 use crate::utils::macro_rules_attribute;
@@ -37,8 +37,8 @@ lazy_static! {
         r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+"
     )
     .unwrap();
-    static ref BYTES_CHAR: HashMap<u8, char> = bytes_char();
-    static ref CHAR_BYTES: HashMap<char, u8> =
+    pub(crate) static ref BYTES_CHAR: HashMap<u8, char> = bytes_char();
+    pub(crate) static ref CHAR_BYTES: HashMap<char, u8> =
         bytes_char().into_iter().map(|(c, b)| (b, c)).collect();
 }
 
@@ -166,6 +166,27 @@ impl Decoder for ByteLevel {
             .collect::<Vec<u8>>();
         Ok(vec![String::from_utf8_lossy(&toks).to_string()])
     }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        let mut bytes = vec![];
+        for token in &tokens {
+            // Like in `decode_chain`, a token with any char outside of the alphabet is kept as is
+            let in_alphabet = token.chars().all(|(c, _)| CHAR_BYTES.contains_key(&c));
+            for (c, sources) in token.chars() {
+                if in_alphabet {
+                    bytes.push((CHAR_BYTES[&c], sources));
+                } else {
+                    let mut buffer = [0; 4];
+                    bytes.extend(
+                        c.encode_utf8(&mut buffer)
+                            .bytes()
+                            .map(|b| (b, sources.clone())),
+                    );
+                }
+            }
+        }
+        Ok(vec![AlignedString::from_utf8_lossy(&bytes)])
+    }
 }
 
 /// As a `PostProcessor`, `ByteLevel` is in charge of trimming the offsets if necessary.
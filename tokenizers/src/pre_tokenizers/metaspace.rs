@@ -1,6 +1,8 @@
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::tokenizer::{Decoder, PreTokenizedString, PreTokenizer, Result, SplitDelimiterBehavior};
+use crate::tokenizer::{
+    AlignedString, Decoder, PreTokenizedString, PreTokenizer, Result, SplitDelimiterBehavior,
+};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Eq)]
 /// Replaces all the whitespaces by the provided meta character and then
@@ -76,27 +78,40 @@ impl PreTokenizer for Metaspace {
     }
 }
 
+impl Metaspace {
+    /// Decode the token at the given index
+    fn decode_token(&self, i: usize, token: &str) -> String {
+        token
+            .chars()
+            .flat_map(|c| {
+                if c == self.replacement {
+                    if i == 0 && self.add_prefix_space {
+                        None
+                    } else {
+                        Some(' ')
+                    }
+                } else {
+                    Some(c)
+                }
+            })
+            .collect()
+    }
+}
+
 impl Decoder for Metaspace {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
         Ok(tokens
             .iter()
             .enumerate()
-            .map(|(i, token)| {
-                token
-                    .chars()
-                    .flat_map(|c| {
-                        if c == self.replacement {
-                            if i == 0 && self.add_prefix_space {
-                                None
-                            } else {
-                                Some(' ')
-                            }
-                        } else {
-                            Some(c)
-                        }
-                    })
-                    .collect::<String>()
-            })
+            .map(|(i, token)| self.decode_token(i, token))
+            .collect())
+    }
+
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        Ok(tokens
+            .iter()
+            .enumerate()
+            .map(|(i, token)| token.map(|token| self.decode_token(i, token)))
             .collect())
     }
 }
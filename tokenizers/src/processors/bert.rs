@@ -23,6 +23,14 @@ impl BertProcessing {
     pub fn new(sep: (String, u32), cls: (String, u32)) -> Self {
         Self { sep, cls }
     }
+
+    pub fn get_sep_copy(&self) -> (String, u32) {
+        (self.sep.0.clone(), self.sep.1)
+    }
+
+    pub fn get_cls_copy(&self) -> (String, u32) {
+        (self.cls.0.clone(), self.cls.1)
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -40,6 +48,10 @@ impl PostProcessor for BertProcessing {
         }
     }
 
+    fn added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        Ok(n_sequences.max(1) + 1)
+    }
+
     fn process_encodings(
         &self,
         mut encodings: Vec<Encoding>,
@@ -179,6 +191,14 @@ impl PostProcessor for BertProcessing {
 
         Ok(encodings)
     }
+
+    fn replace_special_token(&mut self, token: &str, new_token: &str, new_id: u32) {
+        for special in [&mut self.sep, &mut self.cls] {
+            if special.0 == token {
+                *special = (new_token.to_owned(), new_id);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,390 @@
+//! # Chat Processing
+//!
+//! Renders the turns of a conversation into a single `Encoding`, wrapping each of them in the
+//! special tokens of its role, like `<|user|> Hello <|end|>`.
+//!
+//! The turns are encoded with [`TokenizerImpl::encode_chat`], which also gives the mask of
+//! the tokens belonging to the assistant turns, so that a training loss can be limited to
+//! them:
+//! ```
+//! # use tokenizers::processors::chat::{ChatProcessing, ChatRole};
+//! let chat = ChatProcessing::builder()
+//!     .role("user", ChatRole::new(vec!["<|user|>"], vec!["<|end|>"]))
+//!     .role(
+//!         "assistant",
+//!         ChatRole::new(vec!["<|assistant|>"], vec!["<|end|>"])
+//!             .type_id(1)
+//!             .assistant(true),
+//!     )
+//!     .special_tokens(vec![("<|user|>", 1), ("<|assistant|>", 2), ("<|end|>", 3)])
+//!     .build()
+//!     .unwrap();
+//! ```
+//!
+//! When truncating, the tokens are removed from the longest turns first by default, which
+//! usually cuts the answers of the assistant. With `.truncation(ChatTruncation::OldestFirst)`,
+//! the oldest turns get dropped first instead.
+//!
+//! Other inputs are processed as if there were no post-processor.
+//!
+//! [`TokenizerImpl::encode_chat`]: ../../tokenizer/struct.TokenizerImpl.html#method.encode_chat
+//!
+use crate::processors::template::Tokens;
+use crate::tokenizer::{
+    ChatEncoding, ChatTruncation, Encoding, PostProcessor, ProcessorError, Result,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+/// How the turns of a role get rendered
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChatRole {
+    /// The special tokens added before each turn, by their id in the `special_tokens`
+    #[serde(default)]
+    pub prefix: Vec<String>,
+    /// The special tokens added after each turn, by their id in the `special_tokens`
+    #[serde(default)]
+    pub suffix: Vec<String>,
+    /// The type id of all the tokens of a turn
+    #[serde(default)]
+    pub type_id: u32,
+    /// Whether the turns are marked in the assistant mask. The prefix is never marked, while
+    /// the suffix is, so that a model learns to end its turns.
+    #[serde(default)]
+    pub assistant: bool,
+}
+
+impl ChatRole {
+    pub fn new<P: Into<String>, S: Into<String>>(prefix: Vec<P>, suffix: Vec<S>) -> Self {
+        Self {
+            prefix: prefix.into_iter().map(|p| p.into()).collect(),
+            suffix: suffix.into_iter().map(|s| s.into()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn type_id(mut self, type_id: u32) -> Self {
+        self.type_id = type_id;
+        self
+    }
+
+    #[must_use]
+    pub fn assistant(mut self, assistant: bool) -> Self {
+        self.assistant = assistant;
+        self
+    }
+}
+
+/// This PostProcessor wraps each turn of a conversation in the special tokens of its role,
+/// before merging them in the final `Encoding`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Builder, Serialize, Deserialize)]
+#[serde(tag = "type", try_from = "ChatProcessingDeserializer")]
+#[builder(build_fn(validate = "Self::validate", error = "ProcessorError"))]
+pub struct ChatProcessing {
+    #[builder(setter(custom), default)]
+    #[serde(serialize_with = "crate::utils::ordered_map")]
+    roles: HashMap<String, ChatRole>,
+    #[builder(setter(into), default)]
+    special_tokens: Tokens,
+    /// How the conversations that are too long get truncated
+    #[builder(default)]
+    truncation: ChatTruncation,
+}
+
+impl ChatProcessingBuilder {
+    /// Add the given role, replacing any previous one with the same name
+    pub fn role<S: Into<String>>(&mut self, name: S, role: ChatRole) -> &mut Self {
+        self.roles
+            .get_or_insert_with(HashMap::new)
+            .insert(name.into(), role);
+        self
+    }
+
+    fn validate(&self) -> std::result::Result<(), ProcessorError> {
+        let empty = HashMap::new();
+        let missing = self
+            .roles
+            .as_ref()
+            .unwrap_or(&empty)
+            .values()
+            .flat_map(|role| role.prefix.iter().chain(&role.suffix))
+            .filter(|id| {
+                !self
+                    .special_tokens
+                    .as_ref()
+                    .is_some_and(|tokens| tokens.0.contains_key(*id))
+            })
+            .collect::<HashSet<_>>();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ProcessorError::MissingSpecialTokens(
+                missing.into_iter().sorted().cloned().collect(),
+            ))
+        }
+    }
+}
+
+/// We deserialize through the builder, so that the roles get validated against the
+/// special tokens
+#[doc(hidden)]
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+struct ChatProcessingDeserializer {
+    roles: HashMap<String, ChatRole>,
+    special_tokens: Tokens,
+    #[serde(default)]
+    truncation: ChatTruncation,
+}
+impl TryFrom<ChatProcessingDeserializer> for ChatProcessing {
+    type Error = ProcessorError;
+
+    fn try_from(c: ChatProcessingDeserializer) -> std::result::Result<Self, Self::Error> {
+        let mut builder = Self::builder();
+        for (name, role) in c.roles {
+            builder.role(name, role);
+        }
+        builder
+            .special_tokens(c.special_tokens)
+            .truncation(c.truncation)
+            .build()
+    }
+}
+
+impl ChatProcessing {
+    pub fn builder() -> ChatProcessingBuilder {
+        ChatProcessingBuilder::default()
+    }
+
+    pub fn get_role(&self, name: &str) -> Option<&ChatRole> {
+        self.roles.get(name)
+    }
+
+    pub fn get_special_tokens(&self) -> &Tokens {
+        &self.special_tokens
+    }
+
+    pub fn get_truncation(&self) -> ChatTruncation {
+        self.truncation
+    }
+
+    fn role(&self, name: &str) -> Result<&ChatRole> {
+        self.roles
+            .get(name)
+            .ok_or_else(|| ProcessorError::UnknownRole(name.to_owned()).into())
+    }
+
+    fn count_added(&self, ids: &[String]) -> usize {
+        ids.iter()
+            .map(|id| self.special_tokens.0.get(id).map_or(0, |s| s.ids.len()))
+            .sum()
+    }
+
+    fn special_encoding(&self, ids: &[String], type_id: u32) -> Result<Encoding> {
+        let mut encoding_ids = vec![];
+        let mut tokens = vec![];
+        for id in ids {
+            let token = self
+                .special_tokens
+                .0
+                .get(id)
+                .ok_or_else(|| ProcessorError::MissingSpecialTokens(vec![id.clone()]))?;
+            encoding_ids.extend(&token.ids);
+            tokens.extend(token.tokens.iter().cloned());
+        }
+        let len = encoding_ids.len();
+        Ok(Encoding::new(
+            encoding_ids,
+            vec![type_id; len],
+            tokens,
+            vec![None; len],
+            vec![(0, 0); len],
+            vec![1; len],
+            vec![1; len],
+            vec![],
+            HashMap::new(),
+        ))
+    }
+}
+
+impl PostProcessor for ChatProcessing {
+    fn added_tokens(&self, _is_pair: bool) -> usize {
+        0
+    }
+
+    fn added_tokens_chat(&self, roles: &[String]) -> Result<usize> {
+        roles
+            .iter()
+            .map(|name| {
+                let role = self.role(name)?;
+                Ok(self.count_added(&role.prefix) + self.count_added(&role.suffix))
+            })
+            .sum()
+    }
+
+    fn process_encodings(
+        &self,
+        encodings: Vec<Encoding>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        <dyn PostProcessor>::default_process(encodings, add_special_tokens)
+    }
+
+    fn chat_truncation(&self) -> ChatTruncation {
+        self.truncation
+    }
+
+    fn process_chat(
+        &self,
+        turns: Vec<(String, Encoding)>,
+        add_special_tokens: bool,
+    ) -> Result<ChatEncoding> {
+        let mut encodings = Vec::with_capacity(turns.len() * 3);
+        let mut assistant_mask = vec![];
+        for (i, (name, mut encoding)) in turns.into_iter().enumerate() {
+            let role = self.role(&name)?;
+            let mask = u32::from(role.assistant);
+
+            encoding.set_sequence_id(i);
+            encoding.set_type_ids(vec![role.type_id; encoding.len()]);
+            for overflowing in encoding.get_overflowing_mut() {
+                overflowing.set_sequence_id(i);
+                overflowing.set_type_ids(vec![role.type_id; overflowing.len()]);
+            }
+
+            if add_special_tokens {
+                let prefix = self.special_encoding(&role.prefix, role.type_id)?;
+                assistant_mask.resize(assistant_mask.len() + prefix.len(), 0);
+                encodings.push(prefix);
+            }
+            assistant_mask.resize(assistant_mask.len() + encoding.len(), mask);
+            encodings.push(encoding);
+            if add_special_tokens {
+                let suffix = self.special_encoding(&role.suffix, role.type_id)?;
+                assistant_mask.resize(assistant_mask.len() + suffix.len(), mask);
+                encodings.push(suffix);
+            }
+        }
+
+        Ok(ChatEncoding {
+            encoding: Encoding::merge(encodings, false),
+            assistant_mask,
+        })
+    }
+
+    fn replace_special_token(&mut self, token: &str, new_token: &str, new_id: u32) {
+        self.special_tokens.replace(token, new_token, new_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat() -> ChatProcessing {
+        ChatProcessing::builder()
+            .role("user", ChatRole::new(vec!["<|user|>"], vec!["<|end|>"]))
+            .role(
+                "assistant",
+                ChatRole::new(vec!["<|assistant|>"], vec!["<|end|>"])
+                    .type_id(1)
+                    .assistant(true),
+            )
+            .special_tokens(vec![("<|user|>", 1), ("<|assistant|>", 2), ("<|end|>", 3)])
+            .build()
+            .unwrap()
+    }
+
+    fn turn(ids: &[u32]) -> Encoding {
+        let len = ids.len();
+        Encoding::new(
+            ids.to_vec(),
+            vec![0; len],
+            ids.iter().map(|id| id.to_string()).collect(),
+            (0..len as u32).map(Some).collect(),
+            (0..len).map(|i| (i, i + 1)).collect(),
+            vec![0; len],
+            vec![1; len],
+            vec![],
+            HashMap::new(),
+        )
+    }
+
+    #[test]
+    fn process_chat() {
+        let chat = chat();
+        let turns = vec![
+            ("user".to_string(), turn(&[10, 11])),
+            ("assistant".to_string(), turn(&[12])),
+        ];
+        let roles = turns.iter().map(|(r, _)| r.clone()).collect::<Vec<_>>();
+        assert_eq!(chat.added_tokens_chat(&roles).unwrap(), 4);
+        assert!(chat.added_tokens_chat(&["system".into()]).is_err());
+
+        let output = chat.process_chat(turns.clone(), true).unwrap();
+        assert_eq!(output.encoding.get_ids(), [1, 10, 11, 3, 2, 12, 3]);
+        assert_eq!(output.encoding.get_type_ids(), [0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(
+            output.encoding.get_special_tokens_mask(),
+            [1, 0, 0, 1, 1, 0, 1]
+        );
+        assert_eq!(
+            output.encoding.get_sequence_ids(),
+            [None, Some(0), Some(0), None, None, Some(1), None]
+        );
+        assert_eq!(output.assistant_mask, [0, 0, 0, 0, 0, 1, 1]);
+
+        let output = chat.process_chat(turns, false).unwrap();
+        assert_eq!(output.encoding.get_ids(), [10, 11, 12]);
+        assert_eq!(output.assistant_mask, [0, 0, 1]);
+
+        assert!(chat
+            .process_chat(vec![("system".into(), turn(&[10]))], true)
+            .is_err());
+    }
+
+    #[test]
+    fn missing_special_tokens() {
+        let chat = ChatProcessing::builder()
+            .role("user", ChatRole::new(vec!["<|user|>"], vec!["<|end|>"]))
+            .special_tokens(vec![("<|user|>", 1)])
+            .build();
+        assert_eq!(
+            chat.unwrap_err().to_string(),
+            "Missing SpecialToken(s) with id(s) `<|end|>`"
+        );
+    }
+
+    #[test]
+    fn serde() {
+        let chat = chat();
+        let serialized = serde_json::to_string(&chat).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"ChatProcessing","roles":{"assistant":{"prefix":["<|assistant|>"],"suffix":["<|end|>"],"type_id":1,"assistant":true},"user":{"prefix":["<|user|>"],"suffix":["<|end|>"],"type_id":0,"assistant":false}},"special_tokens":{"<|assistant|>":{"id":"<|assistant|>","ids":[2],"tokens":["<|assistant|>"]},"<|end|>":{"id":"<|end|>","ids":[3],"tokens":["<|end|>"]},"<|user|>":{"id":"<|user|>","ids":[1],"tokens":["<|user|>"]}},"truncation":"LongestFirst"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ChatProcessing>(&serialized).unwrap(),
+            chat
+        );
+        assert_eq!(
+            serde_json::from_str::<crate::processors::PostProcessorWrapper>(&serialized).unwrap(),
+            chat.into()
+        );
+
+        // The roles are validated like with the builder
+        let invalid = serialized.replace(
+            r#""suffix":["<|end|>"],"type_id":1"#,
+            r#""suffix":["<|eot|>"],"type_id":1"#,
+        );
+        let err = serde_json::from_str::<ChatProcessing>(&invalid).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Missing SpecialToken(s) with id(s) `<|eot|>`"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod bert;
+pub mod chat;
 pub mod roberta;
 pub mod sequence;
 pub mod template;
@@ -10,10 +11,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::pre_tokenizers::byte_level::ByteLevel;
 use crate::processors::bert::BertProcessing;
+use crate::processors::chat::ChatProcessing;
 use crate::processors::roberta::RobertaProcessing;
 use crate::processors::sequence::Sequence;
 use crate::processors::template::TemplateProcessing;
-use crate::{Encoding, PostProcessor, Result};
+use crate::{ChatEncoding, ChatTruncation, Encoding, PostProcessor, Result};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Eq)]
 #[serde(untagged)]
@@ -23,6 +25,7 @@ pub enum PostProcessorWrapper {
     Bert(BertProcessing),
     ByteLevel(ByteLevel),
     Template(TemplateProcessing),
+    Chat(ChatProcessing),
     Sequence(Sequence),
 }
 
@@ -33,10 +36,22 @@ impl PostProcessor for PostProcessorWrapper {
             Self::ByteLevel(bl) => bl.added_tokens(is_pair),
             Self::Roberta(roberta) => roberta.added_tokens(is_pair),
             Self::Template(template) => template.added_tokens(is_pair),
+            Self::Chat(chat) => chat.added_tokens(is_pair),
             Self::Sequence(bl) => bl.added_tokens(is_pair),
         }
     }
 
+    fn added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        match self {
+            Self::Bert(bert) => bert.added_tokens_multi(n_sequences),
+            Self::ByteLevel(bl) => bl.added_tokens_multi(n_sequences),
+            Self::Roberta(roberta) => roberta.added_tokens_multi(n_sequences),
+            Self::Template(template) => template.added_tokens_multi(n_sequences),
+            Self::Chat(chat) => chat.added_tokens_multi(n_sequences),
+            Self::Sequence(bl) => bl.added_tokens_multi(n_sequences),
+        }
+    }
+
     fn process_encodings(
         &self,
         encodings: Vec<Encoding>,
@@ -47,15 +62,65 @@ impl PostProcessor for PostProcessorWrapper {
             Self::ByteLevel(bl) => bl.process_encodings(encodings, add_special_tokens),
             Self::Roberta(roberta) => roberta.process_encodings(encodings, add_special_tokens),
             Self::Template(template) => template.process_encodings(encodings, add_special_tokens),
+            Self::Chat(chat) => chat.process_encodings(encodings, add_special_tokens),
             Self::Sequence(bl) => bl.process_encodings(encodings, add_special_tokens),
         }
     }
+
+    fn added_tokens_chat(&self, roles: &[String]) -> Result<usize> {
+        match self {
+            Self::Bert(bert) => bert.added_tokens_chat(roles),
+            Self::ByteLevel(bl) => bl.added_tokens_chat(roles),
+            Self::Roberta(roberta) => roberta.added_tokens_chat(roles),
+            Self::Template(template) => template.added_tokens_chat(roles),
+            Self::Chat(chat) => chat.added_tokens_chat(roles),
+            Self::Sequence(bl) => bl.added_tokens_chat(roles),
+        }
+    }
+
+    fn chat_truncation(&self) -> ChatTruncation {
+        match self {
+            Self::Bert(bert) => bert.chat_truncation(),
+            Self::ByteLevel(bl) => bl.chat_truncation(),
+            Self::Roberta(roberta) => roberta.chat_truncation(),
+            Self::Template(template) => template.chat_truncation(),
+            Self::Chat(chat) => chat.chat_truncation(),
+            Self::Sequence(bl) => bl.chat_truncation(),
+        }
+    }
+
+    fn process_chat(
+        &self,
+        turns: Vec<(String, Encoding)>,
+        add_special_tokens: bool,
+    ) -> Result<ChatEncoding> {
+        match self {
+            Self::Bert(bert) => bert.process_chat(turns, add_special_tokens),
+            Self::ByteLevel(bl) => bl.process_chat(turns, add_special_tokens),
+            Self::Roberta(roberta) => roberta.process_chat(turns, add_special_tokens),
+            Self::Template(template) => template.process_chat(turns, add_special_tokens),
+            Self::Chat(chat) => chat.process_chat(turns, add_special_tokens),
+            Self::Sequence(bl) => bl.process_chat(turns, add_special_tokens),
+        }
+    }
+
+    fn replace_special_token(&mut self, token: &str, new_token: &str, new_id: u32) {
+        match self {
+            Self::Bert(bert) => bert.replace_special_token(token, new_token, new_id),
+            Self::ByteLevel(bl) => bl.replace_special_token(token, new_token, new_id),
+            Self::Roberta(roberta) => roberta.replace_special_token(token, new_token, new_id),
+            Self::Template(template) => template.replace_special_token(token, new_token, new_id),
+            Self::Chat(chat) => chat.replace_special_token(token, new_token, new_id),
+            Self::Sequence(bl) => bl.replace_special_token(token, new_token, new_id),
+        }
+    }
 }
 
 impl_enum_from!(BertProcessing, PostProcessorWrapper, Bert);
 impl_enum_from!(ByteLevel, PostProcessorWrapper, ByteLevel);
 impl_enum_from!(RobertaProcessing, PostProcessorWrapper, Roberta);
 impl_enum_from!(TemplateProcessing, PostProcessorWrapper, Template);
+impl_enum_from!(ChatProcessing, PostProcessorWrapper, Chat);
 impl_enum_from!(Sequence, PostProcessorWrapper, Sequence);
 
 #[cfg(test)]
@@ -33,6 +33,14 @@ impl RobertaProcessing {
         }
     }
 
+    pub fn get_sep_copy(&self) -> (String, u32) {
+        (self.sep.0.clone(), self.sep.1)
+    }
+
+    pub fn get_cls_copy(&self) -> (String, u32) {
+        (self.cls.0.clone(), self.cls.1)
+    }
+
     #[must_use]
     pub fn trim_offsets(mut self, v: bool) -> Self {
         self.trim_offsets = v;
@@ -55,6 +63,10 @@ impl PostProcessor for RobertaProcessing {
         }
     }
 
+    fn added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        Ok(2 * n_sequences.max(1))
+    }
+
     fn process_encodings(
         &self,
         mut encodings: Vec<Encoding>,
@@ -222,6 +234,14 @@ impl PostProcessor for RobertaProcessing {
 
         Ok(encodings)
     }
+
+    fn replace_special_token(&mut self, token: &str, new_token: &str, new_id: u32) {
+        for special in [&mut self.sep, &mut self.cls] {
+            if special.0 == token {
+                *special = (new_token.to_owned(), new_id);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
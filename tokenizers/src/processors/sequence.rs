@@ -13,6 +13,10 @@ impl Sequence {
     pub fn new(processors: Vec<PostProcessorWrapper>) -> Self {
         Self { processors }
     }
+
+    pub fn get_processors(&self) -> &[PostProcessorWrapper] {
+        &self.processors
+    }
 }
 
 impl PostProcessor for Sequence {
@@ -23,6 +27,13 @@ impl PostProcessor for Sequence {
             .sum::<usize>()
     }
 
+    fn added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        self.processors
+            .iter()
+            .map(|p| p.added_tokens_multi(n_sequences))
+            .sum::<Result<usize>>()
+    }
+
     fn process_encodings(
         &self,
         mut encodings: Vec<Encoding>,
@@ -33,6 +44,12 @@ impl PostProcessor for Sequence {
         }
         Ok(encodings)
     }
+
+    fn replace_special_token(&mut self, token: &str, new_token: &str, new_id: u32) {
+        for processor in self.processors.iter_mut() {
+            processor.replace_special_token(token, new_token, new_id);
+        }
+    }
 }
 
 #[cfg(test)]
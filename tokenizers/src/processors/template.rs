@@ -46,6 +46,23 @@
 //! - Specifying the `type_id` with default `sequence == A`: `$0`, `$1`, `$2`, ...
 //! - Specifying both: `$A:0`, `$B:1`, ...
 //!
+//! When there are more than two input sequences, the `multi` template is used. It can refer to
+//! the following sequences with `$C`, `$D`, ..., or to all the sequences it doesn't refer to
+//! explicitly with `$*`. The pieces following `$*`, until the next sequence, are repeated for
+//! each of them:
+//! ```
+//! # use tokenizers::processors::template::TemplateProcessing;
+//! let template = TemplateProcessing::builder()
+//!     .try_single("[CLS] $A [SEP]").unwrap()
+//!     .try_pair("[CLS] $A [SEP] $B:1 [SEP]:1").unwrap()
+//!     // A query, followed by any number of passages:
+//!     // [CLS] query [SEP] passage 1 [SEP] passage 2 [SEP] ...
+//!     .try_multi("[CLS] $A [SEP] $*:1 [SEP]:1").unwrap()
+//!     .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+//!     .build()
+//!     .unwrap();
+//! ```
+//!
 //! The same construct is used for special tokens: `<identifier>(:<type_id>)?`.
 //!
 //! **Warning**: You must ensure that you are giving the correct tokens/ids as these will
@@ -55,8 +72,7 @@
 //!
 //! [`TemplateProcessing`]: struct.TemplateProcessing.html
 //!
-use crate::{Encoding, PostProcessor, Result};
-use itertools::Itertools;
+use crate::{Encoding, PostProcessor, ProcessorError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
@@ -69,6 +85,32 @@ pub enum Sequence {
     A,
     /// This is the pair sequence, that is optional
     B,
+    /// Any following sequence, by its index in the inputs: `$C` is `Index(2)`, `$D` is
+    /// `Index(3)`, ...
+    Index(usize),
+    /// Each of the sequences not referred to explicitly by the template, in order. The
+    /// pieces following it, until the next sequence, are repeated for each of them.
+    Rest,
+}
+
+impl Sequence {
+    /// The index of this sequence in the inputs, if it refers to a single one
+    fn index(&self) -> Option<usize> {
+        match self {
+            Self::A => Some(0),
+            Self::B => Some(1),
+            Self::Index(i) => Some(*i),
+            Self::Rest => None,
+        }
+    }
+
+    fn from_index(i: usize) -> Self {
+        match i {
+            0 => Self::A,
+            1 => Self::B,
+            i => Self::Index(i),
+        }
+    }
 }
 
 /// Represents the different kind of pieces that constitute a template.
@@ -116,6 +158,17 @@ impl Piece {
                     id: Sequence::B,
                     type_id: 0,
                 }),
+                "*" => Some(Self::Sequence {
+                    id: Sequence::Rest,
+                    type_id: 0,
+                }),
+                n if n.len() == 1 && n.chars().all(|c| c.is_ascii_uppercase()) => {
+                    let c = n.as_bytes()[0];
+                    Some(Self::Sequence {
+                        id: Sequence::from_index((c - b'A') as usize),
+                        type_id: 0,
+                    })
+                }
                 n => {
                     if let Ok(type_id) = n.parse::<u32>() {
                         Some(Self::Sequence {
@@ -193,9 +246,9 @@ pub struct SpecialToken {
     /// A unique id used to identify this SpecialToken in the template
     id: String,
     /// The list of associated ids
-    ids: Vec<u32>,
+    pub(crate) ids: Vec<u32>,
     /// The list of associated tokens
-    tokens: Vec<String>,
+    pub(crate) tokens: Vec<String>,
 }
 
 impl From<(String, u32)> for SpecialToken {
@@ -226,7 +279,7 @@ impl From<(u32, &str)> for SpecialToken {
 impl SpecialToken {
     pub fn new(id: String, ids: Vec<u32>, tokens: Vec<String>) -> Result<Self> {
         if ids.len() != tokens.len() {
-            Err("SpecialToken: ids and tokens must be of the same length".into())
+            Err(ProcessorError::SpecialTokenLength.into())
         } else {
             Ok(Self { id, ids, tokens })
         }
@@ -249,10 +302,84 @@ impl SpecialToken {
 ///
 /// [`Piece`]: enum.Piece.html
 ///
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Eq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Eq)]
 #[serde(transparent)]
 pub struct Template(Vec<Piece>);
 
+impl Template {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The pieces of this template for `n_sequences` input sequences, with `$*` replaced
+    /// by each of the sequences it stands for.
+    ///
+    /// Fails if the template refers to missing sequences, or if some sequences are not used
+    /// by a template without `$*`.
+    fn expand(&self, n_sequences: usize) -> Result<Vec<Piece>> {
+        let explicit = self
+            .0
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Sequence { id, .. } => id.index(),
+                Piece::SpecialToken { .. } => None,
+            })
+            .collect::<HashSet<_>>();
+        if let Some(i) = explicit.iter().find(|i| **i >= n_sequences) {
+            return Err(ProcessorError::MissingSequence {
+                index: *i,
+                sequences: n_sequences,
+            }
+            .into());
+        }
+        let has_rest = self.0.iter().any(|piece| {
+            matches!(
+                piece,
+                Piece::Sequence {
+                    id: Sequence::Rest,
+                    ..
+                }
+            )
+        });
+        if !has_rest && explicit.len() < n_sequences {
+            return Err(ProcessorError::UnusedSequences {
+                used: explicit.len(),
+                sequences: n_sequences,
+            }
+            .into());
+        }
+
+        let mut pieces = Vec::with_capacity(self.0.len());
+        let mut i = 0;
+        while i < self.0.len() {
+            match &self.0[i] {
+                Piece::Sequence {
+                    id: Sequence::Rest,
+                    type_id,
+                } => {
+                    // The repeated group ends at the next sequence
+                    let end = (i + 1..self.0.len())
+                        .find(|j| matches!(self.0[*j], Piece::Sequence { .. }))
+                        .unwrap_or(self.0.len());
+                    for index in (0..n_sequences).filter(|index| !explicit.contains(index)) {
+                        pieces.push(Piece::Sequence {
+                            id: Sequence::from_index(index),
+                            type_id: *type_id,
+                        });
+                        pieces.extend(self.0[i + 1..end].iter().cloned());
+                    }
+                    i = end;
+                }
+                piece => {
+                    pieces.push(piece.clone());
+                    i += 1;
+                }
+            }
+        }
+        Ok(pieces)
+    }
+}
+
 impl<T> TryFrom<Vec<T>> for Template
 where
     T: TryInto<Piece, Error = String>,
@@ -314,6 +441,20 @@ impl From<HashMap<String, SpecialToken>> for Tokens {
     }
 }
 
+impl Tokens {
+    /// Replace the given token with another one, wherever it is used
+    pub(crate) fn replace(&mut self, token: &str, new_token: &str, new_id: u32) {
+        for special in self.0.values_mut() {
+            for (t, id) in special.tokens.iter_mut().zip(special.ids.iter_mut()) {
+                if t == token {
+                    *t = new_token.to_owned();
+                    *id = new_id;
+                }
+            }
+        }
+    }
+}
+
 /// This PostProcessor takes care of processing each input `Encoding` by applying
 /// the corresponding template, before merging them in the final Encoding.
 ///
@@ -334,12 +475,16 @@ impl From<HashMap<String, SpecialToken>> for Tokens {
 ///
 #[derive(Debug, Clone, PartialEq, Builder, Serialize, Deserialize, Eq)]
 #[serde(tag = "type", from = "TemplateProcessingDeserializer")]
-#[builder(build_fn(validate = "Self::validate"))]
+#[builder(build_fn(validate = "Self::validate", error = "ProcessorError"))]
 pub struct TemplateProcessing {
     #[builder(try_setter, default = "\"$0\".try_into().unwrap()")]
     single: Template,
     #[builder(try_setter, default = "\"$A:0 $B:1\".try_into().unwrap()")]
     pair: Template,
+    /// The template used with more than two sequences
+    #[builder(try_setter, default)]
+    #[serde(skip_serializing_if = "Template::is_empty")]
+    multi: Template,
     #[builder(setter(skip), default = "self.default_added(true)")]
     #[serde(skip)]
     added_single: usize,
@@ -350,17 +495,8 @@ pub struct TemplateProcessing {
     special_tokens: Tokens,
 }
 
-impl From<&str> for TemplateProcessingBuilderError {
-    fn from(e: &str) -> Self {
-        e.to_string().into()
-    }
-}
-
-impl PartialEq for TemplateProcessingBuilderError {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_string() == other.to_string()
-    }
-}
+/// The errors of the [`TemplateProcessingBuilder`], like an invalid template
+pub type TemplateProcessingBuilderError = ProcessorError;
 
 /// We use this custom deserializer to provided the values for `added_single`
 /// and `added_pair` during deserialization, while not having to serialize them
@@ -370,6 +506,8 @@ impl PartialEq for TemplateProcessingBuilderError {
 struct TemplateProcessingDeserializer {
     single: Template,
     pair: Template,
+    #[serde(default)]
+    multi: Template,
     special_tokens: Tokens,
 }
 impl From<TemplateProcessingDeserializer> for TemplateProcessing {
@@ -379,6 +517,7 @@ impl From<TemplateProcessingDeserializer> for TemplateProcessing {
         Self {
             single: t.single,
             pair: t.pair,
+            multi: t.multi,
             added_single,
             added_pair,
             special_tokens: t.special_tokens,
@@ -412,7 +551,7 @@ impl TemplateProcessingBuilder {
         })
     }
 
-    fn validate(&self) -> std::result::Result<(), String> {
+    fn validate(&self) -> std::result::Result<(), ProcessorError> {
         let pair_has_both = self.pair.as_ref().map_or(true, |pair| {
             let mut has_a = false;
             let mut has_b = false;
@@ -433,7 +572,59 @@ impl TemplateProcessingBuilder {
             has_a && has_b
         });
         if !pair_has_both {
-            return Err("Template for `pair` must use both sequences".into());
+            return Err(ProcessorError::PairSequences);
+        }
+
+        let multi_rests = self.multi.as_ref().map_or(0, |multi| {
+            multi
+                .0
+                .iter()
+                .filter(|piece| {
+                    matches!(
+                        piece,
+                        Piece::Sequence {
+                            id: Sequence::Rest,
+                            ..
+                        }
+                    )
+                })
+                .count()
+        });
+        if multi_rests > 1 {
+            return Err(ProcessorError::RepeatedRest);
+        }
+        if multi_rests == 0 {
+            // Without `$*`, the sequences skipped by the template could never be processed
+            let explicit = self
+                .multi
+                .iter()
+                .flat_map(|multi| &multi.0)
+                .filter_map(|piece| match piece {
+                    Piece::Sequence { id, .. } => id.index(),
+                    Piece::SpecialToken { .. } => None,
+                })
+                .collect::<HashSet<_>>();
+            if let Some(i) = explicit.iter().max().filter(|i| **i >= explicit.len()) {
+                return Err(ProcessorError::SkippedSequences(*i));
+            }
+        }
+
+        let beyond_pair = self
+            .single
+            .iter()
+            .chain(&self.pair)
+            .flat_map(|template| &template.0)
+            .any(|piece| {
+                matches!(
+                    piece,
+                    Piece::Sequence {
+                        id: Sequence::Index(_) | Sequence::Rest,
+                        ..
+                    }
+                )
+            });
+        if beyond_pair {
+            return Err(ProcessorError::SequencesBeyondPair);
         }
 
         let check = |sp| {
@@ -454,6 +645,7 @@ impl TemplateProcessingBuilder {
             .as_ref()
             .map_or(empty.iter(), |s| s.0.iter())
             .chain(self.pair.as_ref().map_or(empty.iter(), |s| s.0.iter()))
+            .chain(self.multi.as_ref().map_or(empty.iter(), |s| s.0.iter()))
             .filter_map(|piece| match piece {
                 Piece::Sequence { .. } => None,
                 Piece::SpecialToken { id, .. } => check(id.as_ref()),
@@ -463,9 +655,8 @@ impl TemplateProcessingBuilder {
         if missing.is_empty() {
             Ok(())
         } else {
-            Err(format!(
-                "Missing SpecialToken(s) with id(s) `{}`",
-                missing.iter().join(", ")
+            Err(ProcessorError::MissingSpecialTokens(
+                missing.into_iter().map(String::from).collect(),
             ))
         }
     }
@@ -476,6 +667,7 @@ impl Default for TemplateProcessing {
         Self {
             single: "$0".try_into().unwrap(),
             pair: "$1".try_into().unwrap(),
+            multi: Template::default(),
             added_single: 0,
             added_pair: 0,
             special_tokens: Tokens::default(),
@@ -488,6 +680,10 @@ impl TemplateProcessing {
         TemplateProcessingBuilder::default()
     }
 
+    pub fn get_special_tokens(&self) -> &Tokens {
+        &self.special_tokens
+    }
+
     fn apply_template(
         &self,
         template: &[Piece],
@@ -499,7 +695,8 @@ impl TemplateProcessing {
             .flat_map(|piece| {
                 match piece {
                     Piece::Sequence { id, type_id } => {
-                        let i = usize::from(*id != Sequence::A);
+                        // The template has been expanded, there is no `$*` left
+                        let i = id.index().unwrap_or_default();
                         let encoding = &mut encodings[i];
                         encoding.set_type_ids(vec![*type_id; encoding.len()]);
                         encoding.set_sequence_id(i);
@@ -599,6 +796,17 @@ impl PostProcessor for TemplateProcessing {
         }
     }
 
+    fn added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        match n_sequences {
+            0 | 1 => Ok(self.added_single),
+            2 => Ok(self.added_pair),
+            n => Ok(count_added(
+                &Template(self.multi.expand(n)?),
+                Some(&self.special_tokens),
+            )),
+        }
+    }
+
     fn process_encodings(
         &self,
         encodings: Vec<Encoding>,
@@ -620,16 +828,24 @@ impl PostProcessor for TemplateProcessing {
         //             .ok_or(ProcessorError::InvalidEncodingsVecLength)?;
         //         (encoding, Some(pair))
         //     }
-        //     _ => return Err(Box::new(ProcessorError::InvalidEncodingsVecLength)),
+        //     _ => return Err(ProcessorError::InvalidEncodingsVecLength.into()),
         // };
         let template = match encodings.len() {
-            2 => &self.pair.0,
-            1 => &self.single.0,
-            _ => todo!(),
+            1 => &self.single,
+            2 => &self.pair,
+            _ if self.multi.is_empty() => {
+                return Err(ProcessorError::NoMultiTemplate(encodings.len()).into())
+            }
+            _ => &self.multi,
         };
-        let encodings = self.apply_template(template, encodings, add_special_tokens)?;
+        let template = template.expand(encodings.len())?;
+        let encodings = self.apply_template(&template, encodings, add_special_tokens)?;
         Ok(encodings)
     }
+
+    fn replace_special_token(&mut self, token: &str, new_token: &str, new_id: u32) {
+        self.special_tokens.replace(token, new_token, new_id);
+    }
 }
 
 #[cfg(test)]
@@ -703,7 +919,7 @@ mod tests {
             }),
             "$:1".try_into()
         );
-        assert!(Piece::try_from("$C:1").is_err());
+        assert!(Piece::try_from("$CD:1").is_err());
         assert!(Piece::try_from("$A:").is_err());
     }
 
@@ -827,9 +1043,10 @@ mod tests {
             .unwrap()
             .build();
 
-        let err_a = Err("Missing SpecialToken(s) with id(s) `[SEP], [CLS]`".into());
-        let err_b = Err("Missing SpecialToken(s) with id(s) `[CLS], [SEP]`".into());
-        assert!(processor == err_a || processor == err_b);
+        let err = processor.unwrap_err().to_string();
+        let err_a = "Missing SpecialToken(s) with id(s) `[SEP], [CLS]`";
+        let err_b = "Missing SpecialToken(s) with id(s) `[CLS], [SEP]`";
+        assert!(err == err_a || err == err_b);
     }
 
     #[test]
@@ -897,6 +1114,129 @@ mod tests {
         assert_eq!(pair_encoding.token_to_sequence(5), None);
     }
 
+    #[test]
+    fn multi_template() {
+        assert_eq!(
+            Ok(Piece::Sequence {
+                id: Sequence::Index(2),
+                type_id: 2
+            }),
+            "$C:2".try_into()
+        );
+        assert_eq!(
+            Ok(Piece::Sequence {
+                id: Sequence::Rest,
+                type_id: 1
+            }),
+            "$*:1".try_into()
+        );
+
+        let processor = TemplateProcessing::builder()
+            .try_single("[CLS] $A [SEP]")
+            .unwrap()
+            .try_pair("[CLS] $A [SEP] $B:1 [SEP]:1")
+            .unwrap()
+            .try_multi("[CLS] $A [SEP] $*:1 [SEP]:1 $C:2")
+            .unwrap()
+            .special_tokens(vec![("[CLS]", 1), ("[SEP]", 0)])
+            .build()
+            .unwrap();
+        assert_eq!(processor.added_tokens_multi(2).unwrap(), 3);
+        assert_eq!(processor.added_tokens_multi(3).unwrap(), 3);
+        assert_eq!(processor.added_tokens_multi(5).unwrap(), 5);
+
+        use crate::Token;
+        let encodings = (0..4)
+            .map(|i| Encoding::from_tokens(vec![Token::new(10 + i, "t".into(), (0, 1))], 0))
+            .collect::<Vec<_>>();
+        let encoding = processor.process_multi(encodings, true).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 10, 0, 11, 0, 13, 0, 12]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 1, 1, 1, 1, 2]);
+        assert_eq!(encoding.token_to_sequence(5), Some(3));
+        assert_eq!(encoding.token_to_sequence(7), Some(2));
+
+        // Only uppercase letters refer to sequences, besides the `$a` and `$b` aliases
+        assert!(Piece::try_from("$c").is_err());
+        assert_eq!(
+            Ok(Piece::Sequence {
+                id: Sequence::Index(25),
+                type_id: 0
+            }),
+            "$Z".try_into()
+        );
+
+        // The template can't skip sequences without `$*`, nor refer to missing ones
+        let err = TemplateProcessing::builder()
+            .try_multi("$A $B $D")
+            .unwrap()
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Template for `multi` refers to sequence 3 but skips some of the ones before it, \
+             and has no `$*` for them"
+        );
+        let too_few = TemplateProcessing::builder()
+            .try_multi("$A $D $*")
+            .unwrap()
+            .build()
+            .unwrap();
+        let encodings = (0..3)
+            .map(|i| Encoding::from_tokens(vec![Token::new(i, "t".into(), (0, 1))], 0))
+            .collect::<Vec<_>>();
+        assert!(too_few.process_multi(encodings.clone(), true).is_err());
+        assert!(too_few.added_tokens_multi(3).is_err());
+        assert!(get_bert_template()
+            .process_multi(encodings.clone(), true)
+            .is_err());
+
+        // Without `$*`, all the sequences must be used
+        let too_many = TemplateProcessing::builder()
+            .try_multi("$A $B")
+            .unwrap()
+            .build()
+            .unwrap();
+        let err = too_many.process_multi(encodings, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Template uses 2 sequences but there are 3, and it has no `$*` for the others"
+        );
+        assert!(too_many.added_tokens_multi(3).is_err());
+
+        // The other templates only refer to the first two sequences
+        let err = TemplateProcessing::builder()
+            .try_single("$A $C")
+            .unwrap()
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Only the template for `multi` can use `$C`, `$D`, ... or `$*`"
+        );
+
+        // `$*` can be used only once
+        let err = TemplateProcessing::builder()
+            .try_multi("$A $* $*")
+            .unwrap()
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Template for `multi` can use `$*` only once"
+        );
+
+        // The `multi` template is only serialized when set
+        let serialized = serde_json::to_string(&processor).unwrap();
+        assert!(serialized.contains(r#""multi":"#));
+        assert_eq!(
+            serde_json::from_str::<TemplateProcessing>(&serialized).unwrap(),
+            processor
+        );
+        assert!(!serde_json::to_string(&get_bert_template())
+            .unwrap()
+            .contains(r#""multi":"#));
+    }
+
     #[test]
     fn template_processing_overflowing() {
         let processor = tests::get_bert_template();
@@ -1081,9 +1421,10 @@ mod tests {
             .try_pair("$0 $1")
             .unwrap()
             .build();
+        assert!(matches!(processor, Err(ProcessorError::PairSequences)));
         assert_eq!(
-            processor,
-            Err("Template for `pair` must use both sequences".into())
+            processor.unwrap_err().to_string(),
+            "Template for `pair` must use both sequences"
         );
     }
 
@@ -1096,8 +1437,8 @@ mod tests {
             .unwrap()
             .build();
         assert_ne!(
-            processor,
-            Err("Expect the left side error message to be different from the right side!".into())
+            processor.unwrap_err().to_string(),
+            "Expect the left side error message to be different from the right side!"
         );
     }
 }
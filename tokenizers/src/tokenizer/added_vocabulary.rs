@@ -1,11 +1,29 @@
 use super::{
-    normalizer::Range, Model, NormalizedString, Normalizer, Offsets, PreTokenizedString, Token,
+    normalizer::Range, Model, NormalizedString, Normalizer, Offsets, PreTokenizedString,
+    SpecialTokenRoleError, Token,
 };
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use regex::Regex;
 use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
 use std::collections::{HashMap, HashSet};
 
+#[derive(thiserror::Error, Debug)]
+pub enum AddedTokensError {
+    /// A special token role is given to a missing token
+    #[error(transparent)]
+    Role(#[from] SpecialTokenRoleError),
+    /// A special token is given an id already used by another token
+    #[error("Special token `{token}` can't use id {id}, already used by `{existing}`")]
+    IdAlreadyUsed {
+        token: String,
+        id: u32,
+        existing: String,
+    },
+    /// The input gives a special token that is not allowed to be matched
+    #[error("Special token `{0}` is not allowed in the input")]
+    Disallowed(String),
+}
+
 /// Represent a token added by the user on top of the existing Model vocabulary.
 /// AddedToken can be configured to specify the behavior they should have in various situations
 /// like:
@@ -101,6 +119,59 @@ lazy_static! {
     static ref LEFTMOST_SPACE_AT_END: Regex = Regex::new(r"\s*$").unwrap();
 }
 
+/// Which special tokens get matched in the input when encoding.
+///
+/// Matching a special token in some user provided text gives it the id of the real control
+/// token, so untrusted text should be encoded with [`SpecialTokensMatching::None`], or with
+/// only the special tokens it's allowed to use. The special tokens that are not matched are
+/// tokenized as plain text by the model, and never get their id even when the model knows
+/// them. The classic added tokens are always matched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SpecialTokensMatching {
+    /// Match all the special tokens
+    #[default]
+    All,
+    /// Don't match any special token
+    None,
+    /// Only match the given special tokens
+    Only(HashSet<String>),
+}
+
+impl SpecialTokensMatching {
+    /// Whether the given special token should be matched
+    pub fn allows(&self, token: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::None => false,
+            Self::Only(tokens) => tokens.contains(token),
+        }
+    }
+}
+
+/// Build the `MatchingSet` of the given tokens, matching against their normalized content
+/// when a `normalizer` is provided.
+fn matching_set<N: Normalizer>(
+    tokens: Vec<(&AddedToken, u32)>,
+    normalizer: Option<&N>,
+) -> MatchingSet {
+    let (tokens, ids): (Vec<&AddedToken>, Vec<u32>) = tokens.into_iter().unzip();
+    let patterns: Vec<_> = tokens
+        .iter()
+        .map(|token| {
+            let mut content = NormalizedString::from(token.content.as_ref());
+            if let Some(n) = normalizer {
+                n.normalize(&mut content).unwrap();
+            }
+            content
+        })
+        .collect();
+    let trie = AhoCorasickBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(patterns.iter().map(|content| content.get()))
+        .expect("Failed to build tried when refreshing tokens");
+    (trie, ids)
+}
+
 fn ends_with_word(sentence: &str) -> bool {
     ENDS_WITH_WORD.is_match(sentence)
 }
@@ -313,29 +384,39 @@ impl AddedVocabulary {
             })
             .partition(|(token, _)| token.normalized);
 
-        let (tokens, ids): (Vec<&AddedToken>, Vec<u32>) = non_normalized.into_iter().unzip();
-        let trie = AhoCorasickBuilder::new()
-            .match_kind(MatchKind::LeftmostLongest)
-            .build(tokens.iter().map(|token| &token.content))
-            .expect("Failed to build tried when refreshing tokens");
-        self.split_trie = (trie, ids);
+        self.split_trie = matching_set(non_normalized, None::<&N>);
+        self.split_normalized_trie = matching_set(normalized, normalizer);
+    }
 
-        let (ntokens, nids): (Vec<&AddedToken>, Vec<u32>) = normalized.into_iter().unzip();
-        let patterns: Vec<_> = ntokens
+    /// Build the two `MatchingSet` (non-normalized and normalized) with only the special
+    /// tokens allowed by `matching`. A skipped special token must not be part of the
+    /// automaton at all, otherwise it would hide the tokens overlapping with it.
+    fn allowed_matching_sets<N: Normalizer>(
+        &self,
+        normalizer: Option<&N>,
+        matching: &SpecialTokensMatching,
+    ) -> (MatchingSet, MatchingSet) {
+        type TupleTokenId<'a> = (&'a AddedToken, u32);
+        let (normalized, non_normalized): (Vec<TupleTokenId>, Vec<TupleTokenId>) = self
+            .special_tokens
             .iter()
-            .map(|token| {
-                let mut content = NormalizedString::from(token.content.as_ref());
-                if let Some(n) = normalizer {
-                    n.normalize(&mut content).unwrap();
-                }
-                content
-            })
-            .collect();
-        let normalized_trie = AhoCorasickBuilder::new()
-            .match_kind(MatchKind::LeftmostLongest)
-            .build(patterns.iter().map(|content| content.get()))
-            .expect("Failed to build tried when refreshing tokens (normalized)");
-        self.split_normalized_trie = (normalized_trie, nids);
+            .chain(self.added_tokens.iter())
+            .filter(|token| !token.special || matching.allows(&token.content))
+            .map(|token| (token, self.added_tokens_map[&token.content]))
+            .partition(|(token, _)| token.normalized);
+
+        (
+            matching_set(non_normalized, None::<&N>),
+            matching_set(normalized, normalizer),
+        )
+    }
+
+    /// Whether the given id is the one of a special token that `matching` doesn't allow
+    pub fn is_disallowed_special(&self, id: u32, matching: &SpecialTokensMatching) -> bool {
+        matches!(
+            self.added_tokens_map_r.get(&id),
+            Some(token) if token.special && !matching.allows(&token.content)
+        )
     }
 
     /// Find any AddedToken in the given sentence, using the provided MatchingSet.
@@ -428,19 +509,39 @@ impl AddedVocabulary {
         &self,
         normalizer: Option<&N>,
         sequence: &str,
+    ) -> PreTokenizedString {
+        self.extract_and_normalize_with_matching(normalizer, sequence, &SpecialTokensMatching::All)
+    }
+
+    /// Same as [`extract_and_normalize`](Self::extract_and_normalize), but only the special
+    /// tokens allowed by `matching` are extracted.
+    pub fn extract_and_normalize_with_matching<N: Normalizer>(
+        &self,
+        normalizer: Option<&N>,
+        sequence: &str,
+        matching: &SpecialTokensMatching,
     ) -> PreTokenizedString {
         let mut pretokenized: PreTokenizedString = sequence.into();
 
+        let allowed;
+        let (split_trie, split_normalized_trie) = match matching {
+            SpecialTokensMatching::All => (&self.split_trie, &self.split_normalized_trie),
+            _ => {
+                allowed = self.allowed_matching_sets(normalizer, matching);
+                (&allowed.0, &allowed.1)
+            }
+        };
+
         // 1. We extract all the non-normalized tokens from the non-normalized string
         pretokenized
-            .split(|_, sequence| Ok(self.split_with_indices(sequence, &self.split_trie)))
+            .split(|_, sequence| Ok(self.split_with_indices(sequence, split_trie)))
             .expect("AddedVocabulary bad split");
 
         // 2. Then extract the normalized tokens from the normalized pieces of the string
         pretokenized
             .split(|_, mut sequence| {
                 normalizer.map(|n| n.normalize(&mut sequence));
-                Ok(self.split_with_indices(sequence, &self.split_normalized_trie))
+                Ok(self.split_with_indices(sequence, split_normalized_trie))
             })
             .expect("AddedVocabulary bad split");
 
@@ -740,6 +841,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn special_tokens_matching() {
+        let model = ModelMock::new(&[]);
+        let mut vocab = AddedVocabulary::new();
+        let normalizer: Option<&NormalizerWrapper> = None;
+
+        vocab.add_tokens(&[AddedToken::from("name", false)], &model, normalizer);
+        vocab.add_special_tokens(
+            &[
+                AddedToken::from("[CLS]", true),
+                AddedToken::from("[SEP]", true),
+            ],
+            &model,
+            normalizer,
+        );
+
+        let sentence = "[CLS] My name [SEP]";
+        let result = vocab.extract_and_normalize_with_matching(
+            normalizer,
+            sentence,
+            &SpecialTokensMatching::None,
+        );
+        // The classic added tokens are still extracted
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("[CLS] My ", None),
+                ("name", Some(vec![0])),
+                (" [SEP]", None)
+            ]
+        );
+
+        let result = vocab.extract_and_normalize_with_matching(
+            normalizer,
+            sentence,
+            &SpecialTokensMatching::Only(vec!["[SEP]".to_string()].into_iter().collect()),
+        );
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("[CLS] My ", None),
+                ("name", Some(vec![0])),
+                (" ", None),
+                ("[SEP]", Some(vec![2]))
+            ]
+        );
+    }
+
+    #[test]
+    fn special_tokens_matching_overlap() {
+        let model = ModelMock::new(&[]);
+        let mut vocab = AddedVocabulary::new();
+        let normalizer: Option<&NormalizerWrapper> = None;
+
+        vocab.add_special_tokens(
+            &[AddedToken::from("<|im_start|>", true)],
+            &model,
+            normalizer,
+        );
+        vocab.add_tokens(&[AddedToken::from("im_start", false)], &model, normalizer);
+
+        // The skipped special token doesn't hide the added token inside it
+        let result = vocab.extract_and_normalize_with_matching(
+            normalizer,
+            "<|im_start|>",
+            &SpecialTokensMatching::None,
+        );
+        assert_eq!(
+            simplify_output(&result),
+            vec![("<|", None), ("im_start", Some(vec![1])), ("|>", None)]
+        );
+        let result = vocab.extract_and_normalize(normalizer, "<|im_start|>");
+        assert_eq!(
+            simplify_output(&result),
+            vec![("<|im_start|>", Some(vec![0]))]
+        );
+    }
+
     #[test]
     fn options_use_cases() {
         // Is able to extract both normal and special tokens, with various options (lstrip, rstrip,
@@ -0,0 +1,287 @@
+use super::{
+    pad_encodings, truncate_multi_encodings, Decoder, Encoding, InputSequence, Model, Normalizer,
+    OffsetType, PaddingDirection, PostProcessor, PreTokenizer, ProcessorError, Result,
+    SpecialTokensMatching, TokenizerImpl, TruncationParams,
+};
+use serde::{Deserialize, Serialize};
+
+/// A conversation encoded with [`encode_chat`](TokenizerImpl::encode_chat)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatEncoding {
+    /// The encoding of the whole conversation
+    pub encoding: Encoding,
+    /// For each token of `encoding`, 1 if it belongs to an assistant turn, 0 otherwise. The
+    /// overflowing parts of `encoding` are not covered.
+    pub assistant_mask: Vec<u32>,
+}
+
+/// How [`encode_chat`](TokenizerImpl::encode_chat) truncates the conversations that are
+/// too long
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChatTruncation {
+    /// Remove the tokens from the longest turns first, whatever their role. The answers of
+    /// the assistant are often the longest turns, so this usually cuts the tokens marked in
+    /// the assistant mask.
+    #[default]
+    LongestFirst,
+    /// Drop whole turns, the oldest first, until the conversation fits. The last turn gets
+    /// truncated only if it doesn't fit alone. The dropped turns are not kept as overflowing.
+    OldestFirst,
+}
+
+impl std::convert::AsRef<str> for ChatTruncation {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::LongestFirst => "longest_first",
+            Self::OldestFirst => "oldest_first",
+        }
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    /// Encode a conversation, given as a list of `(role, text)` turns.
+    ///
+    /// The turns are rendered by the post-processor, which must support conversations, like
+    /// [`ChatProcessing`](crate::processors::chat::ChatProcessing) does. When truncating, the
+    /// post-processor chooses between removing the tokens from the longest turns first, and
+    /// dropping the oldest turns first, as given by its [`ChatTruncation`].
+    pub fn encode_chat<'s, R, S>(
+        &self,
+        turns: Vec<(R, S)>,
+        add_special_tokens: bool,
+    ) -> Result<ChatEncoding>
+    where
+        R: Into<String>,
+        S: Into<InputSequence<'s>>,
+    {
+        self.encode_chat_with_offsets(turns, add_special_tokens, OffsetType::Byte)
+    }
+
+    /// Encode a conversation, like `encode_chat`, using offsets relative to the chars
+    pub fn encode_chat_char_offsets<'s, R, S>(
+        &self,
+        turns: Vec<(R, S)>,
+        add_special_tokens: bool,
+    ) -> Result<ChatEncoding>
+    where
+        R: Into<String>,
+        S: Into<InputSequence<'s>>,
+    {
+        self.encode_chat_with_offsets(turns, add_special_tokens, OffsetType::Char)
+    }
+
+    fn encode_chat_with_offsets<'s, R, S>(
+        &self,
+        turns: Vec<(R, S)>,
+        add_special_tokens: bool,
+        offsets_type: OffsetType,
+    ) -> Result<ChatEncoding>
+    where
+        R: Into<String>,
+        S: Into<InputSequence<'s>>,
+    {
+        let processor = self
+            .post_processor
+            .as_ref()
+            .ok_or(ProcessorError::ChatNotSupported)?;
+
+        let mut roles = Vec::with_capacity(turns.len());
+        let mut encodings = Vec::with_capacity(turns.len());
+        for (i, (role, sequence)) in turns.into_iter().enumerate() {
+            roles.push(role.into());
+            encodings.push(self.encode_single_sequence(
+                sequence.into(),
+                i as u32,
+                offsets_type,
+                &SpecialTokensMatching::All,
+            )?);
+        }
+
+        let added_tokens = |roles: &[String]| {
+            if add_special_tokens {
+                processor.added_tokens_chat(roles)
+            } else {
+                Ok(0)
+            }
+        };
+
+        // 1. First we truncate the turns if needed
+        let encodings = if let Some(trunc) = &self.truncation {
+            if let ChatTruncation::OldestFirst = processor.chat_truncation() {
+                // The number of latest turns that fit, keeping at least the last one
+                let mut kept = 0;
+                let mut length = 0;
+                for (role, encoding) in roles.iter().zip(&encodings).rev() {
+                    length += encoding.len() + added_tokens(std::slice::from_ref(role))?;
+                    if length > trunc.max_length {
+                        break;
+                    }
+                    kept += 1;
+                }
+                let dropped = roles.len().saturating_sub(kept.max(1));
+                roles.drain(..dropped);
+                encodings.drain(..dropped);
+            }
+
+            let n_added_tokens = added_tokens(&roles)?;
+            let params = TruncationParams {
+                max_length: trunc.max_length.saturating_sub(n_added_tokens),
+                ..*trunc
+            };
+            truncate_multi_encodings(encodings, &params)?
+        } else {
+            encodings
+        };
+
+        // 2. Then we render the conversation
+        let ChatEncoding {
+            encoding,
+            mut assistant_mask,
+        } = processor.process_chat(
+            roles.into_iter().zip(encodings).collect(),
+            add_special_tokens,
+        )?;
+
+        // 3. And we pad if needed, the mask along with the encoding
+        let encoding = if let Some(params) = &self.padding {
+            let mut arr = [encoding];
+            pad_encodings(&mut arr, params)?;
+            let [encoding] = arr;
+            let padding = encoding.len() - assistant_mask.len();
+            if let PaddingDirection::Left = params.direction {
+                assistant_mask.splice(0..0, vec![0; padding]);
+            } else {
+                assistant_mask.resize(encoding.len(), 0);
+            }
+            encoding
+        } else {
+            encoding
+        };
+
+        Ok(ChatEncoding {
+            encoding,
+            assistant_mask,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChatTruncation;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::processors::chat::{ChatProcessing, ChatRole};
+    use crate::{PaddingDirection, PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+    fn tokenizer(truncation: ChatTruncation) -> Tokenizer {
+        let vocab = [
+            ("[UNK]", 0),
+            ("hi", 1),
+            ("hello", 2),
+            ("there", 3),
+            ("you", 4),
+        ]
+        .iter()
+        .map(|(t, id)| (t.to_string(), *id))
+        .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer.with_post_processor(
+            ChatProcessing::builder()
+                .role("user", ChatRole::new(vec!["<user>"], vec!["<end>"]))
+                .role(
+                    "assistant",
+                    ChatRole::new(vec!["<assistant>"], vec!["<end>"])
+                        .type_id(1)
+                        .assistant(true),
+                )
+                .special_tokens(vec![("<user>", 5), ("<assistant>", 6), ("<end>", 7)])
+                .truncation(truncation)
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+    }
+
+    #[test]
+    fn encode_chat() {
+        let mut tokenizer = tokenizer(ChatTruncation::LongestFirst);
+        let turns = vec![("user", "hi there"), ("assistant", "hello you there")];
+
+        let chat = tokenizer.encode_chat(turns.clone(), true).unwrap();
+        assert_eq!(chat.encoding.get_ids(), [5, 1, 3, 7, 6, 2, 4, 3, 7]);
+        assert_eq!(chat.encoding.get_type_ids(), [0, 0, 0, 0, 1, 1, 1, 1, 1]);
+        assert_eq!(chat.assistant_mask, [0, 0, 0, 0, 0, 1, 1, 1, 1]);
+
+        // The longest turn gets truncated
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 8,
+                ..Default::default()
+            }))
+            .unwrap();
+        let chat = tokenizer.encode_chat(turns.clone(), true).unwrap();
+        assert_eq!(chat.encoding.get_ids(), [5, 1, 3, 7, 6, 2, 4, 7]);
+        assert_eq!(chat.assistant_mask, [0, 0, 0, 0, 0, 1, 1, 1]);
+
+        // The mask gets padded too
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(10),
+            direction: PaddingDirection::Left,
+            ..Default::default()
+        }));
+        let chat = tokenizer.encode_chat(turns, true).unwrap();
+        assert_eq!(chat.encoding.len(), 10);
+        assert_eq!(chat.assistant_mask, [0, 0, 0, 0, 0, 0, 0, 1, 1, 1]);
+
+        // Unknown roles and post-processors without chat support are errors
+        assert!(tokenizer.encode_chat(vec![("system", "hi")], true).is_err());
+        tokenizer.with_post_processor(crate::processors::bert::BertProcessing::default());
+        assert!(tokenizer.encode_chat(vec![("user", "hi")], true).is_err());
+    }
+
+    #[test]
+    fn encode_chat_oldest_first() {
+        let mut tokenizer = tokenizer(ChatTruncation::OldestFirst);
+        let turns = vec![
+            ("user", "hi there"),
+            ("assistant", "hello you"),
+            ("user", "hi"),
+            ("assistant", "hello you there"),
+        ];
+
+        // The oldest turns get dropped, the latest answer is kept whole
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 9,
+                ..Default::default()
+            }))
+            .unwrap();
+        let chat = tokenizer.encode_chat(turns.clone(), true).unwrap();
+        assert_eq!(chat.encoding.get_ids(), [5, 1, 7, 6, 2, 4, 3, 7]);
+        assert_eq!(chat.assistant_mask, [0, 0, 0, 0, 1, 1, 1, 1]);
+
+        // The last turn gets truncated when it doesn't fit alone
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 4,
+                ..Default::default()
+            }))
+            .unwrap();
+        let chat = tokenizer.encode_chat(turns, true).unwrap();
+        assert_eq!(chat.encoding.get_ids(), [6, 2, 4, 7]);
+        assert_eq!(chat.assistant_mask, [0, 1, 1, 1]);
+    }
+}
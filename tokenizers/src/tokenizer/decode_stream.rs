@@ -0,0 +1,229 @@
+use super::{Decoder, Model, Normalizer, PostProcessor, PreTokenizer, Result, TokenizerImpl};
+
+#[derive(thiserror::Error, Debug)]
+pub enum DecodeStreamError {
+    #[error("Invalid prefix encountered while decoding stream: {0:?} does not start with {1:?}")]
+    InvalidPrefix(String, String),
+}
+
+/// A `DecodeStream` decodes ids one at a time, as they are generated, and only returns the
+/// text that has been finalized since the previous step.
+///
+/// Decoding each id independently does not work: some decoders (like `ByteLevel` or
+/// `ByteFallback`) need several tokens to produce a single character, while others (like
+/// `Metaspace`) behave differently for the first token of the sequence. To handle this, the
+/// stream keeps a small window of the previous ids as context, and only emits text once the
+/// decoded string has grown and doesn't end with an incomplete character.
+///
+/// ```
+/// # use tokenizers::Tokenizer;
+/// # use tokenizers::models::wordlevel::WordLevel;
+/// # use tokenizers::decoders::metaspace::Metaspace;
+/// # let vocab = vec![("▁Hey".to_string(), 0), ("▁friend!".to_string(), 1)].into_iter().collect();
+/// # let model = WordLevel::builder().vocab(vocab).build().unwrap();
+/// # let mut tokenizer = Tokenizer::new(model);
+/// # tokenizer.with_decoder(Metaspace::default());
+/// let mut decode_stream = tokenizer.decode_stream(false);
+/// assert_eq!(decode_stream.step(0).unwrap(), Some("Hey".to_string()));
+/// assert_eq!(decode_stream.step(1).unwrap(), Some(" friend!".to_string()));
+/// ```
+pub struct DecodeStream<'tok, M, N, PT, PP, D> {
+    /// The tokenizer used to decode the ids
+    tokenizer: &'tok TokenizerImpl<M, N, PT, PP, D>,
+    /// Whether the special tokens should be skipped while decoding
+    skip_special_tokens: bool,
+    /// The ids currently in the window. The ones before `prefix_index` have already been
+    /// emitted and are only kept as context.
+    ids: Vec<u32>,
+    /// The decoded string of `ids[..prefix_index]`, computed in the context of the window
+    prefix: String,
+    /// The index of the first id in `ids` that has not been emitted yet
+    prefix_index: usize,
+}
+
+impl<'tok, M, N, PT, PP, D> DecodeStream<'tok, M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    pub(crate) fn new(
+        tokenizer: &'tok TokenizerImpl<M, N, PT, PP, D>,
+        skip_special_tokens: bool,
+    ) -> Self {
+        Self {
+            tokenizer,
+            skip_special_tokens,
+            ids: vec![],
+            prefix: String::new(),
+            prefix_index: 0,
+        }
+    }
+
+    /// Add the given id to the stream, and return the newly finalized text, if any.
+    ///
+    /// `None` is returned when the id didn't produce any text yet, for example when it is
+    /// only part of a multi-byte character. This text will be returned with a later id.
+    pub fn step(&mut self, id: u32) -> Result<Option<String>> {
+        self.ids.push(id);
+        let string = self
+            .tokenizer
+            .decode(self.ids.as_slice(), self.skip_special_tokens)?;
+
+        if string.len() > self.prefix.len() && !string.ends_with('�') {
+            if !string.starts_with(&self.prefix) {
+                return Err(DecodeStreamError::InvalidPrefix(string, self.prefix.clone()).into());
+            }
+            let new_text = string[self.prefix.len()..].to_string();
+
+            // Keep the ids that have not been used as context yet, so that the next step
+            // decodes in the same context as this one.
+            let new_prefix_index = self.ids.len() - self.prefix_index;
+            self.ids.drain(..self.prefix_index);
+            self.prefix = self
+                .tokenizer
+                .decode(self.ids.as_slice(), self.skip_special_tokens)?;
+            self.prefix_index = new_prefix_index;
+
+            Ok(Some(new_text))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    /// Create a new [`DecodeStream`], to decode ids one at a time, as they get generated.
+    pub fn decode_stream(&self, skip_special_tokens: bool) -> DecodeStream<'_, M, N, PT, PP, D> {
+        DecodeStream::new(self, skip_special_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::byte_fallback::ByteFallback;
+    use crate::decoders::byte_level::ByteLevel;
+    use crate::decoders::metaspace::Metaspace;
+    use crate::decoders::sequence::Sequence;
+    use crate::models::bpe::BPE;
+    use crate::models::wordlevel::WordLevel;
+    use crate::{AddedToken, Tokenizer};
+    use std::collections::HashMap;
+
+    fn word_level(tokens: &[&str]) -> WordLevel {
+        let vocab: HashMap<String, u32> = tokens
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), i as u32))
+            .collect();
+        WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap()
+    }
+
+    fn stream_all(tokenizer: &Tokenizer, ids: &[u32], skip: bool) -> Vec<Option<String>> {
+        let mut stream = tokenizer.decode_stream(skip);
+        ids.iter().map(|id| stream.step(*id).unwrap()).collect()
+    }
+
+    #[test]
+    fn metaspace() {
+        let mut tokenizer = Tokenizer::new(word_level(&["<unk>", "▁Hey", "▁my", "▁friend", "!"]));
+        tokenizer.with_decoder(Metaspace::default());
+
+        let ids = [1, 2, 3, 4];
+        let outputs = stream_all(&tokenizer, &ids, false);
+        assert_eq!(
+            outputs,
+            vec![
+                Some("Hey".into()),
+                Some(" my".into()),
+                Some(" friend".into()),
+                Some("!".into())
+            ]
+        );
+        let streamed = outputs.into_iter().flatten().collect::<String>();
+        assert_eq!(streamed, tokenizer.decode(&ids, false).unwrap());
+    }
+
+    #[test]
+    fn byte_fallback() {
+        let mut tokenizer = Tokenizer::new(word_level(&[
+            "<unk>",
+            "▁Hey",
+            "<0xE5>",
+            "<0x8F>",
+            "<0xAB>",
+            "▁friend",
+        ]));
+        tokenizer.with_decoder(Sequence::new(vec![
+            ByteFallback::default().into(),
+            Metaspace::default().into(),
+        ]));
+
+        // 叫 is encoded over 3 byte tokens
+        let ids = [1, 2, 3, 4, 5];
+        let outputs = stream_all(&tokenizer, &ids, false);
+        assert_eq!(
+            outputs,
+            vec![
+                Some("Hey".into()),
+                None,
+                None,
+                Some("叫".into()),
+                Some(" friend".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn byte_level() {
+        // "é" is 0xC3 0xA9, which the ByteLevel alphabet represents as "Ã" and "©"
+        let vocab: HashMap<String, u32> = [("Hey", 0), ("Ġfriend", 1), ("Ã", 2), ("©", 3)]
+            .iter()
+            .map(|(t, i)| (t.to_string(), *i))
+            .collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, vec![])
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(bpe);
+        tokenizer.with_decoder(ByteLevel::default());
+
+        let outputs = stream_all(&tokenizer, &[0, 1, 2, 3], false);
+        assert_eq!(
+            outputs,
+            vec![
+                Some("Hey".into()),
+                Some(" friend".into()),
+                None,
+                Some("é".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_special_tokens() {
+        let mut tokenizer = Tokenizer::new(word_level(&["<unk>", "▁Hey", "▁friend"]));
+        tokenizer.with_decoder(Metaspace::default());
+        tokenizer.add_special_tokens(&[AddedToken::from("</s>", true)]);
+        let eos = tokenizer.token_to_id("</s>").unwrap();
+
+        let outputs = stream_all(&tokenizer, &[1, 2, eos], true);
+        assert_eq!(
+            outputs,
+            vec![Some("Hey".into()), Some(" friend".into()), None]
+        );
+    }
+}
@@ -0,0 +1,555 @@
+use super::{
+    Decoder, Model, Normalizer, Offsets, PostProcessor, PreTokenizer, Result, SpecialTokenRole,
+    TokenizerImpl,
+};
+use crate::utils::parallelism::*;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Options controlling which tokens are kept when decoding ids back to a string.
+///
+/// The tokens (or the tokens playing one of the roles) that are explicitly skipped are
+/// always removed, then the ones explicitly kept are always kept. The remaining special
+/// tokens are skipped when `skip_special_tokens` is set.
+///
+/// ```
+/// # use tokenizers::{DecodeOptions, SpecialTokenRole};
+/// // Keep the tool calls, but skip all the other special tokens
+/// let options = DecodeOptions::new(true).keep_token("<tool_call>");
+/// // Keep all the special tokens, but the padding
+/// let options = DecodeOptions::new(false).skip_role(SpecialTokenRole::Pad);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Whether the special tokens should be skipped
+    pub skip_special_tokens: bool,
+    /// The tokens to keep, even if special
+    pub keep_tokens: HashSet<String>,
+    /// The tokens to always skip
+    pub skip_tokens: HashSet<String>,
+    /// The roles whose token should be kept, even if special
+    pub keep_roles: HashSet<SpecialTokenRole>,
+    /// The roles whose token should always be skipped
+    pub skip_roles: HashSet<SpecialTokenRole>,
+    /// The string used in place of the ids that are not part of the vocabulary. These ids
+    /// are removed when this is not set.
+    pub unknown_id_placeholder: Option<String>,
+}
+
+impl DecodeOptions {
+    pub fn new(skip_special_tokens: bool) -> Self {
+        Self {
+            skip_special_tokens,
+            ..Default::default()
+        }
+    }
+
+    /// Keep the given token, even if it is special
+    #[must_use]
+    pub fn keep_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.keep_tokens.insert(token.into());
+        self
+    }
+
+    /// Always skip the given token
+    #[must_use]
+    pub fn skip_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.skip_tokens.insert(token.into());
+        self
+    }
+
+    /// Keep the token playing the given role, even if it is special
+    #[must_use]
+    pub fn keep_role(mut self, role: SpecialTokenRole) -> Self {
+        self.keep_roles.insert(role);
+        self
+    }
+
+    /// Always skip the token playing the given role
+    #[must_use]
+    pub fn skip_role(mut self, role: SpecialTokenRole) -> Self {
+        self.skip_roles.insert(role);
+        self
+    }
+
+    /// Replace the ids that are not part of the vocabulary with the given placeholder
+    #[must_use]
+    pub fn unknown_id_placeholder<S: Into<String>>(mut self, placeholder: S) -> Self {
+        self.unknown_id_placeholder = Some(placeholder.into());
+        self
+    }
+}
+
+/// The smallest range containing both of the given ones, an empty range meaning no token
+fn union(a: Range<usize>, b: Range<usize>) -> Range<usize> {
+    if a.is_empty() {
+        b
+    } else if b.is_empty() {
+        a
+    } else {
+        a.start.min(b.start)..a.end.max(b.end)
+    }
+}
+
+/// A string produced while decoding, which keeps track of the input tokens each of its
+/// parts comes from.
+///
+/// It is made of chunks, each of them with the range of the input tokens that produced it.
+/// A chunk can come from several tokens (when they get merged into a single character for
+/// example), and an empty range means that it doesn't come from any token in particular.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlignedString {
+    chunks: Vec<(String, Range<usize>)>,
+}
+
+impl AlignedString {
+    /// A string produced by the input token at index `token`
+    pub fn new<S: Into<String>>(text: S, token: usize) -> Self {
+        Self::with_sources(text, token..token + 1)
+    }
+
+    /// A string produced by the input tokens in the given range
+    pub fn with_sources<S: Into<String>>(text: S, sources: Range<usize>) -> Self {
+        Self {
+            chunks: vec![(text.into(), sources)],
+        }
+    }
+
+    /// Build a string from its characters, each with the tokens it comes from
+    pub fn from_chars<I: IntoIterator<Item = (char, Range<usize>)>>(chars: I) -> Self {
+        let mut string = Self::default();
+        for (c, sources) in chars {
+            string.push(c.to_string(), sources);
+        }
+        string
+    }
+
+    /// Build a string from bytes, each with the tokens it comes from, replacing the invalid
+    /// sequences like `String::from_utf8_lossy` does. Each character comes from all the
+    /// tokens of its bytes.
+    pub fn from_utf8_lossy(bytes: &[(u8, Range<usize>)]) -> Self {
+        let raw = bytes.iter().map(|(b, _)| *b).collect::<Vec<_>>();
+        let sources = |range: Range<usize>| {
+            bytes[range]
+                .iter()
+                .map(|(_, sources)| sources.clone())
+                .reduce(union)
+                .unwrap_or(0..0)
+        };
+
+        let mut string = Self::default();
+        let mut pos = 0;
+        for chunk in raw.utf8_chunks() {
+            for (i, c) in chunk.valid().char_indices() {
+                let start = pos + i;
+                string.push(c.to_string(), sources(start..start + c.len_utf8()));
+            }
+            pos += chunk.valid().len();
+            if !chunk.invalid().is_empty() {
+                string.push(
+                    char::REPLACEMENT_CHARACTER.to_string(),
+                    sources(pos..pos + chunk.invalid().len()),
+                );
+                pos += chunk.invalid().len();
+            }
+        }
+        string
+    }
+
+    pub fn chunks(&self) -> &[(String, Range<usize>)] {
+        &self.chunks
+    }
+
+    /// The characters of this string, each with the tokens it comes from
+    pub fn chars(&self) -> impl Iterator<Item = (char, Range<usize>)> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(|(text, sources)| text.chars().map(move |c| (c, sources.clone())))
+    }
+
+    /// The whole string
+    pub fn get(&self) -> String {
+        self.chunks.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.iter().all(|(text, _)| text.is_empty())
+    }
+
+    /// The range of all the tokens this string comes from
+    pub fn sources(&self) -> Range<usize> {
+        self.chunks
+            .iter()
+            .map(|(_, sources)| sources.clone())
+            .reduce(union)
+            .unwrap_or(0..0)
+    }
+
+    /// Append the given text, coming from the tokens in `sources`
+    pub fn push<S: Into<String>>(&mut self, text: S, sources: Range<usize>) {
+        let text = text.into();
+        match self.chunks.last_mut() {
+            Some((last, last_sources)) if *last_sources == sources => last.push_str(&text),
+            _ => self.chunks.push((text, sources)),
+        }
+    }
+
+    /// Append the given string
+    pub fn extend(&mut self, other: Self) {
+        for (text, sources) in other.chunks {
+            self.push(text, sources);
+        }
+    }
+
+    /// Transform this string with `f`, keeping track of the tokens as precisely as possible.
+    ///
+    /// When transforming each chunk on its own gives the same result as transforming the
+    /// whole string, the chunks keep their tokens. Otherwise, the whole result is attributed
+    /// to all the tokens of this string.
+    pub fn try_map<F>(&self, f: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Result<String>,
+    {
+        let whole = f(&self.get())?;
+        if self.chunks.len() > 1 {
+            let chunks = self
+                .chunks
+                .iter()
+                .map(|(text, sources)| Ok((f(text)?, sources.clone())))
+                .collect::<Result<Vec<_>>>()?;
+            if chunks
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<String>()
+                == whole
+            {
+                return Ok(Self { chunks });
+            }
+        }
+        Ok(Self::with_sources(whole, self.sources()))
+    }
+
+    /// Transform this string with `f`, like [`try_map`](Self::try_map)
+    pub fn map<F: Fn(&str) -> String>(&self, f: F) -> Self {
+        self.try_map(|text| Ok(f(text)))
+            .expect("An infallible transformation cannot fail")
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    /// The tokens to decode, selected by `options`, with the index of their id
+    fn tokens_to_decode(&self, ids: &[u32], options: &DecodeOptions) -> Vec<(usize, String)> {
+        let role_tokens = |roles: &HashSet<SpecialTokenRole>| {
+            roles
+                .iter()
+                .filter_map(|role| self.get_special_token_role(*role))
+                .collect::<HashSet<_>>()
+        };
+        let skip_roles = role_tokens(&options.skip_roles);
+        let keep_roles = role_tokens(&options.keep_roles);
+        let keep = |token: &str| {
+            if options.skip_tokens.contains(token) || skip_roles.contains(token) {
+                false
+            } else if options.keep_tokens.contains(token) || keep_roles.contains(token) {
+                true
+            } else {
+                !options.skip_special_tokens || !self.added_vocabulary.is_special_token(token)
+            }
+        };
+
+        ids.iter()
+            .enumerate()
+            .filter_map(|(i, id)| {
+                match self.added_vocabulary.id_to_token(*id, &self.model) {
+                    Some(token) => Some(token).filter(|token| keep(token)),
+                    None => options.unknown_id_placeholder.clone(),
+                }
+                .map(|token| (i, token))
+            })
+            .collect()
+    }
+
+    /// Decode the given ids back to a String, keeping the tokens selected by `options`
+    pub fn decode_with_options(&self, ids: &[u32], options: &DecodeOptions) -> Result<String> {
+        let tokens = self
+            .tokens_to_decode(ids, options)
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect::<Vec<_>>();
+
+        if let Some(decoder) = &self.decoder {
+            decoder.decode(tokens)
+        } else {
+            Ok(tokens.join(" "))
+        }
+    }
+
+    /// Decode the given ids back to a String, like `decode`, along with the byte offsets
+    /// of the part of this String produced by each id.
+    ///
+    /// When several ids produce a single character together (like the bytes of a character
+    /// with `ByteLevel`), they all get the offsets of this character. The ids that don't
+    /// produce anything, like the skipped special tokens, get empty offsets at the position
+    /// where they would be.
+    pub fn decode_with_offsets(
+        &self,
+        ids: &[u32],
+        skip_special_tokens: bool,
+    ) -> Result<(String, Vec<Offsets>)> {
+        self.decode_with_offsets_and_options(ids, &DecodeOptions::new(skip_special_tokens))
+    }
+
+    /// Decode the given ids back to a String along with the offsets produced by each id,
+    /// like `decode_with_offsets`, keeping the tokens selected by `options`
+    pub fn decode_with_offsets_and_options(
+        &self,
+        ids: &[u32],
+        options: &DecodeOptions,
+    ) -> Result<(String, Vec<Offsets>)> {
+        let tokens = self.tokens_to_decode(ids, options);
+        let pieces = if let Some(decoder) = &self.decoder {
+            decoder.decode_chain_aligned(
+                tokens
+                    .into_iter()
+                    .map(|(i, token)| AlignedString::new(token, i))
+                    .collect(),
+            )?
+        } else {
+            let mut string = AlignedString::default();
+            for (n, (i, token)) in tokens.into_iter().enumerate() {
+                if n > 0 {
+                    string.push(" ", 0..0);
+                }
+                string.push(token, i..i + 1);
+            }
+            vec![string]
+        };
+
+        let mut decoded = String::new();
+        let mut offsets: Vec<Option<Offsets>> = vec![None; ids.len()];
+        for (text, sources) in pieces.iter().flat_map(|piece| piece.chunks()) {
+            let (start, end) = (decoded.len(), decoded.len() + text.len());
+            for offset in &mut offsets[sources.clone()] {
+                *offset = Some(offset.map_or((start, end), |(s, e)| (s.min(start), e.max(end))));
+            }
+            decoded.push_str(text);
+        }
+
+        let mut last_end = 0;
+        let offsets = offsets
+            .into_iter()
+            .map(|offset| {
+                let offset = offset.unwrap_or((last_end, last_end));
+                last_end = offset.1;
+                offset
+            })
+            .collect();
+        Ok((decoded, offsets))
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model + Send + Sync,
+    N: Normalizer + Send + Sync,
+    PT: PreTokenizer + Send + Sync,
+    PP: PostProcessor + Send + Sync,
+    D: Decoder + Send + Sync,
+{
+    /// Decode all sentences in parallel, keeping the tokens selected by `options`
+    pub fn decode_batch_with_options(
+        &self,
+        sentences: &[&[u32]],
+        options: &DecodeOptions,
+    ) -> Result<Vec<String>> {
+        sentences
+            .into_maybe_par_iter()
+            .map(|sentence| self.decode_with_options(sentence, options))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::byte_fallback::ByteFallback;
+    use crate::decoders::fuse::Fuse;
+    use crate::decoders::sequence::Sequence;
+    use crate::decoders::strip::Strip;
+    use crate::decoders::wordpiece::WordPiece;
+    use crate::decoders::DecoderWrapper;
+    use crate::models::wordlevel::WordLevel;
+    use crate::normalizers::replace::Replace;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::{AddedToken, Tokenizer};
+
+    fn tokenizer() -> Tokenizer {
+        let vocab = [("hello", 0), ("world", 1), ("<unk>", 2)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("<pad>", true),
+            AddedToken::from("<eos>", true),
+            AddedToken::from("<tool_call>", true),
+        ]);
+        tokenizer
+            .with_special_token_role(SpecialTokenRole::Eos, "<eos>")
+            .unwrap()
+            .with_special_token_role(SpecialTokenRole::Pad, "<pad>")
+            .unwrap();
+        tokenizer
+    }
+
+    #[test]
+    fn decode_with_options() {
+        let tokenizer = tokenizer();
+        // hello <tool_call> world <eos> <pad>
+        let ids = [0, 5, 1, 4, 3];
+
+        let decode =
+            |options: &DecodeOptions| tokenizer.decode_with_options(&ids, options).unwrap();
+        assert_eq!(
+            decode(&DecodeOptions::new(false)),
+            "hello <tool_call> world <eos> <pad>"
+        );
+        assert_eq!(decode(&DecodeOptions::new(true)), "hello world");
+        assert_eq!(
+            decode(&DecodeOptions::new(true).keep_token("<tool_call>")),
+            "hello <tool_call> world"
+        );
+        assert_eq!(
+            decode(&DecodeOptions::new(true).keep_role(SpecialTokenRole::Eos)),
+            "hello world <eos>"
+        );
+        assert_eq!(
+            decode(
+                &DecodeOptions::new(false)
+                    .skip_role(SpecialTokenRole::Pad)
+                    .skip_token("<eos>")
+            ),
+            "hello <tool_call> world"
+        );
+        // Skipping wins over keeping
+        assert_eq!(
+            decode(
+                &DecodeOptions::new(true)
+                    .keep_token("<eos>")
+                    .skip_role(SpecialTokenRole::Eos)
+            ),
+            "hello world"
+        );
+        // This is what `decode` does
+        assert_eq!(
+            decode(&DecodeOptions::new(true)),
+            tokenizer.decode(&ids, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_id_placeholder() {
+        let tokenizer = tokenizer();
+        let ids = [0, 42, 1];
+        assert_eq!(
+            tokenizer
+                .decode_with_options(&ids, &DecodeOptions::new(false))
+                .unwrap(),
+            "hello world"
+        );
+        assert_eq!(
+            tokenizer
+                .decode_with_options(
+                    &ids,
+                    &DecodeOptions::new(false).unknown_id_placeholder("<?>")
+                )
+                .unwrap(),
+            "hello <?> world"
+        );
+    }
+
+    fn tokenizer_with_decoder(tokens: &[&str], decoder: impl Into<DecoderWrapper>) -> Tokenizer {
+        let vocab = tokens
+            .iter()
+            .enumerate()
+            .map(|(id, t)| (t.to_string(), id as u32))
+            .collect();
+        let model = WordLevel::builder().vocab(vocab).build().unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_decoder(decoder);
+        tokenizer
+    }
+
+    #[test]
+    fn decode_with_offsets() {
+        let tokenizer = tokenizer();
+        let ids = [0, 5, 1, 4, 3];
+        assert_eq!(
+            tokenizer.decode_with_offsets(&ids, false).unwrap(),
+            (
+                "hello <tool_call> world <eos> <pad>".into(),
+                vec![(0, 5), (6, 17), (18, 23), (24, 29), (30, 35)]
+            )
+        );
+        // The skipped tokens get empty offsets
+        assert_eq!(
+            tokenizer.decode_with_offsets(&ids, true).unwrap(),
+            (
+                "hello world".into(),
+                vec![(0, 5), (5, 5), (6, 11), (11, 11), (11, 11)]
+            )
+        );
+    }
+
+    #[test]
+    fn decode_with_offsets_byte_level() {
+        // `é` is split in two tokens, one for each of its bytes
+        let tokenizer = tokenizer_with_decoder(&["Ġcaf", "Ã", "©", "!"], ByteLevel::default());
+        let ids = [0, 1, 2, 3];
+        let (decoded, offsets) = tokenizer.decode_with_offsets(&ids, false).unwrap();
+        assert_eq!(decoded, tokenizer.decode(&ids, false).unwrap());
+        assert_eq!(decoded, " café!");
+        assert_eq!(offsets, [(0, 4), (4, 6), (4, 6), (6, 7)]);
+    }
+
+    #[test]
+    fn decode_with_offsets_sequence() {
+        // Like the decoder of Llama
+        let decoder = Sequence::new(vec![
+            Replace::new("▁", " ").unwrap().into(),
+            ByteFallback::new().into(),
+            Fuse::new().into(),
+            Strip::new(' ', 1, 0).into(),
+        ]);
+        let tokenizer =
+            tokenizer_with_decoder(&["▁caf", "<0xC3>", "<0xA9>", "▁is", "<0xFF>"], decoder);
+        let ids = [0, 1, 2, 3, 4];
+        let (decoded, offsets) = tokenizer.decode_with_offsets(&ids, false).unwrap();
+        assert_eq!(decoded, tokenizer.decode(&ids, false).unwrap());
+        assert_eq!(decoded, "café is�");
+        assert_eq!(offsets, [(0, 3), (3, 5), (3, 5), (5, 8), (8, 11)]);
+    }
+
+    #[test]
+    fn decode_with_offsets_wordpiece() {
+        let tokenizer =
+            tokenizer_with_decoder(&["hello", "##s", "world", "."], WordPiece::default());
+        let ids = [0, 1, 2, 3];
+        let (decoded, offsets) = tokenizer.decode_with_offsets(&ids, false).unwrap();
+        assert_eq!(decoded, tokenizer.decode(&ids, false).unwrap());
+        assert_eq!(decoded, "hellos world.");
+        assert_eq!(offsets, [(0, 5), (5, 6), (6, 12), (12, 13)]);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::parallelism::*;
 use crate::tokenizer::{Offsets, Token};
 use crate::utils::padding::PaddingDirection;
-use crate::utils::truncation::TruncationDirection;
+use crate::utils::truncation::{TruncationBoundary, TruncationDirection};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ops::Range;
@@ -316,6 +316,15 @@ impl Encoding {
             return;
         }
 
+        if direction == TruncationDirection::Middle {
+            assert!(
+                stride == 0,
+                "`stride` can't be used when truncating the middle"
+            );
+            self.truncate_middle(max_len - max_len / 2, max_len / 2);
+            return;
+        }
+
         assert!(stride < max_len, "`stride` must be strictly less than `max_len={}` (note that `max_len` may be shorter than the max length of the original model, as it subtracts the number of special characters", max_len);
 
         // When truncating, we lose the `sequence_ranges` information.
@@ -350,11 +359,69 @@ impl Encoding {
                     }
                 })
                 .collect(),
+            TruncationDirection::Middle => unreachable!("Middle truncation is handled above"),
         };
 
         let mut i = 0;
         let (start, stop) = parts_ranges[i];
-        let mut new_encoding = Encoding {
+        let mut new_encoding = self.slice(start, stop);
+
+        loop {
+            if i == parts_ranges.len() - 1 {
+                break;
+            }
+            i += 1;
+            let (start, stop) = parts_ranges[i];
+            new_encoding.overflowing.push(self.slice(start, stop));
+        }
+        *self = new_encoding;
+    }
+
+    /// Truncate the current `Encoding` by removing tokens from its middle, keeping only the
+    /// first `head` and the last `tail` tokens.
+    ///
+    /// The removed tokens are added as a single overflowing `Encoding`.
+    pub fn truncate_middle(&mut self, head: usize, tail: usize) {
+        let encoding_len = self.ids.len();
+        if head + tail >= encoding_len {
+            return;
+        }
+
+        // When truncating, we lose the `sequence_ranges` information.
+        self.sequence_ranges.clear();
+
+        let mut new_encoding = self.slice(0, head);
+        new_encoding.merge_with(self.slice(encoding_len - tail, encoding_len), false);
+        new_encoding
+            .overflowing
+            .push(self.slice(head, encoding_len - tail));
+        *self = new_encoding;
+    }
+
+    /// Whether this `Encoding` can be cut right before the token at `index` while
+    /// respecting the given boundary.
+    pub(crate) fn is_boundary(&self, index: usize, boundary: TruncationBoundary) -> bool {
+        if index == 0 || index >= self.len() {
+            return true;
+        }
+        let word_boundary =
+            self.words[index].is_none() || self.words[index] != self.words[index - 1];
+        match boundary {
+            TruncationBoundary::Token => true,
+            TruncationBoundary::Word => word_boundary,
+            TruncationBoundary::Sentence => {
+                word_boundary
+                    && self.tokens[index - 1]
+                        .trim_end()
+                        .ends_with(&['.', '!', '?', '。', '！', '？'][..])
+            }
+        }
+    }
+
+    /// Extract the tokens in `start..stop` as a new `Encoding`, without any overflowing
+    /// part nor `sequence_ranges` information.
+    pub(crate) fn slice(&self, start: usize, stop: usize) -> Encoding {
+        Encoding {
             ids: self.ids[start..stop].to_vec(),
             type_ids: self.type_ids[start..stop].to_vec(),
             tokens: self.tokens[start..stop].to_vec(),
@@ -364,27 +431,7 @@ impl Encoding {
             attention_mask: self.attention_mask[start..stop].to_vec(),
             overflowing: vec![],
             sequence_ranges: HashMap::new(),
-        };
-
-        loop {
-            if i == parts_ranges.len() - 1 {
-                break;
-            }
-            i += 1;
-            let (start, stop) = parts_ranges[i];
-            new_encoding.overflowing.push(Encoding {
-                ids: self.ids[start..stop].to_vec(),
-                type_ids: self.type_ids[start..stop].to_vec(),
-                tokens: self.tokens[start..stop].to_vec(),
-                words: self.words[start..stop].to_vec(),
-                offsets: self.offsets[start..stop].to_vec(),
-                special_tokens_mask: self.special_tokens_mask[start..stop].to_vec(),
-                attention_mask: self.attention_mask[start..stop].to_vec(),
-                overflowing: vec![],
-                sequence_ranges: HashMap::new(),
-            });
         }
-        *self = new_encoding;
     }
 
     /// Merge all Encodings together
@@ -0,0 +1,265 @@
+use super::{
+    AddedTokensError, BuilderError, DecodeStreamError, ProcessorError, SpecialTokenRoleError,
+};
+use crate::models::bpe;
+use crate::models::unigram::{UnigramError, UnigramTrainerError};
+use crate::models::{wordlevel, wordpiece, ModelError};
+use crate::processors::bert::BertProcessorError;
+use crate::utils::packing::PackingError;
+use crate::utils::padding::PaddingError;
+use crate::utils::sentencepiece::SentencePieceError;
+use crate::utils::truncation::TruncationError;
+use std::fmt;
+
+/// The files read line by line, whose errors point at the invalid line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParsedFile {
+    /// A `merges.txt` file of a BPE
+    Merges,
+    /// A `.tiktoken` file, with the tokens of a BPE and their rank
+    Tiktoken,
+}
+
+impl fmt::Display for ParsedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Merges => write!(f, "Merges text"),
+            Self::Tiktoken => write!(f, "Tiktoken"),
+        }
+    }
+}
+
+/// The errors returned by the tokenizers, by subsystem
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing a file failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A model could not be built, loaded or trained
+    #[error(transparent)]
+    Model(#[from] ModelError),
+    /// A merges or tiktoken file is invalid, at the given line, starting from 1
+    #[error("{file} file invalid at line {line}")]
+    Parse { file: ParsedFile, line: usize },
+    #[error(transparent)]
+    Truncation(#[from] TruncationError),
+    /// The encodings could not be padded, like without a token to pad with
+    #[error(transparent)]
+    Padding(#[from] PaddingError),
+    /// The encodings could not be packed, like into empty rows
+    #[error(transparent)]
+    Packing(#[from] PackingError),
+    /// The added tokens are inconsistent, like a special token role given to a missing token
+    #[error(transparent)]
+    AddedTokens(#[from] AddedTokensError),
+    /// The serialized tokenizer uses a version of the format that is not supported
+    #[error("Unknown tokenizer version '{0}'")]
+    UnsupportedVersion(String),
+    /// Serializing or deserializing some JSON failed
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Processor(#[from] ProcessorError),
+    #[error(transparent)]
+    Builder(#[from] BuilderError),
+    #[error(transparent)]
+    DecodeStream(#[from] DecodeStreamError),
+    /// Any other error, like an invalid configuration or a failure of a custom component
+    #[error(transparent)]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Wrap any other error
+    pub fn other<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> Self {
+        Self::Other(error.into())
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::other(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::other(message)
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for Error {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self::Other(error)
+    }
+}
+
+macro_rules! impl_from_other {
+    ($($error:ty),*) => {
+        $(impl From<$error> for Error {
+            fn from(error: $error) -> Self {
+                Self::other(error)
+            }
+        })*
+    };
+}
+
+impl_from_other!(
+    regex::Error,
+    spm_precompiled::PrecompiledError,
+    std::array::TryFromSliceError,
+    std::string::FromUtf8Error
+);
+
+#[cfg(not(feature = "unstable_wasm"))]
+impl_from_other!(onig::Error);
+#[cfg(feature = "unstable_wasm")]
+impl_from_other!(fancy_regex::Error);
+#[cfg(feature = "http")]
+impl_from_other!(ureq::Error);
+
+macro_rules! impl_from_builder_error {
+    ($($error:ty),*) => {
+        $(impl From<$error> for Error {
+            fn from(error: $error) -> Self {
+                Self::Builder(BuilderError(error.to_string()))
+            }
+        })*
+    };
+}
+
+impl_from_builder_error!(
+    crate::models::unigram::UnigramTrainerBuilderError,
+    crate::models::wordlevel::WordLevelTrainerBuilderError
+);
+
+impl From<bpe::Error> for Error {
+    fn from(error: bpe::Error) -> Self {
+        match error {
+            bpe::Error::Io(error) => Self::Io(error),
+            bpe::Error::JsonError(error) => Self::Serialization(error),
+            bpe::Error::BadMerges(line) => Self::Parse {
+                file: ParsedFile::Merges,
+                line,
+            },
+            bpe::Error::BadTiktoken(line) => Self::Parse {
+                file: ParsedFile::Tiktoken,
+                line,
+            },
+            error => Self::Model(error.into()),
+        }
+    }
+}
+
+macro_rules! impl_from_model_error {
+    ($($error:ty),*) => {
+        $(impl From<$error> for Error {
+            fn from(error: $error) -> Self {
+                Self::Model(error.into())
+            }
+        })*
+    };
+}
+
+impl_from_model_error!(
+    wordpiece::Error,
+    wordlevel::Error,
+    UnigramError,
+    UnigramTrainerError,
+    SentencePieceError
+);
+
+#[cfg(feature = "http")]
+impl_from_model_error!(crate::utils::from_pretrained::PretrainedError);
+
+impl From<SpecialTokenRoleError> for Error {
+    fn from(error: SpecialTokenRoleError) -> Self {
+        Self::AddedTokens(error.into())
+    }
+}
+
+impl From<BertProcessorError> for Error {
+    fn from(error: BertProcessorError) -> Self {
+        match error {
+            BertProcessorError::InvalidEncodingsVecLength => {
+                Self::Processor(ProcessorError::InvalidEncodingsVecLength)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::processors::template::TemplateProcessing;
+    use crate::{
+        pad_encodings, Encoding, PaddingParams, PaddingStrategy, SpecialTokenRole, Tokenizer,
+        TruncationParams,
+    };
+
+    #[test]
+    fn module_errors() {
+        assert!(matches!(
+            Error::from(bpe::Error::BadTiktoken(3)),
+            Error::Parse {
+                file: ParsedFile::Tiktoken,
+                line: 3
+            }
+        ));
+        assert!(matches!(
+            Error::from(bpe::Error::InvalidDropout),
+            Error::Model(ModelError::BPE(bpe::Error::InvalidDropout))
+        ));
+        assert!(matches!(
+            Error::from(BertProcessorError::InvalidEncodingsVecLength),
+            Error::Processor(ProcessorError::InvalidEncodingsVecLength)
+        ));
+
+        let mut tokenizer = Tokenizer::new(WordLevel::default());
+        let error = tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 2,
+                stride: 3,
+                ..Default::default()
+            }))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Truncation(TruncationError::InvalidParams(_))
+        ));
+        // The messages are the ones of the module errors
+        assert!(error
+            .to_string()
+            .starts_with("Truncation error: tokenizer stride"));
+
+        let error = tokenizer
+            .with_padding_from_role(PaddingParams::default())
+            .unwrap_err();
+        assert!(matches!(error, Error::Padding(PaddingError::PadToken(_))));
+        assert_eq!(error.to_string(), "No token has the pad role");
+        let mut encodings = [Encoding::default()];
+        let params = PaddingParams {
+            strategy: PaddingStrategy::Buckets(vec![]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            pad_encodings(&mut encodings, &params),
+            Err(Error::Padding(PaddingError::NoBuckets))
+        ));
+
+        assert!(matches!(
+            TemplateProcessing::builder()
+                .try_pair("$A $A")
+                .unwrap()
+                .build(),
+            Err(ProcessorError::PairSequences)
+        ));
+        assert!(matches!(
+            Error::from(SpecialTokenRoleError::MissingRole(SpecialTokenRole::Eos)),
+            Error::AddedTokens(AddedTokensError::Role(_))
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+use super::{
+    Decoder, EncodeInput, Encoding, Model, Normalizer, OffsetType, PostProcessor, PreTokenizer,
+    Result, SpecialTokensMatching, TokenizerImpl, TruncationBoundary,
+};
+use crate::utils::parallelism::*;
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    /// Run the whole pipeline on the given input, like `encode`, but build an `Encoding`
+    /// without tokens nor offsets
+    fn encode_light<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Encoding>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        // Truncating on sentence boundaries needs the tokens
+        let needs_tokens = matches!(
+            &self.truncation,
+            Some(trunc) if trunc.boundary == TruncationBoundary::Sentence
+        );
+
+        let encode_sequence = |sequence, type_id| {
+            self.encode_single_sequence_with(sequence, matching, |pre_tokenized, word_idx| {
+                if needs_tokens {
+                    self.do_tokenize(pre_tokenized, type_id, word_idx, OffsetType::Byte, matching)
+                } else {
+                    pre_tokenized.into_ids_encoding(
+                        |normalized| self.tokenize_split_ids(normalized.get(), matching),
+                        word_idx,
+                        type_id,
+                    )
+                }
+            })
+        };
+
+        let encodings = input
+            .into()
+            .into_sequences()
+            .into_iter()
+            .enumerate()
+            .map(|(i, sequence)| encode_sequence(sequence, i as u32))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.post_process_multi(encodings, add_special_tokens)
+    }
+
+    /// Encode the given input into its ids only.
+    ///
+    /// This gives the same ids as `encode`, including the added tokens, the special tokens
+    /// added by the post-processor, truncation and padding, but does not build the tokens
+    /// and offsets of a full `Encoding`.
+    pub fn encode_ids<'s, E>(&self, input: E, add_special_tokens: bool) -> Result<Vec<u32>>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        self.encode_ids_with_matching(input, add_special_tokens, &SpecialTokensMatching::All)
+    }
+
+    /// Encode the given input into its ids only. Only the special tokens allowed by
+    /// `matching` are extracted from the input, the others being tokenized as plain text
+    /// by the model.
+    pub fn encode_ids_with_matching<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<u32>>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        Ok(self
+            .encode_light(input, add_special_tokens, matching)?
+            .get_ids()
+            .to_vec())
+    }
+
+    /// Count the tokens the given input is encoded into, padding excluded.
+    pub fn count_tokens<'s, E>(&self, input: E, add_special_tokens: bool) -> Result<usize>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        self.count_tokens_with_matching(input, add_special_tokens, &SpecialTokensMatching::All)
+    }
+
+    /// Count the tokens the given input is encoded into, padding excluded. Only the special
+    /// tokens allowed by `matching` are extracted from the input.
+    pub fn count_tokens_with_matching<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<usize>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        Ok(self
+            .encode_light(input, add_special_tokens, matching)?
+            .get_attention_mask()
+            .iter()
+            .filter(|mask| **mask == 1)
+            .count())
+    }
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model + Send + Sync,
+    N: Normalizer + Send + Sync,
+    PT: PreTokenizer + Send + Sync,
+    PP: PostProcessor + Send + Sync,
+    D: Decoder + Send + Sync,
+{
+    /// Encode all the inputs in parallel into their ids only, padding them like
+    /// `encode_batch` does
+    pub fn encode_ids_batch<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<Vec<u32>>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        self.encode_ids_batch_with_matching(inputs, add_special_tokens, &SpecialTokensMatching::All)
+    }
+
+    /// Encode all the inputs in parallel into their ids only, padding them like
+    /// `encode_batch` does. Only the special tokens allowed by `matching` are extracted from
+    /// the inputs.
+    pub fn encode_ids_batch_with_matching<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<Vec<u32>>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        let mut encodings = inputs
+            .into_maybe_par_iter()
+            .map(|input| self.encode_light(input, add_special_tokens, matching))
+            .collect::<Result<Vec<Encoding>>>()?;
+
+        if let Some(params) = &self.padding {
+            // We do the padding here to make sure we handle the batch padding
+            super::pad_encodings(&mut encodings, params)?;
+        }
+
+        Ok(encodings
+            .into_iter()
+            .map(|encoding| encoding.get_ids().to_vec())
+            .collect())
+    }
+
+    /// Count in parallel the tokens each input is encoded into, padding excluded
+    pub fn count_tokens_batch<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<usize>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        self.count_tokens_batch_with_matching(
+            inputs,
+            add_special_tokens,
+            &SpecialTokensMatching::All,
+        )
+    }
+
+    /// Count in parallel the tokens each input is encoded into, padding excluded. Only the
+    /// special tokens allowed by `matching` are extracted from the inputs.
+    pub fn count_tokens_batch_with_matching<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<usize>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        inputs
+            .into_maybe_par_iter()
+            .map(|input| self.count_tokens_with_matching(input, add_special_tokens, matching))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::bpe::BPE;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::processors::template::TemplateProcessing;
+    use crate::{
+        AddedToken, EncodeInput, PaddingParams, PaddingStrategy, Tokenizer, TruncationDirection,
+        TruncationParams,
+    };
+
+    fn tokenizer() -> Tokenizer {
+        let vocab = [("a", 0), ("b", 1), ("ab", 2), ("Ġ", 3), ("Ġa", 4)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let merges = vec![
+            ("a".to_string(), "b".to_string()),
+            ("Ġ".to_string(), "a".to_string()),
+        ];
+        let model = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(ByteLevel::default().add_prefix_space(false));
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("<s>", true),
+            AddedToken::from("</s>", true),
+        ]);
+        tokenizer.with_post_processor(
+            TemplateProcessing::builder()
+                .try_single("<s> $A </s>")
+                .unwrap()
+                .try_pair("<s> $A </s> $B:1 </s>:1")
+                .unwrap()
+                .try_multi("<s> $A </s> $*:1 </s>:1")
+                .unwrap()
+                .special_tokens(vec![("<s>", 5), ("</s>", 6)])
+                .build()
+                .unwrap(),
+        );
+        tokenizer
+    }
+
+    #[test]
+    fn disallowed_special_token_in_vocab() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+        use crate::SpecialTokensMatching;
+
+        let vocab = [("[UNK]", 0), ("[CLS]", 1), ("hello", 2)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+
+        let matching = SpecialTokensMatching::None;
+        let encoding = tokenizer
+            .encode_with_matching("hello [CLS]", false, &matching)
+            .unwrap();
+        assert_eq!(encoding.get_ids(), &[2, 0, 0, 0, 0, 0]);
+        assert_eq!(encoding.get_offsets()[1..3], [(6, 7), (7, 8)]);
+        let ids = tokenizer
+            .encode_ids_with_matching("hello [CLS]", false, &matching)
+            .unwrap();
+        assert_eq!(ids, encoding.get_ids());
+
+        let ids = tokenizer.encode_ids("hello [CLS]", false).unwrap();
+        assert_eq!(ids, [2, 1]);
+
+        let inputs = vec!["hello [CLS]", "[CLS]"];
+        let ids = tokenizer
+            .encode_ids_batch_with_matching(inputs.clone(), false, &matching)
+            .unwrap();
+        assert_eq!(ids, vec![vec![2, 0, 0, 0, 0, 0], vec![0, 0, 0, 0, 0]]);
+        let counts = tokenizer
+            .count_tokens_batch_with_matching(inputs.clone(), false, &matching)
+            .unwrap();
+        assert_eq!(counts, vec![6, 5]);
+        assert_eq!(
+            tokenizer.count_tokens_batch(inputs, false).unwrap(),
+            vec![2, 1]
+        );
+    }
+
+    #[test]
+    fn same_ids_as_encode() {
+        let mut tokenizer = tokenizer();
+        let inputs = vec!["ab a</s>b", "a ab ab a", "b"];
+        let check = |tokenizer: &Tokenizer| {
+            for input in &inputs {
+                for add_special_tokens in [true, false].iter() {
+                    let encoding = tokenizer.encode(*input, *add_special_tokens).unwrap();
+                    let ids = tokenizer.encode_ids(*input, *add_special_tokens).unwrap();
+                    assert_eq!(encoding.get_ids(), ids.as_slice());
+                    let pair = tokenizer
+                        .encode((*input, "a"), *add_special_tokens)
+                        .unwrap();
+                    let ids = tokenizer
+                        .encode_ids((*input, "a"), *add_special_tokens)
+                        .unwrap();
+                    assert_eq!(pair.get_ids(), ids.as_slice());
+                }
+            }
+            let encodings = tokenizer.encode_batch(inputs.clone(), true).unwrap();
+            let ids = tokenizer.encode_ids_batch(inputs.clone(), true).unwrap();
+            assert_eq!(
+                encodings
+                    .iter()
+                    .map(|e| e.get_ids().to_vec())
+                    .collect::<Vec<_>>(),
+                ids
+            );
+        };
+
+        check(&tokenizer);
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 5,
+                direction: TruncationDirection::Left,
+                ..Default::default()
+            }))
+            .unwrap();
+        check(&tokenizer);
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        check(&tokenizer);
+    }
+
+    #[test]
+    fn count_tokens() {
+        let mut tokenizer = tokenizer();
+        assert_eq!(tokenizer.count_tokens("ab a</s>b", true).unwrap(), 6);
+        assert_eq!(tokenizer.count_tokens("ab a</s>b", false).unwrap(), 4);
+
+        // Padding is not counted
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(10),
+            ..Default::default()
+        }));
+        assert_eq!(tokenizer.encode_ids("ab", true).unwrap().len(), 10);
+        assert_eq!(
+            tokenizer
+                .count_tokens_batch(vec!["ab", "ab a</s>b"], true)
+                .unwrap(),
+            vec![3, 6]
+        );
+    }
+
+    #[test]
+    fn multi_sequences() {
+        let mut tokenizer = tokenizer();
+        let input = || EncodeInput::Multi(vec!["ab".into(), "a".into(), "b b".into()]);
+        let encoding = tokenizer.encode(input(), true).unwrap();
+        assert_eq!(encoding.get_ids(), [5, 2, 6, 0, 6, 1, 3, 1, 6]);
+        assert_eq!(encoding.get_type_ids(), [0, 0, 0, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(encoding.get_sequence_ids()[7], Some(2));
+        assert_eq!(
+            tokenizer.encode_ids(input(), true).unwrap(),
+            encoding.get_ids()
+        );
+
+        // The longest sequence is truncated first
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 8,
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(
+            tokenizer.encode(input(), true).unwrap().get_ids(),
+            [5, 2, 6, 0, 6, 1, 3, 6]
+        );
+    }
+}
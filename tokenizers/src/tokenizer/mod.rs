@@ -10,7 +10,7 @@
 //!   ...).
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fs::{read_to_string, File},
     io::prelude::*,
     io::BufReader,
@@ -26,11 +26,22 @@ use crate::utils::parallelism::*;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
 
 mod added_vocabulary;
+mod chat;
+mod decode_stream;
+mod decoding;
 mod encoding;
+mod error;
+mod ids;
+mod nbest;
 pub mod normalizer;
 pub mod pattern;
 pub mod pre_tokenizer;
+mod roles;
+mod round_trip;
 mod serialization;
+mod trace;
+mod validation;
+mod windowed;
 
 // Re-export wrappers
 pub use crate::decoders::DecoderWrapper;
@@ -40,16 +51,28 @@ pub use crate::pre_tokenizers::PreTokenizerWrapper;
 pub use crate::processors::PostProcessorWrapper;
 // And some other types
 pub use crate::utils::iter::LinesWithEnding;
-pub use crate::utils::padding::{pad_encodings, PaddingDirection, PaddingParams, PaddingStrategy};
+pub use crate::utils::padding::{
+    group_by_length, pad_encodings, sort_by_length, PaddingDirection, PaddingError, PaddingParams,
+    PaddingStrategy,
+};
 pub use crate::utils::truncation::{
-    truncate_encodings, TruncationDirection, TruncationParams, TruncationStrategy,
+    truncate_encodings, truncate_multi_encodings, TruncationBoundary, TruncationDirection,
+    TruncationError, TruncationParams, TruncationStrategy,
 };
 pub use added_vocabulary::*;
+pub use chat::{ChatEncoding, ChatTruncation};
+pub use decode_stream::{DecodeStream, DecodeStreamError};
+pub use decoding::{AlignedString, DecodeOptions};
 pub use encoding::*;
+pub use error::{Error, ParsedFile};
 pub use normalizer::{NormalizedString, OffsetReferential, SplitDelimiterBehavior};
 pub use pre_tokenizer::*;
+pub use roles::{SpecialTokenRole, SpecialTokenRoleError};
+pub use round_trip::{MismatchCause, RoundTripMismatch, RoundTripReport};
+pub use trace::{EncodeTrace, SequenceTrace, TraceInsertion, TraceSplit};
+pub use validation::{Diagnostic, DiagnosticKind, Severity};
+pub use windowed::EncodingWindow;
 
-pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
 pub type Offsets = (usize, usize);
 
@@ -73,6 +96,12 @@ pub trait Model {
     /// Tokenize the given sequence into multiple underlying `Token`. The `offsets` on the `Token`
     /// are expected to be relative to the given sequence.
     fn tokenize(&self, sequence: &str) -> Result<Vec<Token>>;
+    /// Tokenize the given sequence into the ids of its tokens only. Models can provide a
+    /// cheaper implementation than `tokenize`, as neither the tokens nor their offsets are
+    /// needed.
+    fn tokenize_ids(&self, sequence: &str) -> Result<Vec<u32>> {
+        Ok(self.tokenize(sequence)?.into_iter().map(|t| t.id).collect())
+    }
     /// Find the ID associated to a string token
     fn token_to_id(&self, token: &str) -> Option<u32>;
     /// Find the string token associated to an ID
@@ -86,6 +115,12 @@ pub trait Model {
     fn save(&self, folder: &Path, prefix: Option<&str>) -> Result<Vec<PathBuf>>;
     /// Get an instance of a Trainer capable of training this Model
     fn get_trainer(&self) -> <Self as Model>::Trainer;
+    /// Tokenize the given sequence into its `n` best tokenization candidates, each with its
+    /// score. The candidates are sorted from best to worst. Models that can only produce a
+    /// single tokenization return an error.
+    fn tokenize_nbest(&self, _sequence: &str, _n: usize) -> Result<Vec<(Vec<Token>, f64)>> {
+        Err("This model cannot produce multiple tokenization candidates".into())
+    }
 }
 
 /// A `PostProcessor` has the responsibility to post process an encoded output of the `Tokenizer`.
@@ -93,6 +128,11 @@ pub trait Model {
 pub trait PostProcessor {
     /// Returns the number of tokens that will be added during the processing step
     fn added_tokens(&self, is_pair: bool) -> usize;
+    /// Returns the number of tokens that will be added during the processing step of
+    /// `n_sequences` sequences, or an error if it can't process that many sequences
+    fn added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        Ok(self.added_tokens(n_sequences > 1))
+    }
     /// Process both encodings and returns a new merged one
     fn process(
         &self,
@@ -100,11 +140,21 @@ pub trait PostProcessor {
         pair_encoding: Option<Encoding>,
         add_special_tokens: bool,
     ) -> Result<Encoding> {
-        let mut encodings = if let Some(pair_encoding) = pair_encoding {
+        let encodings = if let Some(pair_encoding) = pair_encoding {
             vec![encoding, pair_encoding]
         } else {
             vec![encoding]
         };
+        self.process_multi(encodings, add_special_tokens)
+    }
+
+    /// Process any amount of encodings, one for each input sequence, and returns a new
+    /// merged one
+    fn process_multi(
+        &self,
+        mut encodings: Vec<Encoding>,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
         encodings.iter_mut().enumerate().for_each(|(i, encoding)| {
             encoding.set_sequence_id(i);
             encoding
@@ -124,6 +174,29 @@ pub trait PostProcessor {
         encodings: Vec<Encoding>,
         add_special_tokens: bool,
     ) -> Result<Vec<Encoding>>;
+
+    /// Replace the given special token with another one, along with its id, like when the
+    /// token playing a role changes
+    fn replace_special_token(&mut self, _token: &str, _new_token: &str, _new_id: u32) {}
+
+    /// Returns the number of tokens that will be added during the processing step of a
+    /// conversation made of turns with the given roles
+    fn added_tokens_chat(&self, _roles: &[String]) -> Result<usize> {
+        Ok(0)
+    }
+    /// How the conversations that are too long get truncated
+    fn chat_truncation(&self) -> ChatTruncation {
+        ChatTruncation::default()
+    }
+    /// Process the encodings of the turns of a conversation, each with the role of its
+    /// author, and returns a new merged one along with the mask of the assistant tokens
+    fn process_chat(
+        &self,
+        _turns: Vec<(String, Encoding)>,
+        _add_special_tokens: bool,
+    ) -> Result<ChatEncoding> {
+        Err(ProcessorError::ChatNotSupported.into())
+    }
 }
 impl dyn PostProcessor {
     pub fn default_process(
@@ -148,6 +221,50 @@ impl dyn PostProcessor {
 pub enum ProcessorError {
     #[error("encodings vector length must be either 1 or 2")]
     InvalidEncodingsVecLength,
+    #[error("the post-processor does not support conversations")]
+    ChatNotSupported,
+    /// A field of the builder of a post-processor was not set
+    #[error("`{0}` must be initialized")]
+    UninitializedField(&'static str),
+    /// A special token of a template doesn't have as many ids as tokens
+    #[error("SpecialToken: ids and tokens must be of the same length")]
+    SpecialTokenLength,
+    /// The templates or the roles use special tokens that are not given, by their id
+    #[error("Missing SpecialToken(s) with id(s) `{}`", .0.join(", "))]
+    MissingSpecialTokens(Vec<String>),
+    #[error("Template for `pair` must use both sequences")]
+    PairSequences,
+    #[error("Template for `multi` can use `$*` only once")]
+    RepeatedRest,
+    /// The `multi` template skips the given sequence, and has no `$*` for it
+    #[error(
+        "Template for `multi` refers to sequence {0} but skips some of the ones before it, \
+         and has no `$*` for them"
+    )]
+    SkippedSequences(usize),
+    /// The `single` or `pair` template refers to sequences beyond the first two
+    #[error("Only the template for `multi` can use `$C`, `$D`, ... or `$*`")]
+    SequencesBeyondPair,
+    /// There is no `multi` template to process the given number of sequences
+    #[error("TemplateProcessing: no `multi` template to process {0} sequences")]
+    NoMultiTemplate(usize),
+    /// The template refers to a sequence that was not given
+    #[error("Template refers to sequence {index} but there are only {sequences} sequences")]
+    MissingSequence { index: usize, sequences: usize },
+    /// The template doesn't use all the sequences, and has no `$*` for the others
+    #[error(
+        "Template uses {used} sequences but there are {sequences}, and it has no `$*` for the \
+         others"
+    )]
+    UnusedSequences { used: usize, sequences: usize },
+    #[error("ChatProcessing: unknown role `{0}`")]
+    UnknownRole(String),
+}
+
+impl From<derive_builder::UninitializedFieldError> for ProcessorError {
+    fn from(error: derive_builder::UninitializedFieldError) -> Self {
+        Self::UninitializedField(error.field_name())
+    }
 }
 
 /// A `Decoder` changes the raw tokens into its more readable form.
@@ -157,6 +274,28 @@ pub trait Decoder {
         Ok(results.join(""))
     }
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>>;
+    /// Decode the given tokens like `decode_chain`, keeping track of the input tokens each
+    /// part of the returned strings comes from.
+    ///
+    /// By default, each returned string is attributed to the token at the same position when
+    /// there are as many of them as the given tokens, or to all of them otherwise.
+    fn decode_chain_aligned(&self, tokens: Vec<AlignedString>) -> Result<Vec<AlignedString>> {
+        let results = self.decode_chain(tokens.iter().map(|token| token.get()).collect())?;
+        if results.len() == tokens.len() {
+            Ok(tokens
+                .iter()
+                .zip(results)
+                .map(|(token, result)| AlignedString::with_sources(result, token.sources()))
+                .collect())
+        } else {
+            let mut all = AlignedString::default();
+            tokens.into_iter().for_each(|token| all.extend(token));
+            Ok(vec![AlignedString::with_sources(
+                results.join(""),
+                all.sources(),
+            )])
+        }
+    }
 }
 
 /// A `Trainer` has the responsibility to train a model. We feed it with lines/sentences
@@ -177,7 +316,7 @@ pub trait Trainer {
         F: Fn(&str) -> Result<Vec<String>> + Sync;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Token {
     pub id: u32,
     pub value: String,
@@ -198,6 +337,31 @@ pub enum InputSequence<'s> {
     PreTokenizedCow(Cow<'s, [Cow<'s, str>]>),
 }
 
+impl InputSequence<'_> {
+    /// The subsequences to encode one by one, with the word index to use for the
+    /// pre-tokenized ones
+    fn subsequences(&self) -> Vec<(Option<u32>, &str)> {
+        match self {
+            Self::PreTokenized(seq) => seq
+                .iter()
+                .enumerate()
+                .map(|(i, s)| (Some(i as u32), *s))
+                .collect(),
+            Self::PreTokenizedOwned(seq) => seq
+                .iter()
+                .enumerate()
+                .map(|(i, s)| (Some(i as u32), s.as_str()))
+                .collect(),
+            Self::PreTokenizedCow(seq) => seq
+                .iter()
+                .enumerate()
+                .map(|(i, s)| (Some(i as u32), s.as_ref()))
+                .collect(),
+            Self::Raw(seq) => vec![(None, seq.as_ref())],
+        }
+    }
+}
+
 impl<'s> From<Cow<'s, str>> for InputSequence<'s> {
     fn from(input: Cow<'s, str>) -> Self {
         Self::Raw(input)
@@ -256,6 +420,19 @@ impl<'s> From<&'s [Cow<'s, str>]> for InputSequence<'s> {
 pub enum EncodeInput<'s> {
     Single(InputSequence<'s>),
     Dual(InputSequence<'s>, InputSequence<'s>),
+    /// Any number of sequences, like a query and several passages
+    Multi(Vec<InputSequence<'s>>),
+}
+
+impl<'s> EncodeInput<'s> {
+    /// The sequences of this input, in order
+    pub fn into_sequences(self) -> Vec<InputSequence<'s>> {
+        match self {
+            Self::Single(s1) => vec![s1],
+            Self::Dual(s1, s2) => vec![s1, s2],
+            Self::Multi(sequences) => sequences,
+        }
+    }
 }
 
 impl<'s, I: Into<InputSequence<'s>>> From<I> for EncodeInput<'s> {
@@ -335,7 +512,7 @@ where
     pub fn build(self) -> Result<TokenizerImpl<M, N, PT, PP, D>> {
         let model = self
             .model
-            .ok_or_else(|| Box::new(BuilderError("Model missing.".into())))?;
+            .ok_or_else(|| BuilderError("Model missing.".into()))?;
         Ok(TokenizerImpl {
             normalizer: self.normalizer,
             pre_tokenizer: self.pre_tokenizer,
@@ -344,6 +521,7 @@ where
             post_processor: self.post_processor,
             decoder: self.decoder,
             added_vocabulary: self.added_vocabulary,
+            special_token_roles: BTreeMap::new(),
             truncation: self.truncation,
             padding: self.padding,
         })
@@ -430,28 +608,117 @@ impl Tokenizer {
     }
     pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
         let content = read_to_string(file)?;
-        let tokenizer = serde_json::from_str(&content)?;
-        Ok(tokenizer)
+        serialization::from_json(content.as_bytes())
     }
     pub fn from_bytes<P: AsRef<[u8]>>(bytes: P) -> Result<Self> {
-        let tokenizer = serde_json::from_slice(bytes.as_ref())?;
-        Ok(tokenizer)
+        serialization::from_json(bytes.as_ref())
     }
     #[cfg(feature = "http")]
     pub fn from_pretrained<S: AsRef<str>>(
         identifier: S,
         params: Option<crate::utils::from_pretrained::FromPretrainedParameters>,
     ) -> Result<Self> {
-        let tokenizer_file = crate::utils::from_pretrained::from_pretrained(identifier, params)?;
-        Tokenizer::from_file(tokenizer_file)
+        use crate::utils::from_pretrained::{
+            legacy_tokenizer, resolve_pretrained, PretrainedFiles,
+        };
+
+        match resolve_pretrained(identifier, params)? {
+            PretrainedFiles::Tokenizer(tokenizer_file) => Tokenizer::from_file(tokenizer_file),
+            PretrainedFiles::Legacy {
+                vocab,
+                merges,
+                special_tokens_map,
+                tokenizer_config,
+            } => legacy_tokenizer(
+                &vocab,
+                merges.as_deref(),
+                special_tokens_map.as_deref(),
+                tokenizer_config.as_deref(),
+            ),
+        }
+    }
+
+    /// Load a tokenizer from a `.tiktoken` file. The input is first split using the given
+    /// regex `pattern` (the `pat_str` of the tiktoken encoding), and then tokenized by a
+    /// byte-level `BPE`. The `special_tokens` get added with their given id.
+    pub fn from_tiktoken<P: AsRef<Path>>(
+        file: P,
+        pattern: &str,
+        special_tokens: &[(&str, u32)],
+    ) -> Result<Self> {
+        use crate::models::bpe::BPE;
+        use crate::pre_tokenizers::byte_level::ByteLevel;
+        use crate::pre_tokenizers::{sequence::Sequence, split::Split, split::SplitPattern};
+
+        let file = file.as_ref().to_str().ok_or("Invalid tiktoken file path")?;
+        let (mut vocab, merges) = BPE::read_tiktoken(file)?;
+        for (token, id) in special_tokens {
+            if let Some(existing) = vocab.iter().find(|(_, i)| *i == id) {
+                return Err(AddedTokensError::IdAlreadyUsed {
+                    token: token.to_string(),
+                    id: *id,
+                    existing: existing.0.clone(),
+                }
+                .into());
+            }
+            vocab.insert(token.to_string(), *id);
+        }
+
+        let mut tokenizer = Tokenizer::new(BPE::builder().vocab_and_merges(vocab, merges).build()?);
+        tokenizer.with_pre_tokenizer(Sequence::new(vec![
+            Split::new(
+                SplitPattern::Regex(pattern.to_string()),
+                SplitDelimiterBehavior::Isolated,
+                false,
+            )?
+            .into(),
+            ByteLevel::new(false, false, false).into(),
+        ]));
+        tokenizer.with_decoder(ByteLevel::default());
+        tokenizer.add_special_tokens(
+            &special_tokens
+                .iter()
+                .map(|(token, _)| AddedToken::from(*token, true))
+                .collect::<Vec<_>>(),
+        );
+        Ok(tokenizer)
+    }
+
+    /// Load a tokenizer from a SentencePiece `.model` file. Both Unigram and BPE models are
+    /// supported, along with their normalization rules and byte fallback.
+    ///
+    /// The control pieces, like `</s>`, become special tokens. Contrary to SentencePiece,
+    /// they are then extracted when they appear in the input text, unless encoding with
+    /// [`SpecialTokensMatching::None`] which gives the same ids as SentencePiece.
+    pub fn from_sentencepiece<P: AsRef<Path>>(file: P) -> Result<Self> {
+        crate::utils::sentencepiece::from_sentencepiece(file)
+    }
+
+    /// Save the vocabulary of this tokenizer as a `.tiktoken` file, so that it can be used by
+    /// tiktoken-based tools. This only works with a byte-level `BPE` model. The special tokens
+    /// are not part of the file, and neither is the split pattern: they must be provided to
+    /// tiktoken separately.
+    pub fn save_tiktoken<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        match self.get_model() {
+            ModelWrapper::BPE(bpe) => {
+                let special_tokens = self
+                    .get_added_tokens_decoder()
+                    .into_values()
+                    .filter(|token| token.special)
+                    .map(|token| token.content)
+                    .collect::<HashSet<_>>();
+                bpe.save_tiktoken(path, |token| !special_tokens.contains(token))
+            }
+            _ => Err("Only BPE models can be saved as tiktoken files".into()),
+        }
     }
 }
 
 impl std::str::FromStr for Tokenizer {
-    type Err = Box<dyn std::error::Error + Send + Sync>;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        Ok(serde_json::from_str(s)?)
+        serialization::from_json(s.as_bytes())
     }
 }
 
@@ -471,6 +738,7 @@ where
             post_processor: t.post_processor.map(Into::into),
             decoder: t.decoder.map(Into::into),
             added_vocabulary: t.added_vocabulary,
+            special_token_roles: t.special_token_roles,
             padding: t.padding,
             truncation: t.truncation,
         })
@@ -497,10 +765,6 @@ impl DerefMut for Tokenizer {
     }
 }
 
-#[derive(thiserror::Error, Debug)]
-#[error("{0}")]
-pub struct TruncationParamError(String);
-
 /// A `Tokenizer` is capable of encoding/decoding any text.
 #[derive(Clone, Debug)]
 pub struct TokenizerImpl<M, N, PT, PP, D> {
@@ -513,6 +777,7 @@ pub struct TokenizerImpl<M, N, PT, PP, D> {
 
     // Added Vocabulary capabilities
     added_vocabulary: AddedVocabulary,
+    special_token_roles: BTreeMap<SpecialTokenRole, String>,
 
     // General processing parameters
     truncation: Option<TruncationParams>,
@@ -537,6 +802,7 @@ where
             decoder: None,
 
             added_vocabulary: AddedVocabulary::new(),
+            special_token_roles: BTreeMap::new(),
 
             truncation: None,
             padding: None,
@@ -600,16 +866,25 @@ where
 
     /// Set the truncation parameters
     ///
-    /// Fails if `stride` is too high relative to `max_length` and `post_processor.added_tokens()`
+    /// Fails if `stride` is too high relative to `max_length` and `post_processor.added_tokens()`,
+    /// or if it is set when truncating the middle
     pub fn with_truncation(&mut self, trunc: Option<TruncationParams>) -> Result<&mut Self> {
         if let Some(trunc_params) = &trunc {
+            if trunc_params.direction == TruncationDirection::Middle && trunc_params.stride > 0 {
+                return Err(TruncationError::InvalidParams(format!(
+                    "tokenizer stride set to {}, but it can't be used when truncating the middle",
+                    trunc_params.stride
+                ))
+                .into());
+            }
             let n_added_tokens = self.get_n_added_tokens(false);
             let effective_max_length = trunc_params.max_length - n_added_tokens;
             if effective_max_length < trunc_params.stride {
-                return Err(Box::new(TruncationParamError(format!(
+                return Err(TruncationError::InvalidParams(format!(
                     "tokenizer stride set to {}, which is greater than or equal to its effective max length of {} (= {} original max length - {} added special tokens), ",
                     trunc_params.stride, effective_max_length, trunc_params.max_length, n_added_tokens
-                ))));
+                ))
+                .into());
             }
         }
         self.truncation = trunc;
@@ -659,6 +934,149 @@ where
         final_vocab
     }
 
+    /// Set the token playing the given role, which must be part of the vocabulary.
+    ///
+    /// When the role was played by another token, the padding and the post-processor using
+    /// it are updated to use the new one, unless it still plays some other role.
+    pub fn with_special_token_role(
+        &mut self,
+        role: SpecialTokenRole,
+        token: impl Into<String>,
+    ) -> Result<&mut Self> {
+        let token = token.into();
+        let id = self
+            .token_to_id(&token)
+            .ok_or_else(|| SpecialTokenRoleError::MissingToken(role, token.clone()))?;
+        let previous = match self.special_token_roles.insert(role, token.clone()) {
+            Some(previous) if previous != token => previous,
+            _ => return Ok(self),
+        };
+        if self.special_token_roles.values().any(|t| *t == previous) {
+            return Ok(self);
+        }
+
+        if let Some(padding) = self.padding.as_mut() {
+            if padding.pad_token == previous {
+                padding.pad_token = token.clone();
+                padding.pad_id = id;
+            }
+        }
+        if let Some(post_processor) = self.post_processor.as_mut() {
+            post_processor.replace_special_token(&previous, &token, id);
+        }
+        Ok(self)
+    }
+
+    /// Set the tokens playing the given roles, like with `with_special_token_role`
+    pub fn with_special_token_roles(
+        &mut self,
+        roles: BTreeMap<SpecialTokenRole, String>,
+    ) -> Result<&mut Self> {
+        for (role, token) in roles {
+            self.with_special_token_role(role, token)?;
+        }
+        Ok(self)
+    }
+
+    /// Get the token playing the given role
+    pub fn get_special_token_role(&self, role: SpecialTokenRole) -> Option<&str> {
+        self.special_token_roles.get(&role).map(|t| t.as_str())
+    }
+
+    /// Get the tokens playing a role
+    pub fn get_special_token_roles(&self) -> &BTreeMap<SpecialTokenRole, String> {
+        &self.special_token_roles
+    }
+
+    /// Get the id of the token playing the given role
+    pub fn get_special_token_role_id(&self, role: SpecialTokenRole) -> Option<u32> {
+        self.get_special_token_role(role)
+            .and_then(|token| self.token_to_id(token))
+    }
+
+    /// Get the token playing the given role, along with its id, as expected by the
+    /// post-processors like `BertProcessing`, `RobertaProcessing`, or the special tokens of
+    /// `TemplateProcessing`.
+    ///
+    /// ```
+    /// # use tokenizers::{AddedToken, SpecialTokenRole, Tokenizer};
+    /// # use tokenizers::models::bpe::BPE;
+    /// # use tokenizers::processors::bert::BertProcessing;
+    /// # let mut tokenizer = Tokenizer::new(BPE::default());
+    /// # tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true), AddedToken::from("[SEP]", true)]);
+    /// tokenizer
+    ///     .with_special_token_role(SpecialTokenRole::Cls, "[CLS]")?
+    ///     .with_special_token_role(SpecialTokenRole::Sep, "[SEP]")?;
+    /// let processor = BertProcessing::new(
+    ///     tokenizer.get_special_token_role_with_id(SpecialTokenRole::Sep)?,
+    ///     tokenizer.get_special_token_role_with_id(SpecialTokenRole::Cls)?,
+    /// );
+    /// tokenizer.with_post_processor(processor);
+    /// # Ok::<(), tokenizers::Error>(())
+    /// ```
+    pub fn get_special_token_role_with_id(&self, role: SpecialTokenRole) -> Result<(String, u32)> {
+        Ok(self.special_token_role_with_id(role)?)
+    }
+
+    fn special_token_role_with_id(
+        &self,
+        role: SpecialTokenRole,
+    ) -> std::result::Result<(String, u32), SpecialTokenRoleError> {
+        let token = self
+            .get_special_token_role(role)
+            .ok_or(SpecialTokenRoleError::MissingRole(role))?;
+        let id = self
+            .token_to_id(token)
+            .ok_or_else(|| SpecialTokenRoleError::MissingToken(role, token.to_owned()))?;
+        Ok((token.to_owned(), id))
+    }
+
+    /// Get the id of the `bos` token
+    pub fn bos_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Bos)
+    }
+
+    /// Get the id of the `eos` token
+    pub fn eos_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Eos)
+    }
+
+    /// Get the id of the `pad` token
+    pub fn pad_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Pad)
+    }
+
+    /// Get the id of the `unk` token
+    pub fn unk_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Unk)
+    }
+
+    /// Get the id of the `mask` token
+    pub fn mask_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Mask)
+    }
+
+    /// Get the id of the `cls` token
+    pub fn cls_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Cls)
+    }
+
+    /// Get the id of the `sep` token
+    pub fn sep_token_id(&self) -> Option<u32> {
+        self.get_special_token_role_id(SpecialTokenRole::Sep)
+    }
+
+    /// Set the padding parameters, padding with the token playing the `pad` role
+    pub fn with_padding_from_role(&mut self, mut padding: PaddingParams) -> Result<&mut Self> {
+        let (pad_token, pad_id) = self
+            .special_token_role_with_id(SpecialTokenRole::Pad)
+            .map_err(PaddingError::PadToken)?;
+        padding.pad_token = pad_token;
+        padding.pad_id = pad_id;
+        self.padding = Some(padding);
+        Ok(self)
+    }
+
     /// Get the added tokens decoder
     pub fn get_added_tokens_decoder(&self) -> HashMap<u32, AddedToken> {
         self.added_vocabulary.get_added_tokens_decoder().clone()
@@ -691,44 +1109,37 @@ where
         sequence: InputSequence,
         type_id: u32,
         offsets_type: OffsetType,
+        matching: &SpecialTokensMatching,
     ) -> Result<Encoding> {
-        let encode = |is_pre_tokenized, subseq_idx, subseq| -> Result<Encoding> {
-            let normalized = self
-                .added_vocabulary
-                .extract_and_normalize(self.normalizer.as_ref(), subseq);
-            let pre_tokenized = self.do_pre_tokenize(normalized)?;
-            let subseq_encoding = self.do_tokenize(
-                pre_tokenized,
-                type_id,
-                if is_pre_tokenized {
-                    Some(subseq_idx as u32)
-                } else {
-                    None
-                },
-                offsets_type,
-            )?;
-
-            Ok(subseq_encoding)
-        };
+        self.encode_single_sequence_with(sequence, matching, |pre_tokenized, word_idx| {
+            self.do_tokenize(pre_tokenized, type_id, word_idx, offsets_type, matching)
+        })
+    }
 
-        match sequence {
-            InputSequence::PreTokenized(seq) => seq
-                .iter()
-                .enumerate()
-                .map(|(i, sequence)| encode(true, i, sequence))
-                .collect(),
-            InputSequence::PreTokenizedOwned(seq) => seq
-                .iter()
-                .enumerate()
-                .map(|(i, sequence)| encode(true, i, sequence))
-                .collect(),
-            InputSequence::PreTokenizedCow(seq) => seq
-                .iter()
-                .enumerate()
-                .map(|(i, sequence)| encode(true, i, sequence))
-                .collect(),
-            InputSequence::Raw(seq) => encode(false, 0, seq.as_ref()),
-        }
+    /// Extract the added tokens, normalize and pre-tokenize the given sequence, then build
+    /// its `Encoding` using `tokenize`, with the word index to use for pre-tokenized inputs
+    fn encode_single_sequence_with<F>(
+        &self,
+        sequence: InputSequence,
+        matching: &SpecialTokensMatching,
+        tokenize: F,
+    ) -> Result<Encoding>
+    where
+        F: Fn(PreTokenizedString, Option<u32>) -> Result<Encoding>,
+    {
+        sequence
+            .subsequences()
+            .into_iter()
+            .map(|(word_idx, subseq)| {
+                let normalized = self.added_vocabulary.extract_and_normalize_with_matching(
+                    self.normalizer.as_ref(),
+                    subseq,
+                    matching,
+                );
+                let pre_tokenized = self.do_pre_tokenize(normalized)?;
+                tokenize(pre_tokenized, word_idx)
+            })
+            .collect()
     }
 
     /// Encode the given input. This method accepts both single sequences, as well as pair
@@ -757,20 +1168,33 @@ where
     where
         E: Into<EncodeInput<'s>>,
     {
-        // Extract sequences from the EncodeInput
-        let (sequence, pair) = match input.into() {
-            EncodeInput::Single(s1) => (s1, None),
-            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
-        };
+        self.encode_with_matching(input, add_special_tokens, &SpecialTokensMatching::All)
+    }
 
-        // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Byte)?;
-        let pair_encoding = pair
-            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Byte))
-            .transpose()?;
+    /// Encode the given input. Only the special tokens allowed by `matching` are
+    /// extracted from the input, the others being tokenized as plain text by the model.
+    pub fn encode_with_matching<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Encoding>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        // Encode each sequence of the EncodeInput
+        let encodings = input
+            .into()
+            .into_sequences()
+            .into_iter()
+            .enumerate()
+            .map(|(i, sequence)| {
+                self.encode_single_sequence(sequence, i as u32, OffsetType::Byte, matching)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         // And finally post process
-        self.post_process(encoding, pair_encoding, add_special_tokens)
+        self.post_process_multi(encodings, add_special_tokens)
     }
 
     /// Encode the given input, using offsets relative to chars instead of bytes.
@@ -800,40 +1224,43 @@ where
     where
         E: Into<EncodeInput<'s>>,
     {
-        // Extract sequences from the EncodeInput
-        let (sequence, pair) = match input.into() {
-            EncodeInput::Single(s1) => (s1, None),
-            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
-        };
+        self.encode_char_offsets_with_matching(
+            input,
+            add_special_tokens,
+            &SpecialTokensMatching::All,
+        )
+    }
 
-        // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Char)?;
-        let pair_encoding = pair
-            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Char))
-            .transpose()?;
+    /// Encode the given input, using offsets relative to chars instead of bytes. Only the
+    /// special tokens allowed by `matching` are extracted from the input, the others being
+    /// tokenized as plain text by the model.
+    pub fn encode_char_offsets_with_matching<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Encoding>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        // Encode each sequence of the EncodeInput
+        let encodings = input
+            .into()
+            .into_sequences()
+            .into_iter()
+            .enumerate()
+            .map(|(i, sequence)| {
+                self.encode_single_sequence(sequence, i as u32, OffsetType::Char, matching)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         // And finally post process
-        self.post_process(encoding, pair_encoding, add_special_tokens)
+        self.post_process_multi(encodings, add_special_tokens)
     }
 
     /// Decode the given ids, back to a String
     pub fn decode(&self, ids: &[u32], skip_special_tokens: bool) -> Result<String> {
-        let tokens = ids
-            .iter()
-            .filter_map(|id| {
-                self.added_vocabulary
-                    .id_to_token(*id, &self.model)
-                    .filter(|token| {
-                        !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
-                    })
-            })
-            .collect::<Vec<_>>();
-
-        if let Some(decoder) = &self.decoder {
-            decoder.decode(tokens)
-        } else {
-            Ok(tokens.join(" "))
-        }
+        self.decode_with_options(ids, &DecodeOptions::new(skip_special_tokens))
     }
 }
 
@@ -849,11 +1276,82 @@ where
         type_id: u32,
         word_idx: Option<u32>,
         offsets_type: OffsetType,
+        matching: &SpecialTokensMatching,
     ) -> Result<Encoding> {
         let mut pretokenized: PreTokenizedString = pretokenized.into();
-        pretokenized.tokenize(|normalized| self.model.tokenize(normalized.get()))?;
+        pretokenized.tokenize(|normalized| self.tokenize_split(normalized.get(), matching))?;
         pretokenized.into_encoding(word_idx, type_id, offsets_type)
     }
+
+    /// Tokenize a split of the input with the model.
+    ///
+    /// A special token that `matching` doesn't allow can still be produced by the model when
+    /// it is also part of its vocabulary. Such tokens get tokenized again one char at a time,
+    /// so the input can never resolve to their id.
+    fn tokenize_split(
+        &self,
+        sequence: &str,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<Token>> {
+        let tokens = self.model.tokenize(sequence)?;
+        // The unknown token can be special too, but it doesn't come from its content
+        let disallowed = |token: &Token| {
+            self.added_vocabulary
+                .is_disallowed_special(token.id, matching)
+                && self
+                    .added_vocabulary
+                    .id_to_token(token.id, &self.model)
+                    .as_deref()
+                    == Some(token.value.as_str())
+        };
+        if !tokens.iter().any(disallowed) {
+            return Ok(tokens);
+        }
+
+        let mut result = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            if !disallowed(&token) {
+                result.push(token);
+                continue;
+            }
+            let (start, end) = token.offsets;
+            for (i, c) in sequence[start..end].char_indices() {
+                let offset = start + i;
+                for mut char_token in self
+                    .model
+                    .tokenize(&sequence[offset..offset + c.len_utf8()])?
+                {
+                    if disallowed(&char_token) {
+                        return Err(AddedTokensError::Disallowed(char_token.value).into());
+                    }
+                    char_token.offsets.0 += offset;
+                    char_token.offsets.1 += offset;
+                    result.push(char_token);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Same as [`tokenize_split`](Self::tokenize_split), for the ids only
+    fn tokenize_split_ids(
+        &self,
+        sequence: &str,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<u32>> {
+        let ids = self.model.tokenize_ids(sequence)?;
+        if ids
+            .iter()
+            .any(|id| self.added_vocabulary.is_disallowed_special(*id, matching))
+        {
+            return Ok(self
+                .tokenize_split(sequence, matching)?
+                .into_iter()
+                .map(|token| token.id)
+                .collect());
+        }
+        Ok(ids)
+    }
 }
 
 impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
@@ -920,35 +1418,43 @@ where
         encoding: Encoding,
         pair_encoding: Option<Encoding>,
         add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        let encodings = if let Some(pair_encoding) = pair_encoding {
+            vec![encoding, pair_encoding]
+        } else {
+            vec![encoding]
+        };
+        self.post_process_multi(encodings, add_special_tokens)
+    }
+
+    /// Post processing logic for any number of sequences, handling the case where there
+    /// is no PostProcessor set
+    pub fn post_process_multi(
+        &self,
+        encodings: Vec<Encoding>,
+        add_special_tokens: bool,
     ) -> Result<Encoding> {
         // 1. First we truncate if needed
-        let (encoding, pair_encoding) = {
-            if let Some(trunc) = &self.truncation {
-                let n_added_tokens = self.get_n_added_tokens(pair_encoding.is_some());
-
-                if add_special_tokens && n_added_tokens > 0 {
-                    let params = TruncationParams {
-                        max_length: trunc.max_length - n_added_tokens,
-                        ..*trunc
-                    };
-                    truncate_encodings(encoding, pair_encoding, &params)?
-                } else {
-                    truncate_encodings(encoding, pair_encoding, trunc)?
-                }
+        let encodings = if let Some(trunc) = &self.truncation {
+            let n_added_tokens = self.get_n_added_tokens_multi(encodings.len())?;
+
+            if add_special_tokens && n_added_tokens > 0 {
+                let params = TruncationParams {
+                    max_length: trunc.max_length - n_added_tokens,
+                    ..*trunc
+                };
+                truncate_multi_encodings(encodings, &params)?
             } else {
-                (encoding, pair_encoding)
+                truncate_multi_encodings(encodings, trunc)?
             }
+        } else {
+            encodings
         };
 
         // 2. Then We post process
         let final_encoding = if let Some(processor) = &self.post_processor {
-            processor.process(encoding, pair_encoding, add_special_tokens)?
+            processor.process_multi(encodings, add_special_tokens)?
         } else {
-            let encodings = if let Some(pair_encoding) = pair_encoding {
-                vec![encoding, pair_encoding]
-            } else {
-                vec![encoding]
-            };
             let mut encodings =
                 <dyn PostProcessor>::default_process(encodings, add_special_tokens)?;
             if encodings.len() != 1 {
@@ -976,6 +1482,14 @@ where
             0
         }
     }
+
+    fn get_n_added_tokens_multi(&self, n_sequences: usize) -> Result<usize> {
+        if let Some(processor) = &self.post_processor {
+            processor.added_tokens_multi(n_sequences)
+        } else {
+            Ok(0)
+        }
+    }
 }
 
 impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
@@ -992,12 +1506,26 @@ where
         inputs: Vec<E>,
         add_special_tokens: bool,
     ) -> Result<Vec<Encoding>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        self.encode_batch_with_matching(inputs, add_special_tokens, &SpecialTokensMatching::All)
+    }
+
+    /// Encode all the sentences in parallel, only extracting the special tokens allowed
+    /// by `matching`.
+    pub fn encode_batch_with_matching<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<Encoding>>
     where
         E: Into<EncodeInput<'s>> + Send,
     {
         let mut encodings = inputs
             .into_maybe_par_iter()
-            .map(|input| self.encode(input, add_special_tokens))
+            .map(|input| self.encode_with_matching(input, add_special_tokens, matching))
             .collect::<Result<Vec<Encoding>>>()?;
 
         if let Some(params) = &self.padding {
@@ -1015,12 +1543,32 @@ where
         inputs: Vec<E>,
         add_special_tokens: bool,
     ) -> Result<Vec<Encoding>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        self.encode_batch_char_offsets_with_matching(
+            inputs,
+            add_special_tokens,
+            &SpecialTokensMatching::All,
+        )
+    }
+
+    /// Encode all the sentences in parallel, with offsets relative to chars, only extracting
+    /// the special tokens allowed by `matching`.
+    pub fn encode_batch_char_offsets_with_matching<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+        matching: &SpecialTokensMatching,
+    ) -> Result<Vec<Encoding>>
     where
         E: Into<EncodeInput<'s>> + Send,
     {
         let mut encodings = inputs
             .into_maybe_par_iter()
-            .map(|input| self.encode_char_offsets(input, add_special_tokens))
+            .map(|input| {
+                self.encode_char_offsets_with_matching(input, add_special_tokens, matching)
+            })
             .collect::<Result<Vec<Encoding>>>()?;
 
         if let Some(params) = &self.padding {
@@ -1179,7 +1727,7 @@ where
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        Ok(serde_json::from_str(s)?)
+        serialization::from_json(s.as_bytes())
     }
 }
 
@@ -1194,8 +1742,7 @@ where
     /// Instantiate a new Tokenizer from the given file
     pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
         let content = read_to_string(file)?;
-        let tokenizer = serde_json::from_str(&content)?;
-        Ok(tokenizer)
+        serialization::from_json(content.as_bytes())
     }
 }
 
@@ -1209,8 +1756,7 @@ where
 {
     /// Instantiate a new Tokenizer from bytes
     pub fn from_bytes<P: AsRef<[u8]>>(bytes: P) -> Result<Self> {
-        let tokenizer = serde_json::from_slice(bytes.as_ref())?;
-        Ok(tokenizer)
+        serialization::from_json(bytes.as_ref())
     }
 }
 
@@ -0,0 +1,254 @@
+use super::{
+    Decoder, EncodeInput, Encoding, InputSequence, Model, Normalizer, OffsetReferential,
+    OffsetType, PostProcessor, PreTokenizedString, PreTokenizer, Result, Token, TokenizerImpl,
+};
+
+/// Combine several lists of scored candidates, keeping the `k` best combinations. Each
+/// combination is made of one candidate index per list, and its score is the sum of the
+/// candidates scores. The result is sorted from best to worst.
+fn combine_nbest(lists: &[Vec<f64>], k: usize) -> Vec<(Vec<usize>, f64)> {
+    let mut combinations = vec![(vec![], 0.0)];
+    for scores in lists {
+        let mut next = Vec::with_capacity(combinations.len() * scores.len());
+        for (choices, score) in &combinations {
+            for (i, candidate_score) in scores.iter().enumerate() {
+                let mut choices: Vec<usize> = choices.clone();
+                choices.push(i);
+                next.push((choices, score + candidate_score));
+            }
+        }
+        next.sort_by(|a, b| b.1.total_cmp(&a.1));
+        next.truncate(k);
+        combinations = next;
+    }
+    combinations
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    /// Encode a single sequence into its `k` best candidates
+    fn encode_single_sequence_nbest(
+        &self,
+        sequence: InputSequence,
+        type_id: u32,
+        offsets_type: OffsetType,
+        k: usize,
+    ) -> Result<Vec<(Encoding, f64)>> {
+        let subsequences: Vec<(PreTokenizedString, Option<u32>)> = {
+            let pre_tokenize = |subseq: &str, word_idx| -> Result<_> {
+                let normalized = self
+                    .added_vocabulary
+                    .extract_and_normalize(self.normalizer.as_ref(), subseq);
+                Ok((self.do_pre_tokenize(normalized)?, word_idx))
+            };
+            match sequence {
+                InputSequence::PreTokenized(seq) => seq
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| pre_tokenize(s, Some(i as u32)))
+                    .collect::<Result<_>>()?,
+                InputSequence::PreTokenizedOwned(seq) => seq
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| pre_tokenize(s, Some(i as u32)))
+                    .collect::<Result<_>>()?,
+                InputSequence::PreTokenizedCow(seq) => seq
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| pre_tokenize(s, Some(i as u32)))
+                    .collect::<Result<_>>()?,
+                InputSequence::Raw(seq) => vec![pre_tokenize(seq.as_ref(), None)?],
+            }
+        };
+
+        // The candidates of each split that still needs to be tokenized, in order
+        let candidates: Vec<Vec<(Vec<Token>, f64)>> = subsequences
+            .iter()
+            .flat_map(|(pre_tokenized, _)| {
+                pre_tokenized
+                    .get_splits(OffsetReferential::Normalized, OffsetType::Byte)
+                    .into_iter()
+                    .filter(|(_, _, tokens)| tokens.is_none())
+                    .map(|(split, _, _)| self.model.tokenize_nbest(split, k))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Result<_>>()?;
+        let scores = candidates
+            .iter()
+            .map(|c| c.iter().map(|(_, score)| *score).collect())
+            .collect::<Vec<_>>();
+
+        combine_nbest(&scores, k)
+            .into_iter()
+            .map(|(choices, score)| {
+                let mut chosen = choices
+                    .into_iter()
+                    .enumerate()
+                    .map(|(split, choice)| candidates[split][choice].0.clone());
+                let encoding = subsequences
+                    .iter()
+                    .map(|(pre_tokenized, word_idx)| {
+                        let mut pre_tokenized = pre_tokenized.clone();
+                        pre_tokenized.tokenize(|_| {
+                            chosen
+                                .next()
+                                .ok_or_else(|| "Missing tokenization candidate".into())
+                        })?;
+                        pre_tokenized.into_encoding(*word_idx, type_id, offsets_type)
+                    })
+                    .collect::<Result<Encoding>>()?;
+                Ok((encoding, score))
+            })
+            .collect()
+    }
+
+    /// Encode the given input into its `k` best tokenization candidates, each with its
+    /// score. Just like with [`encode`](Self::encode), the input can be made of one
+    /// or several sequences, and each candidate gets truncated, post-processed and padded.
+    ///
+    /// The candidates are sorted from best to worst, and there might be less than `k` of them
+    /// if the input doesn't have that many possible tokenizations. This fails if the model
+    /// can't produce alternative tokenizations.
+    pub fn encode_nbest<'s, E>(
+        &self,
+        input: E,
+        k: usize,
+        add_special_tokens: bool,
+    ) -> Result<Vec<(Encoding, f64)>>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        let candidates = input
+            .into()
+            .into_sequences()
+            .into_iter()
+            .enumerate()
+            .map(|(i, sequence)| {
+                self.encode_single_sequence_nbest(sequence, i as u32, OffsetType::Byte, k)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let lists = candidates
+            .iter()
+            .map(|encodings| encodings.iter().map(|(_, score)| *score).collect())
+            .collect::<Vec<Vec<f64>>>();
+
+        combine_nbest(&lists, k)
+            .into_iter()
+            .map(|(choices, score)| {
+                let encodings = candidates
+                    .iter()
+                    .zip(choices)
+                    .map(|(encodings, choice)| encodings[choice].0.clone())
+                    .collect();
+                Ok((
+                    self.post_process_multi(encodings, add_special_tokens)?,
+                    score,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::combine_nbest;
+    use crate::models::unigram::Unigram;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::processors::template::TemplateProcessing;
+    use crate::{AddedToken, Tokenizer};
+
+    fn unigram_tokenizer() -> Tokenizer {
+        let pieces = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -1.0),
+            ("b".to_string(), -1.0),
+            ("ab".to_string(), -1.5),
+            ("c".to_string(), -0.5),
+        ];
+        let mut tokenizer = Tokenizer::new(Unigram::from(pieces, Some(0), false).unwrap());
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer
+    }
+
+    #[test]
+    fn combine() {
+        let combinations = combine_nbest(&[vec![-1.0, -2.0], vec![-0.5, -3.0]], 3);
+        assert_eq!(
+            combinations,
+            vec![(vec![0, 0], -1.5), (vec![1, 0], -2.5), (vec![0, 1], -4.0)]
+        );
+        assert_eq!(combine_nbest(&[], 3), vec![(vec![], 0.0)]);
+    }
+
+    #[test]
+    fn encode_nbest() {
+        let tokenizer = unigram_tokenizer();
+        let candidates = tokenizer.encode_nbest("ab ab", 3, false).unwrap();
+        let tokens = candidates
+            .iter()
+            .map(|(e, s)| (e.get_tokens().join(" "), *s))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                ("ab ab".to_string(), -3.0),
+                ("ab a b".to_string(), -3.5),
+                ("a b ab".to_string(), -3.5),
+            ]
+        );
+
+        // Offsets and word ids are relative to the original input
+        let (encoding, _) = &candidates[1];
+        assert_eq!(encoding.get_offsets(), &[(0, 2), (3, 4), (4, 5)]);
+        assert_eq!(encoding.get_word_ids(), &[Some(0), Some(1), Some(1)]);
+
+        // The best candidate is the same as the regular encoding
+        assert_eq!(candidates[0].0, tokenizer.encode("ab ab", false).unwrap());
+    }
+
+    #[test]
+    fn encode_nbest_post_processed() {
+        let mut tokenizer = unigram_tokenizer();
+        tokenizer.add_special_tokens(&[AddedToken::from("[SEP]", true)]);
+        tokenizer.with_post_processor(
+            TemplateProcessing::builder()
+                .try_single("$A [SEP]")
+                .unwrap()
+                .try_pair("$A [SEP] $B:1 [SEP]:1")
+                .unwrap()
+                .special_tokens(vec![("[SEP]", 5)])
+                .build()
+                .unwrap(),
+        );
+
+        let candidates = tokenizer.encode_nbest(("ab", "c"), 2, true).unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].0.get_tokens(), &["ab", "[SEP]", "c", "[SEP]"]);
+        assert_eq!(candidates[0].0.get_type_ids(), &[0, 0, 1, 1]);
+        assert_eq!(
+            candidates[1].0.get_tokens(),
+            &["a", "b", "[SEP]", "c", "[SEP]"]
+        );
+        assert_eq!(candidates[1].1, -2.5);
+    }
+
+    #[test]
+    fn encode_nbest_unsupported() {
+        let vocab = vec![("a".to_string(), 0)].into_iter().collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("a".into())
+            .build()
+            .unwrap();
+        let tokenizer = Tokenizer::new(model);
+        assert!(tokenizer.encode_nbest("a", 2, false).is_err());
+    }
+}
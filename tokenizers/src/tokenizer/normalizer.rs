@@ -771,6 +771,14 @@ impl NormalizedString {
         self.normalized.is_empty()
     }
 
+    /// For each byte of the normalized string, the offsets of the bytes it comes from in the
+    /// whole original string (including the part before this slice)
+    pub(crate) fn alignments_shifted(&self) -> impl Iterator<Item = Offsets> + '_ {
+        self.alignments
+            .iter()
+            .map(move |(start, end)| (start + self.original_shift, end + self.original_shift))
+    }
+
     /// Recalculate original alignments
     #[allow(dead_code)]
     pub(crate) fn alignments_original(&self) -> Vec<(usize, usize)> {
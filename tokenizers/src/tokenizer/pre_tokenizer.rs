@@ -114,9 +114,9 @@ impl PreTokenizedString {
 
     /// Tokenize all the splits that do not have attached `Tokens`, using the provided
     /// `tokenize` function
-    pub fn tokenize<F>(&mut self, tokenize: F) -> Result<()>
+    pub fn tokenize<F>(&mut self, mut tokenize: F) -> Result<()>
     where
-        F: Fn(&NormalizedString) -> Result<Vec<Token>>,
+        F: FnMut(&NormalizedString) -> Result<Vec<Token>>,
     {
         for split in self.splits.iter_mut().filter(|s| s.tokens.is_none()) {
             split.tokens = Some(tokenize(&split.normalized)?);
@@ -125,6 +125,11 @@ impl PreTokenizedString {
         Ok(())
     }
 
+    /// The `NormalizedString` of each split
+    pub(crate) fn normalized_splits(&self) -> impl Iterator<Item = &NormalizedString> {
+        self.splits.iter().map(|split| &split.normalized)
+    }
+
     /// Transform the current `PreTokenizedString` into an `Encoding`.
     ///
     /// If a `word_idx` is provided, any word in the generated `Encoding`
@@ -186,6 +191,43 @@ impl PreTokenizedString {
         }
     }
 
+    /// Transform the current `PreTokenizedString` into an `Encoding` holding only the ids,
+    /// type ids and word ids of its tokens. The splits that have not been tokenized yet
+    /// are given to `tokenize_ids`. The tokens are left empty and the offsets are all
+    /// `(0, 0)`, which avoids allocating them one by one.
+    pub(crate) fn into_ids_encoding<F>(
+        self,
+        tokenize_ids: F,
+        word_idx: Option<u32>,
+        type_id: u32,
+    ) -> Result<Encoding>
+    where
+        F: Fn(&NormalizedString) -> Result<Vec<u32>>,
+    {
+        let mut ids = vec![];
+        let mut words = vec![];
+        for (idx, split) in self.splits.into_iter().enumerate() {
+            let word = word_idx.or(Some(idx as u32));
+            match split.tokens {
+                Some(tokens) => ids.extend(tokens.into_iter().map(|token| token.id)),
+                None => ids.extend(tokenize_ids(&split.normalized)?),
+            }
+            words.resize(ids.len(), word);
+        }
+        let len = ids.len();
+        Ok(Encoding::new(
+            ids,
+            vec![type_id; len],
+            vec![String::new(); len],
+            words,
+            vec![(0, 0); len],
+            vec![0; len],
+            vec![1; len],
+            vec![],
+            HashMap::new(),
+        ))
+    }
+
     /// Returns a list of splits, each of them being a slice of the normalized
     /// string, the associated offsets either in original or normalized
     /// referential, as well as the potention tokens
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+/// The role a special token plays for the model, like marking the end of a sequence or
+/// being used for padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecialTokenRole {
+    /// Beginning of sequence
+    Bos,
+    /// End of sequence
+    Eos,
+    /// Padding
+    Pad,
+    /// Unknown token
+    Unk,
+    /// Masked token, for masked language modeling
+    Mask,
+    /// Classification token
+    Cls,
+    /// Separator between sequences
+    Sep,
+}
+
+impl std::fmt::Display for SpecialTokenRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Self::Bos => "bos",
+            Self::Eos => "eos",
+            Self::Pad => "pad",
+            Self::Unk => "unk",
+            Self::Mask => "mask",
+            Self::Cls => "cls",
+            Self::Sep => "sep",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SpecialTokenRoleError {
+    #[error("No token has the {0} role")]
+    MissingRole(SpecialTokenRole),
+    #[error("The {0} token '{1}' is not part of the vocabulary")]
+    MissingToken(SpecialTokenRole, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::processors::template::TemplateProcessing;
+    use crate::{AddedToken, PaddingParams, PaddingStrategy, Tokenizer};
+
+    #[test]
+    fn padding_from_role() {
+        let vocab = [("hello".to_string(), 0)].iter().cloned().collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("hello".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.add_special_tokens(&[AddedToken::from("<pad>", true)]);
+
+        let padding = PaddingParams {
+            strategy: PaddingStrategy::Fixed(3),
+            ..Default::default()
+        };
+        let err = tokenizer
+            .with_padding_from_role(padding.clone())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "No token has the pad role");
+
+        tokenizer
+            .with_special_token_role(SpecialTokenRole::Pad, "<pad>")
+            .unwrap();
+        tokenizer.with_padding_from_role(padding).unwrap();
+        let encoding = tokenizer.encode("hello", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[0, 1, 1]);
+        assert_eq!(encoding.get_tokens(), &["hello", "<pad>", "<pad>"]);
+    }
+
+    #[test]
+    fn reassign_role() {
+        let vocab = [("hello".to_string(), 0)].iter().cloned().collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("hello".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("<pad>", true),
+            AddedToken::from("<s>", true),
+            AddedToken::from("</s>", true),
+            AddedToken::from("[PAD]", true),
+            AddedToken::from("[CLS]", true),
+        ]);
+
+        let err = tokenizer
+            .with_special_token_role(SpecialTokenRole::Pad, "<missing>")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The pad token '<missing>' is not part of the vocabulary"
+        );
+        assert_eq!(
+            tokenizer.get_special_token_role(SpecialTokenRole::Pad),
+            None
+        );
+
+        tokenizer
+            .with_special_token_role(SpecialTokenRole::Pad, "<pad>")
+            .unwrap()
+            .with_special_token_role(SpecialTokenRole::Cls, "<s>")
+            .unwrap()
+            .with_special_token_role(SpecialTokenRole::Sep, "</s>")
+            .unwrap();
+        tokenizer
+            .with_padding_from_role(PaddingParams {
+                strategy: PaddingStrategy::Fixed(4),
+                ..Default::default()
+            })
+            .unwrap();
+        let processor = TemplateProcessing::builder()
+            .try_single("<s> $A </s>")
+            .unwrap()
+            .special_tokens(vec![
+                tokenizer
+                    .get_special_token_role_with_id(SpecialTokenRole::Cls)
+                    .unwrap(),
+                tokenizer
+                    .get_special_token_role_with_id(SpecialTokenRole::Sep)
+                    .unwrap(),
+            ])
+            .build()
+            .unwrap();
+        tokenizer.with_post_processor(processor);
+        let encoding = tokenizer.encode("hello", true).unwrap();
+        assert_eq!(encoding.get_tokens(), &["<s>", "hello", "</s>", "<pad>"]);
+
+        tokenizer
+            .with_special_token_role(SpecialTokenRole::Pad, "[PAD]")
+            .unwrap()
+            .with_special_token_role(SpecialTokenRole::Cls, "[CLS]")
+            .unwrap();
+        let encoding = tokenizer.encode("hello", true).unwrap();
+        assert_eq!(encoding.get_tokens(), &["[CLS]", "hello", "</s>", "[PAD]"]);
+        assert_eq!(encoding.get_ids(), &[5, 0, 3, 4]);
+    }
+}
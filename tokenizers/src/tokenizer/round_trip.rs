@@ -0,0 +1,449 @@
+use super::{Encoding, Offsets, Result, TokenizerImpl};
+use crate::decoders::DecoderWrapper;
+use crate::models::ModelWrapper;
+use crate::normalizers::NormalizerWrapper;
+use crate::pre_tokenizers::PreTokenizerWrapper;
+use crate::processors::PostProcessorWrapper;
+use crate::utils::parallelism::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Why a text did not survive a round-trip through the tokenizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchCause {
+    /// The normalizer changed the text, and the decoder did not undo it
+    Normalizer,
+    /// Some parts of the text were encoded as the unknown token
+    UnknownToken,
+    /// Some special tokens of the text were skipped while decoding
+    SpecialTokens,
+    /// The end of the text was truncated
+    Truncation,
+    /// The decoder did not give back the normalized text, like when cleaning up spaces
+    Decoder,
+}
+
+/// A text that did not survive a round-trip
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTripMismatch {
+    /// The index of the text in the corpus
+    pub index: usize,
+    pub cause: MismatchCause,
+    pub original: String,
+    pub decoded: String,
+    /// The byte offsets of the parts of `original` that were lost or altered
+    pub lost: Vec<Offsets>,
+    /// The words of `original` around the first lost part, when they fail on their own,
+    /// or the whole `original` otherwise
+    pub minimal: String,
+}
+
+/// The result of [`check_round_trip`](TokenizerImpl::check_round_trip)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTripReport {
+    /// The number of texts checked
+    pub total: usize,
+    /// The number of texts decoded back exactly
+    pub exact: usize,
+    /// The number of mismatches for each cause
+    pub counts: BTreeMap<MismatchCause, usize>,
+    /// The shortest mismatches for each cause
+    pub examples: Vec<RoundTripMismatch>,
+}
+
+impl RoundTripReport {
+    /// The number of texts that were not decoded back exactly
+    pub fn mismatches(&self) -> usize {
+        self.total - self.exact
+    }
+
+    /// Add the report of the texts following the ones checked by this report, keeping at
+    /// most `max_examples` examples for each cause
+    pub fn merge(&mut self, other: RoundTripReport, max_examples: usize) {
+        let offset = self.total;
+        self.total += other.total;
+        self.exact += other.exact;
+        for (cause, count) in other.counts {
+            *self.counts.entry(cause).or_default() += count;
+        }
+        self.examples
+            .extend(other.examples.into_iter().map(|mut example| {
+                example.index += offset;
+                example
+            }));
+        keep_shortest(&mut self.examples, max_examples);
+    }
+}
+
+/// Keep the `max_examples` shortest examples of each cause, sorted by cause
+fn keep_shortest(examples: &mut Vec<RoundTripMismatch>, max_examples: usize) {
+    examples.sort_by_key(|example| (example.cause, example.original.len(), example.index));
+    let mut kept = BTreeMap::new();
+    examples.retain(|example| {
+        let count = kept.entry(example.cause).or_insert(0);
+        *count += 1;
+        *count <= max_examples
+    });
+}
+
+/// Add `range` to the sorted `ranges`, merging it with the last one when they touch
+fn push_range(ranges: &mut Vec<Offsets>, range: Offsets) {
+    match ranges.last_mut() {
+        Some(last) if last.1 >= range.0 => last.1 = last.1.max(range.1),
+        _ => ranges.push(range),
+    }
+}
+
+/// The ids of the encoding, without the padding
+fn unpadded_ids(encoding: &Encoding) -> Vec<u32> {
+    encoding
+        .get_ids()
+        .iter()
+        .zip(encoding.get_attention_mask())
+        .filter(|(_, mask)| **mask == 1)
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/// The byte range of `expected` that differs from `actual`, once their common prefix and
+/// suffix are removed. It is empty when `actual` only has some additional content.
+fn diff_range(expected: &str, actual: &str) -> Range<usize> {
+    let same_len = |(a, b): (char, char)| (a == b).then(|| a.len_utf8());
+    let prefix: usize = expected
+        .chars()
+        .zip(actual.chars())
+        .map_while(same_len)
+        .sum();
+    let suffix: usize = expected[prefix..]
+        .chars()
+        .rev()
+        .zip(actual[prefix..].chars().rev())
+        .map_while(same_len)
+        .sum();
+    prefix..expected.len() - suffix
+}
+
+/// The ranges of `original` that were removed or modified to give `normalized`, using the
+/// `alignments` of each byte of `normalized`
+fn changed_ranges(original: &str, normalized: &str, alignments: &[Offsets]) -> Vec<Offsets> {
+    let mut changed = vec![];
+    let mut covered = 0;
+    let mut start = 0;
+    while start < alignments.len() {
+        // All the bytes produced by the same original chars form a group
+        let (from, to) = alignments[start];
+        let end = alignments[start..]
+            .iter()
+            .position(|alignment| *alignment != (from, to))
+            .map_or(alignments.len(), |len| start + len);
+
+        if from > covered {
+            push_range(&mut changed, (covered, from));
+        }
+        if normalized.get(start..end) != original.get(from..to) {
+            push_range(&mut changed, (from, to));
+        }
+        covered = covered.max(to);
+        start = end;
+    }
+    if covered < original.len() {
+        push_range(&mut changed, (covered, original.len()));
+    }
+    changed
+}
+
+impl
+    TokenizerImpl<
+        ModelWrapper,
+        NormalizerWrapper,
+        PreTokenizerWrapper,
+        PostProcessorWrapper,
+        DecoderWrapper,
+    >
+{
+    /// Check which texts of the corpus survive a round-trip through `encode_batch` and
+    /// `decode_batch`, and classify the other ones by what made them differ. The special
+    /// tokens are not added while encoding, but skipped while decoding. At most
+    /// `max_examples` examples are kept for each cause, the shortest ones.
+    pub fn check_round_trip<S>(&self, corpus: &[S], max_examples: usize) -> Result<RoundTripReport>
+    where
+        S: AsRef<str> + Sync,
+    {
+        let texts = corpus.iter().map(|text| text.as_ref()).collect::<Vec<_>>();
+        let encodings = self.encode_batch(texts.clone(), false)?;
+        let ids = encodings.iter().map(unpadded_ids).collect::<Vec<_>>();
+        let decoded = self.decode_batch(
+            &ids.iter().map(|ids| ids.as_slice()).collect::<Vec<_>>(),
+            true,
+        )?;
+
+        let mismatches = (0..texts.len())
+            .into_maybe_par_iter()
+            .map(|index| {
+                let (original, decoded) = (texts[index], &decoded[index]);
+                if original == decoded {
+                    return Ok(None);
+                }
+                let (cause, lost) =
+                    self.mismatch_cause(original, &encodings[index], &ids[index])?;
+                Ok(Some(RoundTripMismatch {
+                    index,
+                    cause,
+                    original: original.to_owned(),
+                    decoded: decoded.clone(),
+                    lost,
+                    minimal: String::new(),
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut report = RoundTripReport {
+            total: texts.len(),
+            ..Default::default()
+        };
+        for mismatch in mismatches {
+            match mismatch {
+                Some(mismatch) => {
+                    *report.counts.entry(mismatch.cause).or_default() += 1;
+                    report.examples.push(mismatch);
+                }
+                None => report.exact += 1,
+            }
+        }
+
+        // Looking for the minimal examples requires encoding again, so we only do it for the
+        // ones we keep
+        keep_shortest(&mut report.examples, max_examples);
+        for example in &mut report.examples {
+            let lost = example.lost.first().copied().unwrap_or_default();
+            example.minimal = self.minimal_example(&example.original, lost)?;
+        }
+        Ok(report)
+    }
+
+    /// Find why `original` did not survive the round-trip, along with the parts of it that
+    /// were lost
+    fn mismatch_cause(
+        &self,
+        original: &str,
+        encoding: &Encoding,
+        ids: &[u32],
+    ) -> Result<(MismatchCause, Vec<Offsets>)> {
+        let tokens = encoding
+            .get_ids()
+            .iter()
+            .zip(encoding.get_tokens())
+            .zip(encoding.get_offsets())
+            .zip(encoding.get_attention_mask())
+            .filter(|(_, mask)| **mask == 1)
+            .map(|(((id, token), offsets), _)| (*id, token, *offsets))
+            .collect::<Vec<_>>();
+
+        if !encoding.get_overflowing().is_empty() {
+            let end = tokens.iter().map(|(_, _, offsets)| offsets.1).max();
+            let end = end.unwrap_or_default();
+            return Ok((MismatchCause::Truncation, vec![(end, original.len())]));
+        }
+
+        let unk_id = self.model.get_unk_id();
+        let mut unknown = vec![];
+        for (_, _, offsets) in tokens.iter().filter(|(id, _, _)| Some(*id) == unk_id) {
+            push_range(&mut unknown, *offsets);
+        }
+        if !unknown.is_empty() {
+            return Ok((MismatchCause::UnknownToken, unknown));
+        }
+
+        let mut special = vec![];
+        for (_, _, offsets) in tokens
+            .iter()
+            .filter(|(_, token, _)| self.added_vocabulary.is_special_token(token))
+        {
+            push_range(&mut special, *offsets);
+        }
+        if !special.is_empty() && self.decode(ids, false)? == original {
+            return Ok((MismatchCause::SpecialTokens, special));
+        }
+
+        let pre_tokenized = self
+            .added_vocabulary
+            .extract_and_normalize(self.normalizer.as_ref(), original);
+        let normalized = pre_tokenized
+            .normalized_splits()
+            .map(|normalized| normalized.get())
+            .collect::<String>();
+        let alignments = pre_tokenized
+            .normalized_splits()
+            .flat_map(|normalized| normalized.alignments_shifted())
+            .collect::<Vec<_>>();
+
+        let decoded = self.decode(ids, true)?;
+        if normalized != original && decoded == normalized {
+            let changed = changed_ranges(original, &normalized, &alignments);
+            return Ok((MismatchCause::Normalizer, changed));
+        }
+
+        // Whatever the decoder lost, it is relative to the normalized text
+        let range = diff_range(&normalized, &decoded);
+        let start = alignments
+            .get(range.start)
+            .map_or(original.len(), |alignment| alignment.0);
+        let end = if range.is_empty() {
+            start
+        } else {
+            alignments[range.end - 1].1
+        };
+        Ok((MismatchCause::Decoder, vec![(start, end)]))
+    }
+
+    /// The whitespace delimited words of `original` around `lost`, when they fail to
+    /// round-trip on their own, or the whole `original` otherwise
+    fn minimal_example(&self, original: &str, lost: Offsets) -> Result<String> {
+        // Lost whitespace only shows between the words around it
+        let (mut from, mut to) = lost;
+        if original[from..to].trim().is_empty() {
+            from = original[..from].trim_end().len();
+            to = original.len() - original[to..].trim_start().len();
+        }
+
+        let start = original[..from]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let end = original[to..]
+            .find(char::is_whitespace)
+            .map_or(original.len(), |i| to + i);
+
+        let candidate = &original[start..end];
+        if candidate.len() < original.len() {
+            let encoding = self.encode(candidate, false)?;
+            if self.decode(&unpadded_ids(&encoding), true)? != candidate {
+                return Ok(candidate.to_owned());
+            }
+        }
+        Ok(original.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::normalizers::utils::Lowercase;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::{AddedToken, Tokenizer, TruncationParams};
+
+    fn tokenizer() -> Tokenizer {
+        let vocab = [("[UNK]", 0), ("hello", 1), ("there", 2), ("you", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_normalizer(Lowercase);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer.add_special_tokens(&[AddedToken::from("<s>", true)]);
+        tokenizer
+    }
+
+    fn causes(report: &RoundTripReport) -> Vec<(usize, MismatchCause, Vec<Offsets>, &str)> {
+        report
+            .examples
+            .iter()
+            .map(|example| {
+                (
+                    example.index,
+                    example.cause,
+                    example.lost.clone(),
+                    example.minimal.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_round_trip() {
+        let tokenizer = tokenizer();
+        let corpus = [
+            "hello there",
+            "Hello there",
+            "you hello stranger there",
+            "hello <s> there",
+            "hello  there",
+        ];
+        let report = tokenizer.check_round_trip(&corpus, 5).unwrap();
+        assert_eq!(report.total, 5);
+        assert_eq!(report.exact, 1);
+        assert_eq!(report.mismatches(), 4);
+        assert_eq!(
+            causes(&report),
+            [
+                (1, MismatchCause::Normalizer, vec![(0, 1)], "Hello"),
+                (2, MismatchCause::UnknownToken, vec![(10, 18)], "stranger"),
+                (3, MismatchCause::SpecialTokens, vec![(6, 9)], "<s>"),
+                (4, MismatchCause::Decoder, vec![(6, 7)], "hello  there"),
+            ]
+        );
+        assert_eq!(report.examples[1].decoded, "you hello [UNK] there");
+
+        let serialized = serde_json::to_string(&report).unwrap();
+        assert!(serialized.contains(r#""counts":{"normalizer":1,"unknown_token":1"#));
+        assert_eq!(
+            serde_json::from_str::<RoundTripReport>(&serialized).unwrap(),
+            report
+        );
+    }
+
+    #[test]
+    fn check_round_trip_truncation_and_merge() {
+        let mut tokenizer = tokenizer();
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 2,
+                ..Default::default()
+            }))
+            .unwrap();
+
+        let mut report = tokenizer
+            .check_round_trip(&["hello there you", "Hello"], 1)
+            .unwrap();
+        assert_eq!(
+            causes(&report)[1],
+            (
+                0,
+                MismatchCause::Truncation,
+                vec![(11, 15)],
+                "hello there you"
+            )
+        );
+
+        let other = tokenizer
+            .check_round_trip(&["HELLO there", "hi", "there"], 1)
+            .unwrap();
+        report.merge(other, 1);
+        assert_eq!(report.total, 5);
+        assert_eq!(report.exact, 1);
+        assert_eq!(report.counts[&MismatchCause::Normalizer], 2);
+        assert_eq!(report.counts[&MismatchCause::UnknownToken], 1);
+        // Only the shortest example is kept for each cause
+        assert_eq!(
+            report
+                .examples
+                .iter()
+                .map(|example| (example.index, example.cause))
+                .collect::<Vec<_>>(),
+            [
+                (1, MismatchCause::Normalizer),
+                (3, MismatchCause::UnknownToken),
+                (0, MismatchCause::Truncation)
+            ]
+        );
+    }
+}
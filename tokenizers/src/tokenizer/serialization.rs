@@ -1,8 +1,9 @@
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 
 use serde::{
     self,
-    de::{Error, MapAccess, Visitor},
+    de::{DeserializeOwned, Error, MapAccess, Visitor},
     ser::SerializeStruct,
     Deserialize, Deserializer, Serialize, Serializer,
 };
@@ -12,6 +13,23 @@ use crate::{Decoder, Model, Normalizer, PostProcessor, PreTokenizer, TokenizerBu
 
 static SERIALIZATION_VERSION: &str = "1.0";
 
+/// Deserialize a tokenizer from JSON, telling apart the versions of the format we don't
+/// support from the other errors
+pub(crate) fn from_json<T: DeserializeOwned>(json: &[u8]) -> super::Result<T> {
+    serde_json::from_slice(json).map_err(|error| {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: String,
+        }
+        match serde_json::from_slice::<Versioned>(json) {
+            Ok(Versioned { version }) if version != SERIALIZATION_VERSION => {
+                super::Error::UnsupportedVersion(version)
+            }
+            _ => error.into(),
+        }
+    })
+}
+
 impl<M, N, PT, PP, D> Serialize for TokenizerImpl<M, N, PT, PP, D>
 where
     M: Serialize,
@@ -24,7 +42,7 @@ where
     where
         S: Serializer,
     {
-        let mut tokenizer = serializer.serialize_struct("Tokenizer", 9)?;
+        let mut tokenizer = serializer.serialize_struct("Tokenizer", 10)?;
 
         // Start by adding the current version
         tokenizer.serialize_field("version", SERIALIZATION_VERSION)?;
@@ -35,6 +53,11 @@ where
 
         // Added tokens
         tokenizer.serialize_field("added_tokens", &self.added_vocabulary)?;
+        if self.special_token_roles.is_empty() {
+            tokenizer.skip_field("special_token_roles")?;
+        } else {
+            tokenizer.serialize_field("special_token_roles", &self.special_token_roles)?;
+        }
 
         // Then add our parts
         tokenizer.serialize_field("normalizer", &self.normalizer)?;
@@ -66,6 +89,7 @@ where
                 "truncation",
                 "padding",
                 "added_tokens",
+                "special_token_roles",
                 "normalizer",
                 "pre_tokenizer",
                 "post_processor",
@@ -111,6 +135,7 @@ where
     {
         let mut builder = TokenizerBuilder::new();
         let mut tokens: Vec<AddedTokenWithId> = vec![];
+        let mut roles = BTreeMap::new();
         while let Some(key) = map.next_key::<String>()? {
             match key.as_ref() {
                 "version" => {
@@ -128,6 +153,9 @@ where
                 "added_tokens" => {
                     tokens = map.next_value()?;
                 }
+                "special_token_roles" => {
+                    roles = map.next_value()?;
+                }
                 "normalizer" => {
                     builder = builder.with_normalizer(map.next_value()?);
                 }
@@ -171,13 +199,18 @@ where
         let added_tokens: Vec<_> = tokens.into_iter().map(|token| token.token).collect();
         tokenizer.add_tokens(&added_tokens[..]);
 
+        // The roles are set last, once all their tokens are known
+        tokenizer
+            .with_special_token_roles(roles)
+            .map_err(|e| V::Error::custom(e.to_string()))?;
+
         Ok(tokenizer)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::tokenizer::Tokenizer;
+    use crate::tokenizer::{Error, Tokenizer};
     use std::str::FromStr;
 
     #[test]
@@ -233,4 +266,64 @@ mod tests {
         // It should be exactly the same as above
         assert_eq!(tok_str, tok_json);
     }
+
+    #[test]
+    fn special_token_roles() {
+        let tok_json = r#"{
+  "version": "1.0",
+  "truncation": null,
+  "padding": null,
+  "added_tokens": [
+    {
+      "id": 1,
+      "content": "<eos>",
+      "single_word": false,
+      "lstrip": false,
+      "rstrip": false,
+      "normalized": false,
+      "special": true
+    }
+  ],
+  "special_token_roles": {
+    "eos": "<eos>",
+    "unk": "[UNK]"
+  },
+  "normalizer": null,
+  "pre_tokenizer": null,
+  "post_processor": null,
+  "decoder": null,
+  "model": {
+    "type": "WordLevel",
+    "vocab": {
+      "[UNK]": 0
+    },
+    "unk_token": "[UNK]"
+  }
+}"#;
+        let tokenizer = Tokenizer::from_str(tok_json).unwrap();
+        assert_eq!(tokenizer.eos_token_id(), Some(1));
+        assert_eq!(tokenizer.unk_token_id(), Some(0));
+        assert_eq!(tokenizer.pad_token_id(), None);
+        assert_eq!(serde_json::to_string_pretty(&tokenizer).unwrap(), tok_json);
+
+        // The roles must point to tokens of the vocabulary
+        let err = Tokenizer::from_str(&tok_json.replace(r#""eos": "<eos>""#, r#""eos": "</s>""#))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("The eos token '</s>' is not part of the vocabulary"));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let tok_json = r#"{"version": "2.0", "model": {"type": "WordLevel", "vocab": {}}}"#;
+        assert!(matches!(
+            Tokenizer::from_str(tok_json),
+            Err(Error::UnsupportedVersion(version)) if version == "2.0"
+        ));
+        assert!(matches!(
+            Tokenizer::from_str(&tok_json.replace("2.0", "1.0")[1..]),
+            Err(Error::Serialization(_))
+        ));
+    }
 }
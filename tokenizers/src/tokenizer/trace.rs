@@ -0,0 +1,292 @@
+use super::{
+    Decoder, EncodeInput, Encoding, Model, Normalizer, OffsetReferential, OffsetType, Offsets,
+    PostProcessor, PreTokenizedString, PreTokenizer, Result, Token, TokenizerImpl,
+};
+use serde::{Deserialize, Serialize};
+
+/// A split of an input sequence, at some stage of the pipeline
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceSplit {
+    /// The normalized content of the split
+    pub normalized: String,
+    /// The offsets of the split in the original sequence
+    pub offsets: Offsets,
+    /// The tokens of the split, once known, with offsets relative to `normalized`
+    pub tokens: Option<Vec<Token>>,
+}
+
+impl TraceSplit {
+    fn from_pre_tokenized(pre_tokenized: &PreTokenizedString) -> Vec<Self> {
+        pre_tokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .map(|(normalized, offsets, tokens)| Self {
+                normalized: normalized.to_owned(),
+                offsets,
+                tokens: tokens.clone(),
+            })
+            .collect()
+    }
+}
+
+/// What each stage of the pipeline did to an input sequence. Pre-tokenized sequences get
+/// one of these for each of their words.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceTrace {
+    /// The index of the sequence in the input
+    pub sequence_id: usize,
+    /// The index of the word, for pre-tokenized sequences
+    pub word_id: Option<u32>,
+    /// The sequence, as given
+    pub original: String,
+    /// The splits around the added tokens. The ones matching an added token have it as
+    /// their only token, and are left untouched by the normalizer.
+    pub added_tokens: Vec<TraceSplit>,
+    /// The whole normalized sequence
+    pub normalized: String,
+    /// For each byte of `normalized`, the offsets of the bytes it comes from in `original`
+    pub alignments: Vec<Offsets>,
+    /// The splits produced by the pre-tokenizer
+    pub pre_tokenized: Vec<TraceSplit>,
+    /// The splits with the tokens produced by the model
+    pub tokenized: Vec<TraceSplit>,
+}
+
+/// A token inserted by the post-processor
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceInsertion {
+    /// The index of the token in the final encoding
+    pub index: usize,
+    pub id: u32,
+    pub token: String,
+}
+
+/// Everything that happened while encoding an input, as returned by
+/// [`encode_with_trace`](TokenizerImpl::encode_with_trace)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncodeTrace {
+    /// The trace of each input sequence, in order
+    pub sequences: Vec<SequenceTrace>,
+    /// The tokens inserted by the post-processor, like the special tokens
+    pub insertions: Vec<TraceInsertion>,
+    /// The final encoding, like the one returned by `encode`
+    pub encoding: Encoding,
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model,
+    N: Normalizer,
+    PT: PreTokenizer,
+    PP: PostProcessor,
+    D: Decoder,
+{
+    /// Encode the given input like `encode`, keeping track of what each stage of the
+    /// pipeline does. All the offsets are byte offsets.
+    pub fn encode_with_trace<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+    ) -> Result<EncodeTrace>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        let mut sequences = vec![];
+        let mut encodings = vec![];
+        for (sequence_id, sequence) in input.into().into_sequences().into_iter().enumerate() {
+            let mut words = vec![];
+            for (word_id, subseq) in sequence.subsequences() {
+                let pre_tokenized = self
+                    .added_vocabulary
+                    .extract_and_normalize(self.normalizer.as_ref(), subseq);
+                let added_tokens = TraceSplit::from_pre_tokenized(&pre_tokenized);
+                let normalized = pre_tokenized
+                    .normalized_splits()
+                    .map(|normalized| normalized.get())
+                    .collect();
+                let alignments = pre_tokenized
+                    .normalized_splits()
+                    .flat_map(|normalized| normalized.alignments_shifted())
+                    .collect();
+
+                let mut pre_tokenized = self.do_pre_tokenize(pre_tokenized)?;
+                let pre_tokenized_splits = TraceSplit::from_pre_tokenized(&pre_tokenized);
+
+                pre_tokenized.tokenize(|normalized| self.model.tokenize(normalized.get()))?;
+                let tokenized = TraceSplit::from_pre_tokenized(&pre_tokenized);
+
+                words.push(pre_tokenized.into_encoding(
+                    word_id,
+                    sequence_id as u32,
+                    OffsetType::Byte,
+                )?);
+                sequences.push(SequenceTrace {
+                    sequence_id,
+                    word_id,
+                    original: subseq.to_owned(),
+                    added_tokens,
+                    normalized,
+                    alignments,
+                    pre_tokenized: pre_tokenized_splits,
+                    tokenized,
+                });
+            }
+            encodings.push(words.into_iter().collect());
+        }
+
+        let encoding = self.post_process_multi(encodings, add_special_tokens)?;
+        // The padding is not part of the post-processing
+        let insertions = encoding
+            .get_sequence_ids()
+            .into_iter()
+            .enumerate()
+            .filter(|(i, sequence_id)| {
+                sequence_id.is_none() && encoding.get_attention_mask()[*i] == 1
+            })
+            .map(|(index, _)| TraceInsertion {
+                index,
+                id: encoding.get_ids()[index],
+                token: encoding.get_tokens()[index].clone(),
+            })
+            .collect();
+
+        Ok(EncodeTrace {
+            sequences,
+            insertions,
+            encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::normalizers::utils::Lowercase;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::processors::template::TemplateProcessing;
+    use crate::{AddedToken, Tokenizer};
+
+    fn tokenizer() -> Tokenizer {
+        let vocab = [
+            ("[UNK]", 0),
+            ("hello", 1),
+            ("there", 2),
+            ("[CLS]", 3),
+            ("[SEP]", 4),
+        ]
+        .iter()
+        .map(|(t, id)| (t.to_string(), *id))
+        .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_normalizer(Lowercase);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer.with_post_processor(
+            TemplateProcessing::builder()
+                .try_single("[CLS] $A [SEP]")
+                .unwrap()
+                .try_pair("[CLS] $A [SEP] $B:1 [SEP]:1")
+                .unwrap()
+                .special_tokens(vec![("[CLS]", 3), ("[SEP]", 4)])
+                .build()
+                .unwrap(),
+        );
+        tokenizer.add_tokens(&[AddedToken::from("<x>", false)]);
+        tokenizer
+    }
+
+    #[test]
+    fn encode_with_trace() {
+        let tokenizer = tokenizer();
+        let trace = tokenizer
+            .encode_with_trace(("Hello<x> THERE", "hi"), true)
+            .unwrap();
+        assert_eq!(
+            trace.encoding,
+            tokenizer.encode(("Hello<x> THERE", "hi"), true).unwrap()
+        );
+        assert_eq!(trace.sequences.len(), 2);
+
+        let first = &trace.sequences[0];
+        assert_eq!(first.original, "Hello<x> THERE");
+        assert_eq!(
+            first
+                .added_tokens
+                .iter()
+                .map(|split| (
+                    split.normalized.as_str(),
+                    split.offsets,
+                    split.tokens.is_some()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("hello", (0, 5), false),
+                ("<x>", (5, 8), true),
+                (" there", (8, 14), false)
+            ]
+        );
+        assert_eq!(first.normalized, "hello<x> there");
+        assert_eq!(first.alignments.len(), first.normalized.len());
+        assert_eq!(first.alignments[10], (10, 11));
+        assert_eq!(
+            first
+                .pre_tokenized
+                .iter()
+                .map(|split| (split.normalized.as_str(), split.offsets))
+                .collect::<Vec<_>>(),
+            [("hello", (0, 5)), ("<x>", (5, 8)), ("there", (9, 14))]
+        );
+        assert_eq!(
+            first
+                .tokenized
+                .iter()
+                .map(|split| split.tokens.as_ref().unwrap()[0].id)
+                .collect::<Vec<_>>(),
+            [1, 5, 2]
+        );
+
+        assert_eq!(trace.sequences[1].sequence_id, 1);
+        assert_eq!(
+            trace.sequences[1].tokenized[0].tokens.as_ref().unwrap()[0].id,
+            0
+        );
+
+        assert_eq!(
+            trace
+                .insertions
+                .iter()
+                .map(|insertion| (insertion.index, insertion.token.as_str()))
+                .collect::<Vec<_>>(),
+            [(0, "[CLS]"), (4, "[SEP]"), (6, "[SEP]")]
+        );
+    }
+
+    #[test]
+    fn encode_with_trace_pre_tokenized() {
+        let tokenizer = tokenizer();
+        let trace = tokenizer
+            .encode_with_trace(&["Hello", "there"][..], false)
+            .unwrap();
+        assert_eq!(
+            trace
+                .sequences
+                .iter()
+                .map(|sequence| (sequence.word_id, sequence.normalized.as_str()))
+                .collect::<Vec<_>>(),
+            [(Some(0), "hello"), (Some(1), "there")]
+        );
+        assert_eq!(trace.encoding.get_word_ids(), [Some(0), Some(1)]);
+        assert!(trace.insertions.is_empty());
+
+        let serialized = serde_json::to_string(&trace).unwrap();
+        assert_eq!(
+            serde_json::from_str::<EncodeTrace>(&serialized).unwrap(),
+            trace
+        );
+    }
+}
@@ -0,0 +1,531 @@
+use super::{Model, TokenizerImpl};
+use crate::decoders::DecoderWrapper;
+use crate::models::bpe::BPE;
+use crate::models::ModelWrapper;
+use crate::normalizers::NormalizerWrapper;
+use crate::pre_tokenizers::PreTokenizerWrapper;
+use crate::processors::PostProcessorWrapper;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// How much a [`Diagnostic`] matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Something suspicious, that may be intended
+    Warning,
+    /// Something that makes the tokenizer produce wrong results
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The problems [`validate`](TokenizerImpl::validate) looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// Some ids are not used by any token
+    VocabHole,
+    /// Several tokens share the same id
+    DuplicateId,
+    /// An added token has the id of another token of the model
+    ShadowedToken,
+    /// A special token is used with an id that is not its own
+    SpecialTokenMismatch,
+    /// A special token is not part of the vocabulary
+    SpecialTokenMissing,
+    /// Some tokens of the model can never be produced
+    UnreachableTokens,
+    /// The decoder does not revert what the pre-tokenizer does
+    DecoderMismatch,
+}
+
+/// A problem found in a tokenizer by [`validate`](TokenizerImpl::validate)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new<S: Into<String>>(severity: Severity, kind: DiagnosticKind, message: S) -> Self {
+        Self {
+            severity,
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// The first few of the given items, to keep the messages short
+fn examples<T: fmt::Display>(items: &[T]) -> String {
+    const MAX_EXAMPLES: usize = 5;
+    let mut examples = items
+        .iter()
+        .take(MAX_EXAMPLES)
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>();
+    if items.len() > MAX_EXAMPLES {
+        examples.push("...".into());
+    }
+    examples.join(", ")
+}
+
+fn any_pre_tokenizer<F>(pre_tokenizer: &PreTokenizerWrapper, f: &F) -> bool
+where
+    F: Fn(&PreTokenizerWrapper) -> bool,
+{
+    match pre_tokenizer {
+        PreTokenizerWrapper::Sequence(sequence) => sequence
+            .get_pre_tokenizers()
+            .iter()
+            .any(|pre_tokenizer| any_pre_tokenizer(pre_tokenizer, f)),
+        pre_tokenizer => f(pre_tokenizer),
+    }
+}
+
+fn any_decoder<F>(decoder: &DecoderWrapper, f: &F) -> bool
+where
+    F: Fn(&DecoderWrapper) -> bool,
+{
+    match decoder {
+        DecoderWrapper::Sequence(sequence) => sequence
+            .get_decoders()
+            .iter()
+            .any(|decoder| any_decoder(decoder, f)),
+        decoder => f(decoder),
+    }
+}
+
+/// The special tokens used by the given post-processor, with its name
+fn processor_tokens(processor: &PostProcessorWrapper, tokens: &mut Vec<(&str, String, u32)>) {
+    match processor {
+        PostProcessorWrapper::Bert(bert) => {
+            for (token, id) in [bert.get_cls_copy(), bert.get_sep_copy()] {
+                tokens.push(("BertProcessing", token, id));
+            }
+        }
+        PostProcessorWrapper::Roberta(roberta) => {
+            for (token, id) in [roberta.get_cls_copy(), roberta.get_sep_copy()] {
+                tokens.push(("RobertaProcessing", token, id));
+            }
+        }
+        PostProcessorWrapper::Template(template) => {
+            for special in template.get_special_tokens().0.values() {
+                for (token, id) in special.tokens.iter().zip(&special.ids) {
+                    tokens.push(("TemplateProcessing", token.clone(), *id));
+                }
+            }
+        }
+        PostProcessorWrapper::Chat(chat) => {
+            for special in chat.get_special_tokens().0.values() {
+                for (token, id) in special.tokens.iter().zip(&special.ids) {
+                    tokens.push(("ChatProcessing", token.clone(), *id));
+                }
+            }
+        }
+        PostProcessorWrapper::Sequence(sequence) => {
+            for processor in sequence.get_processors() {
+                processor_tokens(processor, tokens);
+            }
+        }
+        PostProcessorWrapper::ByteLevel(_) => {}
+    }
+}
+
+/// The tokens of the given `BPE` that no sequence of merges can produce, starting from
+/// the single characters, the bytes (with `byte_fallback`) and the given `reachable` ids
+fn unreachable_tokens(bpe: &BPE, mut reachable: HashSet<u32>) -> Vec<(u32, &str)> {
+    let is_initial = |token: &str| {
+        let mut symbol = token;
+        if let Some(prefix) = &bpe.continuing_subword_prefix {
+            symbol = symbol.strip_prefix(prefix.as_str()).unwrap_or(symbol);
+        }
+        if let Some(suffix) = &bpe.end_of_word_suffix {
+            symbol = symbol.strip_suffix(suffix.as_str()).unwrap_or(symbol);
+        }
+        symbol.chars().count() == 1
+            || bpe.unk_token.as_deref() == Some(token)
+            || (bpe.byte_fallback
+                && token.len() == 6
+                && token.starts_with("<0x")
+                && token.ends_with('>'))
+    };
+    reachable.extend(
+        bpe.vocab
+            .iter()
+            .filter(|(token, _)| is_initial(token))
+            .map(|(_, id)| *id),
+    );
+
+    // A merge can only happen once both of its parts can be produced
+    let mut merges = bpe
+        .merges
+        .iter()
+        .map(|(pair, (rank, new_id))| (*rank, *pair, *new_id))
+        .collect::<Vec<_>>();
+    merges.sort_unstable();
+    loop {
+        let n_reachable = reachable.len();
+        for (_, (a, b), new_id) in &merges {
+            if reachable.contains(a) && reachable.contains(b) {
+                reachable.insert(*new_id);
+            }
+        }
+        if reachable.len() == n_reachable {
+            break;
+        }
+    }
+
+    let mut unreachable = bpe
+        .vocab
+        .iter()
+        .filter(|(_, id)| !reachable.contains(id))
+        .map(|(token, id)| (*id, token.as_str()))
+        .collect::<Vec<_>>();
+    unreachable.sort_unstable();
+    unreachable
+}
+
+impl
+    TokenizerImpl<
+        ModelWrapper,
+        NormalizerWrapper,
+        PreTokenizerWrapper,
+        PostProcessorWrapper,
+        DecoderWrapper,
+    >
+{
+    /// Check that the components of this tokenizer are consistent with each other, which
+    /// is not verified when loading a tokenizer. The errors come first.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        self.validate_vocab(&mut diagnostics);
+        self.validate_special_tokens(&mut diagnostics);
+        self.validate_model(&mut diagnostics);
+        self.validate_decoder(&mut diagnostics);
+        diagnostics.sort_by_key(|diagnostic| Reverse(diagnostic.severity));
+        diagnostics
+    }
+
+    fn validate_vocab(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let mut tokens_by_id: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        for (token, id) in self.model.get_vocab() {
+            tokens_by_id.entry(id).or_default().push(token);
+        }
+        for (id, tokens) in &mut tokens_by_id {
+            if tokens.len() > 1 {
+                tokens.sort();
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticKind::DuplicateId,
+                    format!("The tokens {} share the id {}", examples(tokens), id),
+                ));
+            }
+        }
+
+        let added_tokens = self
+            .get_added_tokens_decoder()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        for (id, token) in &added_tokens {
+            if let Some(tokens) = tokens_by_id.get(id) {
+                if !tokens.contains(&token.content) {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        DiagnosticKind::ShadowedToken,
+                        format!(
+                            "The added token '{}' has the id {} of the token '{}' of the model",
+                            token.content, id, tokens[0]
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let max_id = tokens_by_id
+            .keys()
+            .chain(added_tokens.keys())
+            .max()
+            .copied();
+        if let Some(max_id) = max_id {
+            let holes = (0..max_id)
+                .filter(|id| !tokens_by_id.contains_key(id) && !added_tokens.contains_key(id))
+                .collect::<Vec<_>>();
+            if !holes.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::VocabHole,
+                    format!(
+                        "{} id(s) are not used by any token: {}",
+                        holes.len(),
+                        examples(&holes)
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn validate_special_tokens(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let mut tokens = vec![];
+        if let Some(processor) = &self.post_processor {
+            processor_tokens(processor, &mut tokens);
+        }
+        if let Some(padding) = &self.padding {
+            tokens.push(("padding", padding.pad_token.clone(), padding.pad_id));
+        }
+        tokens.sort();
+        tokens.dedup();
+
+        for (component, token, id) in tokens {
+            match self.token_to_id(&token) {
+                Some(actual) if actual != id => diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticKind::SpecialTokenMismatch,
+                    format!(
+                        "The {} uses the id {} for '{}', whose id is {}",
+                        component, id, token, actual
+                    ),
+                )),
+                None => diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::SpecialTokenMissing,
+                    format!(
+                        "The {} uses '{}' (with the id {}), which is not part of the vocabulary",
+                        component, token, id
+                    ),
+                )),
+                _ => {}
+            }
+        }
+
+        let unk_token = match &self.model {
+            ModelWrapper::BPE(bpe) => bpe.unk_token.clone(),
+            ModelWrapper::WordPiece(wordpiece) => Some(wordpiece.unk_token.clone()),
+            ModelWrapper::WordLevel(wordlevel) => Some(wordlevel.unk_token.clone()),
+            ModelWrapper::Unigram(_) => None,
+        };
+        if let Some(unk_token) = unk_token {
+            if self.model.token_to_id(&unk_token).is_none() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    DiagnosticKind::SpecialTokenMissing,
+                    format!(
+                        "The unknown token '{}' is not part of the vocabulary of the model",
+                        unk_token
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn validate_model(&self, diagnostics: &mut Vec<Diagnostic>) {
+        if let ModelWrapper::BPE(bpe) = &self.model {
+            let added = self.get_added_tokens_decoder().into_keys().collect();
+            let unreachable = unreachable_tokens(bpe, added)
+                .into_iter()
+                .map(|(_, token)| format!("'{}'", token))
+                .collect::<Vec<_>>();
+            if !unreachable.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::UnreachableTokens,
+                    format!(
+                        "{} token(s) of the model can't be produced by any merge: {}",
+                        unreachable.len(),
+                        examples(&unreachable)
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn validate_decoder(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let has_pre_tokenizer = |f: &dyn Fn(&PreTokenizerWrapper) -> bool| {
+            self.pre_tokenizer
+                .as_ref()
+                .is_some_and(|pre_tokenizer| any_pre_tokenizer(pre_tokenizer, &f))
+        };
+        let has_decoder = |f: &dyn Fn(&DecoderWrapper) -> bool| {
+            self.decoder
+                .as_ref()
+                .is_some_and(|decoder| any_decoder(decoder, &f))
+        };
+        let mut mismatch = |message: &str| {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                DiagnosticKind::DecoderMismatch,
+                message,
+            ))
+        };
+
+        let byte_level_pre_tokenizer =
+            has_pre_tokenizer(&|p| matches!(p, PreTokenizerWrapper::ByteLevel(_)));
+        let byte_level_decoder = has_decoder(&|d| matches!(d, DecoderWrapper::ByteLevel(_)));
+        if byte_level_pre_tokenizer && !byte_level_decoder {
+            mismatch(
+                "The ByteLevel pre-tokenizer needs a ByteLevel decoder to convert the bytes \
+                 back to text",
+            );
+        }
+        if byte_level_decoder && !byte_level_pre_tokenizer {
+            mismatch("The ByteLevel decoder expects the tokens of a ByteLevel pre-tokenizer");
+        }
+
+        if has_pre_tokenizer(&|p| matches!(p, PreTokenizerWrapper::Metaspace(_)))
+            && !has_decoder(&|d| {
+                matches!(d, DecoderWrapper::Metaspace(_) | DecoderWrapper::Replace(_))
+            })
+        {
+            mismatch("The Metaspace pre-tokenizer needs a Metaspace decoder to restore the spaces");
+        }
+
+        if let ModelWrapper::WordPiece(wordpiece) = &self.model {
+            let prefix = &wordpiece.continuing_subword_prefix;
+            if has_decoder(
+                &|d| matches!(d, DecoderWrapper::WordPiece(dec) if &dec.prefix != prefix),
+            ) {
+                mismatch(&format!(
+                    "The WordPiece decoder doesn't use the prefix '{}' of the model",
+                    prefix
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::processors::bert::BertProcessing;
+    use crate::{AddedToken, Tokenizer};
+
+    fn tokenizer(vocab: &[(&str, u32)]) -> Tokenizer {
+        let vocab = vocab.iter().map(|(t, id)| (t.to_string(), *id)).collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer.with_post_processor(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        ));
+        tokenizer
+    }
+
+    fn kinds(tokenizer: &Tokenizer) -> Vec<(Severity, DiagnosticKind)> {
+        tokenizer
+            .validate()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.kind))
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let tokenizer = tokenizer(&[("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2), ("hello", 3)]);
+        assert_eq!(tokenizer.validate(), vec![]);
+    }
+
+    #[test]
+    fn special_tokens() {
+        let swapped = tokenizer(&[("[UNK]", 0), ("[SEP]", 1), ("[CLS]", 2), ("hello", 3)]);
+        let diagnostics = swapped.validate();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "error: The BertProcessing uses the id 1 for '[CLS]', whose id is 2",
+                "error: The BertProcessing uses the id 2 for '[SEP]', whose id is 1",
+            ]
+        );
+
+        let without_unk = tokenizer(&[("<unk>", 0), ("[CLS]", 1), ("[SEP]", 2)]);
+        assert_eq!(
+            kinds(&without_unk),
+            [(Severity::Error, DiagnosticKind::SpecialTokenMissing)]
+        );
+    }
+
+    #[test]
+    fn vocab() {
+        // The added tokens get their ids after the size of the vocab, ignoring its holes
+        let mut tokenizer = tokenizer(&[("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2), ("hi", 5)]);
+        assert_eq!(
+            kinds(&tokenizer),
+            [(Severity::Warning, DiagnosticKind::VocabHole)]
+        );
+        tokenizer.add_tokens(&[AddedToken::from("<x>", false)]);
+        tokenizer.add_tokens(&[AddedToken::from("<y>", false)]);
+        assert_eq!(
+            tokenizer.validate()[0].to_string(),
+            "error: The added token '<y>' has the id 5 of the token 'hi' of the model"
+        );
+    }
+
+    #[test]
+    fn unreachable_bpe_tokens() {
+        let vocab = [
+            ("a", 0),
+            ("b", 1),
+            ("c", 2),
+            ("ab", 3),
+            ("abc", 4),
+            ("bc", 5),
+        ]
+        .iter()
+        .map(|(t, id)| (t.to_string(), *id))
+        .collect();
+        let merges = vec![("a".into(), "b".into()), ("ab".into(), "c".into())];
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap();
+        let tokenizer = Tokenizer::new(bpe);
+        let diagnostics = tokenizer.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "1 token(s) of the model can't be produced by any merge: 'bc'"
+        );
+    }
+
+    #[test]
+    fn decoder_mismatch() {
+        let mut tokenizer = tokenizer(&[("[UNK]", 0), ("[CLS]", 1), ("[SEP]", 2)]);
+        tokenizer.with_pre_tokenizer(ByteLevel::default());
+        tokenizer.with_decoder(WordPieceDecoder::default());
+        assert_eq!(
+            kinds(&tokenizer),
+            [(Severity::Warning, DiagnosticKind::DecoderMismatch)]
+        );
+
+        tokenizer.with_decoder(ByteLevel::default());
+        assert_eq!(tokenizer.validate(), vec![]);
+    }
+}
@@ -0,0 +1,241 @@
+use super::{
+    pad_encodings, Decoder, Encoding, InputSequence, Model, Normalizer, OffsetType, PostProcessor,
+    PreTokenizer, Result, SpecialTokensMatching, TokenizerImpl, TruncationError,
+};
+use crate::utils::parallelism::*;
+
+/// One window of a document encoded with
+/// [`encode_batch_windowed`](TokenizerImpl::encode_batch_windowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingWindow {
+    /// The index of the document this window comes from, in the input batch
+    pub document: usize,
+    /// The index of this window in its document
+    pub window: usize,
+    /// The encoding of this window
+    pub encoding: Encoding,
+}
+
+/// Split `words` (the word ids of an encoding) into windows of at most `max_len` tokens,
+/// each of them starting with the last `stride` tokens of the previous one.
+///
+/// Windows only end (and start) between two words, unless a single word is too long to fit
+/// in a window, in which case it gets split.
+fn window_ranges(words: &[Option<u32>], max_len: usize, stride: usize) -> Vec<(usize, usize)> {
+    // Whether a window can start or end right before the token at `i`
+    let is_boundary =
+        |i: usize| i == 0 || i == words.len() || words[i].is_none() || words[i] != words[i - 1];
+
+    let mut ranges = vec![];
+    let mut start = 0;
+    loop {
+        let mut stop = std::cmp::min(start + max_len, words.len());
+        if let Some(boundary) = (start + 1..=stop).rev().find(|i| is_boundary(*i)) {
+            stop = boundary;
+        }
+        ranges.push((start, stop));
+        if stop == words.len() {
+            break;
+        }
+
+        // The next window starts with (at most) `stride` tokens of overlap, made of whole
+        // words when possible, and always makes some progress.
+        let min_start = std::cmp::max(stop.saturating_sub(stride), start + 1);
+        start = (min_start..stop).find(|i| is_boundary(*i)).unwrap_or(stop);
+    }
+    ranges
+}
+
+impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
+where
+    M: Model + Send + Sync,
+    N: Normalizer + Send + Sync,
+    PT: PreTokenizer + Send + Sync,
+    PP: PostProcessor + Send + Sync,
+    D: Decoder + Send + Sync,
+{
+    /// Encode all the documents in parallel, splitting each of them into windows of at most
+    /// `max_length` tokens (special tokens included), with `stride` tokens of overlap between
+    /// two consecutive windows.
+    ///
+    /// Windows are cut between two words (as given by
+    /// [`Encoding::get_word_ids`]) so a word is never split across two windows, unless it
+    /// doesn't fit in a single one. All the windows are returned in a flat list, tagged with
+    /// their document and window index.
+    ///
+    /// Each window gets post-processed, and padded like with
+    /// [`encode_batch`](Self::encode_batch).
+    pub fn encode_batch_windowed<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        max_length: usize,
+        stride: usize,
+        add_special_tokens: bool,
+    ) -> Result<Vec<EncodingWindow>>
+    where
+        E: Into<InputSequence<'s>> + Send,
+    {
+        let n_added_tokens = if add_special_tokens {
+            self.get_n_added_tokens(false)
+        } else {
+            0
+        };
+        let budget = max_length.saturating_sub(n_added_tokens);
+        if budget == 0 {
+            return Err(TruncationError::InvalidParams(format!(
+                "`max_length` ({}) must be greater than the number of added special tokens ({})",
+                max_length, n_added_tokens
+            ))
+            .into());
+        }
+        if stride >= budget {
+            return Err(TruncationError::InvalidParams(format!(
+                "`stride` ({}) must be strictly less than the number of tokens per window ({})",
+                stride, budget
+            ))
+            .into());
+        }
+
+        let documents = inputs.into_iter().enumerate().collect::<Vec<_>>();
+        let windows = documents
+            .into_maybe_par_iter()
+            .map(|(document, input)| {
+                let encoding = self.encode_single_sequence(
+                    input.into(),
+                    0,
+                    OffsetType::Byte,
+                    &SpecialTokensMatching::All,
+                )?;
+                window_ranges(encoding.get_word_ids(), budget, stride)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(window, (start, stop))| {
+                        Ok(EncodingWindow {
+                            document,
+                            window,
+                            encoding: self.post_process(
+                                encoding.slice(start, stop),
+                                None,
+                                add_special_tokens,
+                            )?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut windows = windows.into_iter().flatten().collect::<Vec<_>>();
+
+        if let Some(params) = &self.padding {
+            // We do the padding here to make sure we handle the batch padding
+            let mut encodings = windows
+                .iter_mut()
+                .map(|w| std::mem::take(&mut w.encoding))
+                .collect::<Vec<_>>();
+            pad_encodings(&mut encodings, params)?;
+            for (window, encoding) in windows.iter_mut().zip(encodings) {
+                window.encoding = encoding;
+            }
+        }
+
+        Ok(windows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::window_ranges;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::processors::bert::BertProcessing;
+    use crate::Tokenizer;
+    use std::collections::HashMap;
+
+    fn tokenizer() -> Tokenizer {
+        let vocab: HashMap<String, u32> = ["[UNK]", "[SEP]", "[CLS]", "a", "b", "c", "d", "e"]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), i as u32))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer
+    }
+
+    #[test]
+    fn ranges() {
+        let words = [
+            Some(0),
+            Some(1),
+            Some(1),
+            Some(2),
+            Some(3),
+            Some(3),
+            Some(3),
+        ];
+        assert_eq!(window_ranges(&words, 3, 0), vec![(0, 3), (3, 4), (4, 7)]);
+        assert_eq!(window_ranges(&words, 4, 1), vec![(0, 4), (3, 7)]);
+        assert_eq!(window_ranges(&words, 7, 2), vec![(0, 7)]);
+        // A word too long for a window gets split
+        assert_eq!(
+            window_ranges(&words, 2, 0),
+            vec![(0, 1), (1, 3), (3, 4), (4, 6), (6, 7)]
+        );
+        // The overlap only contains whole words
+        assert_eq!(window_ranges(&words, 5, 2), vec![(0, 4), (3, 7)]);
+        assert_eq!(window_ranges(&[], 3, 1), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn encode_batch_windowed() {
+        let tokenizer = tokenizer();
+        let windows = tokenizer
+            .encode_batch_windowed(vec!["a b c d e", "a b"], 2, 1, false)
+            .unwrap();
+        let tags = windows
+            .iter()
+            .map(|w| (w.document, w.window, w.encoding.get_tokens().join(" ")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            vec![
+                (0, 0, "a b".to_string()),
+                (0, 1, "b c".to_string()),
+                (0, 2, "c d".to_string()),
+                (0, 3, "d e".to_string()),
+                (1, 0, "a b".to_string()),
+            ]
+        );
+        // Offsets and word ids stay relative to the whole document
+        assert_eq!(windows[3].encoding.get_offsets(), &[(6, 7), (8, 9)]);
+        assert_eq!(windows[3].encoding.get_word_ids(), &[Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn encode_batch_windowed_special_tokens() {
+        let mut tokenizer = tokenizer();
+        tokenizer.with_post_processor(BertProcessing::new(
+            ("[SEP]".into(), 1),
+            ("[CLS]".into(), 2),
+        ));
+        let windows = tokenizer
+            .encode_batch_windowed(vec!["a b c"], 4, 0, true)
+            .unwrap();
+        let tokens = windows
+            .iter()
+            .map(|w| w.encoding.get_tokens().join(" "))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec!["[CLS] a b [SEP]", "[CLS] c [SEP]"]);
+
+        assert!(tokenizer
+            .encode_batch_windowed(vec!["a b c"], 2, 0, true)
+            .is_err());
+        assert!(tokenizer
+            .encode_batch_windowed(vec!["a b c"], 4, 2, true)
+            .is_err());
+    }
+}
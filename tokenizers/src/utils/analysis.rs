@@ -0,0 +1,556 @@
+//! Statistics about how tokenizers split a corpus.
+//!
+//! They help picking a vocabulary size, or choosing between several tokenizers. An
+//! [`Analyzer`] accumulates the statistics of one tokenizer over the chunks of a corpus, and
+//! [`compare`] puts the ones of several tokenizers side by side.
+use crate::pre_tokenizers::unicode_scripts::{get_script, Script};
+use crate::tokenizer::{Encoding, Offsets, Result};
+use crate::Tokenizer;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// Configures what gets computed by an [`Analyzer`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisConfig {
+    /// Whether to break the statistics down by the Unicode script of the words
+    pub by_script: bool,
+    /// The maximum number of tokens listed as never used, and as the longest ones
+    pub max_tokens: usize,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            by_script: false,
+            max_tokens: 20,
+        }
+    }
+}
+
+/// What was seen in some part of the corpus
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Counts {
+    words: usize,
+    chars: usize,
+    bytes: usize,
+    tokens: usize,
+    unknown_tokens: usize,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.tokens += other.tokens;
+        self.unknown_tokens += other.unknown_tokens;
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// How many tokens some part of the corpus got split into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fertility {
+    /// The number of whitespace delimited words
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+    pub tokens: usize,
+    /// The number of tokens that are the unknown token of the model
+    pub unknown_tokens: usize,
+    pub tokens_per_word: f64,
+    pub tokens_per_char: f64,
+    pub bytes_per_token: f64,
+    /// The proportion of the tokens that are the unknown token
+    pub unknown_rate: f64,
+}
+
+impl From<Counts> for Fertility {
+    fn from(counts: Counts) -> Self {
+        Self {
+            words: counts.words,
+            chars: counts.chars,
+            bytes: counts.bytes,
+            tokens: counts.tokens,
+            unknown_tokens: counts.unknown_tokens,
+            tokens_per_word: ratio(counts.tokens, counts.words),
+            tokens_per_char: ratio(counts.tokens, counts.chars),
+            bytes_per_token: ratio(counts.bytes, counts.tokens),
+            unknown_rate: ratio(counts.unknown_tokens, counts.tokens),
+        }
+    }
+}
+
+/// The number of tokens of the vocabulary used between `min` and `max` times
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageBucket {
+    pub min: u64,
+    pub max: u64,
+    pub tokens: usize,
+}
+
+/// The statistics of a tokenizer over a corpus
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenizerStats {
+    pub name: String,
+    /// The number of texts in the corpus
+    pub sequences: usize,
+    pub overall: Fertility,
+    /// The statistics of the words of each Unicode script, when broken down by script
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scripts: BTreeMap<String, Fertility>,
+    /// The size of the vocabulary, with the added tokens
+    pub vocab_size: usize,
+    /// The number of tokens of the vocabulary used at least once
+    pub used_tokens: usize,
+    /// The number of tokens never used, apart from the special tokens
+    pub never_used_count: usize,
+    /// The first tokens never used, by id
+    pub never_used: Vec<String>,
+    /// The number of tokens of the vocabulary used 0 times, once, 2-3 times, 4-7 times, ...
+    pub usage_histogram: Vec<UsageBucket>,
+    /// The longest tokens of the vocabulary, as stored in it
+    pub longest_tokens: Vec<String>,
+}
+
+/// The whitespace delimited words of `text`
+fn words(text: &str) -> Vec<Offsets> {
+    let mut words = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (start, c.is_whitespace()) {
+            (Some(s), true) => {
+                words.push((s, i));
+                start = None;
+            }
+            (None, false) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, text.len()));
+    }
+    words
+}
+
+/// The script of the first char of `word` that is specific to a script, or `Common`
+fn word_script(word: &str) -> String {
+    let script = word
+        .chars()
+        .map(get_script)
+        .find(|script| !matches!(script, Script::Any | Script::Common | Script::Inherited))
+        .unwrap_or(Script::Common);
+    format!("{:?}", script)
+}
+
+/// Accumulates the statistics of a tokenizer over the chunks of a corpus
+pub struct Analyzer<'a> {
+    name: String,
+    tokenizer: Cow<'a, Tokenizer>,
+    config: AnalysisConfig,
+    unk_id: Option<u32>,
+    sequences: usize,
+    overall: Counts,
+    scripts: BTreeMap<String, Counts>,
+    usage: HashMap<u32, u64>,
+}
+
+impl<'a> Analyzer<'a> {
+    /// Analyze the given tokenizer. Its truncation and padding are disabled, so that the whole
+    /// texts get counted.
+    pub fn new<S: Into<String>>(name: S, tokenizer: &'a Tokenizer, config: AnalysisConfig) -> Self {
+        let tokenizer = if tokenizer.get_truncation().is_some() || tokenizer.get_padding().is_some()
+        {
+            let mut tokenizer = tokenizer.clone();
+            tokenizer.with_padding(None);
+            tokenizer
+                .with_truncation(None)
+                .expect("Failed to set truncation to `None`! This should never happen");
+            Cow::Owned(tokenizer)
+        } else {
+            Cow::Borrowed(tokenizer)
+        };
+        Self {
+            name: name.into(),
+            unk_id: tokenizer.get_model().get_unk_id(),
+            tokenizer,
+            config,
+            sequences: 0,
+            overall: Counts::default(),
+            scripts: BTreeMap::new(),
+            usage: HashMap::new(),
+        }
+    }
+
+    /// Encode the texts with `encode_batch` and add them to the statistics. The special
+    /// tokens of the post-processor are not added.
+    pub fn update<S: AsRef<str> + Sync>(&mut self, texts: &[S]) -> Result<()> {
+        let texts = texts.iter().map(|text| text.as_ref()).collect::<Vec<_>>();
+        let encodings = self.tokenizer.encode_batch(texts.clone(), false)?;
+        for (text, encoding) in texts.into_iter().zip(&encodings) {
+            self.add(text, encoding);
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, text: &str, encoding: &Encoding) {
+        let unk_id = self.unk_id;
+        let tokens = encoding
+            .get_ids()
+            .iter()
+            .zip(encoding.get_offsets())
+            .map(|(id, offsets)| (*id, *offsets))
+            .collect::<Vec<_>>();
+        for (id, _) in &tokens {
+            *self.usage.entry(*id).or_default() += 1;
+        }
+
+        let words = words(text);
+        self.sequences += 1;
+        self.overall.add(&Counts {
+            words: words.len(),
+            chars: text.chars().count(),
+            bytes: text.len(),
+            tokens: tokens.len(),
+            unknown_tokens: tokens.iter().filter(|(id, _)| Some(*id) == unk_id).count(),
+        });
+        if !self.config.by_script || words.is_empty() {
+            return;
+        }
+
+        let mut word_counts = words
+            .iter()
+            .map(|(start, end)| Counts {
+                words: 1,
+                chars: text[*start..*end].chars().count(),
+                bytes: end - start,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        // Each token belongs to the word it starts in, or to the next one when it starts with
+        // some whitespace
+        for (id, (start, _)) in tokens {
+            let word = words
+                .partition_point(|(_, end)| *end <= start)
+                .min(words.len() - 1);
+            word_counts[word].tokens += 1;
+            if Some(id) == unk_id {
+                word_counts[word].unknown_tokens += 1;
+            }
+        }
+        for ((start, end), counts) in words.into_iter().zip(word_counts) {
+            self.scripts
+                .entry(word_script(&text[start..end]))
+                .or_default()
+                .add(&counts);
+        }
+    }
+
+    /// Compute the statistics of everything seen so far
+    pub fn finish(self) -> TokenizerStats {
+        let vocab = self.tokenizer.get_vocab(true);
+        let special = self
+            .tokenizer
+            .get_added_tokens_decoder()
+            .into_iter()
+            .filter(|(_, token)| token.special)
+            .map(|(id, _)| id)
+            .collect::<HashSet<_>>();
+
+        let mut never_used = vocab
+            .iter()
+            .filter(|(_, id)| !self.usage.contains_key(id) && !special.contains(id))
+            .map(|(token, id)| (*id, token))
+            .collect::<Vec<_>>();
+        never_used.sort_unstable();
+
+        let mut histogram = vec![];
+        for id in vocab.values() {
+            let count = self.usage.get(id).copied().unwrap_or(0);
+            let bucket = (u64::BITS - count.leading_zeros()) as usize;
+            if histogram.len() <= bucket {
+                histogram.resize(bucket + 1, 0);
+            }
+            histogram[bucket] += 1;
+        }
+        let usage_histogram = histogram
+            .into_iter()
+            .enumerate()
+            .map(|(i, tokens)| match i {
+                0 => UsageBucket {
+                    min: 0,
+                    max: 0,
+                    tokens,
+                },
+                i => UsageBucket {
+                    min: 1 << (i - 1),
+                    max: u64::MAX >> (u64::BITS as usize - i),
+                    tokens,
+                },
+            })
+            .collect();
+
+        let mut longest_tokens = vocab.keys().collect::<Vec<_>>();
+        longest_tokens.sort_unstable_by_key(|token| (Reverse(token.chars().count()), *token));
+
+        TokenizerStats {
+            name: self.name,
+            sequences: self.sequences,
+            overall: self.overall.into(),
+            scripts: self
+                .scripts
+                .into_iter()
+                .map(|(script, counts)| (script, counts.into()))
+                .collect(),
+            vocab_size: vocab.len(),
+            used_tokens: self.usage.len(),
+            never_used_count: never_used.len(),
+            never_used: never_used
+                .into_iter()
+                .take(self.config.max_tokens)
+                .map(|(_, token)| token.clone())
+                .collect(),
+            usage_histogram,
+            longest_tokens: longest_tokens
+                .into_iter()
+                .take(self.config.max_tokens)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// The statistics of several tokenizers over the same corpus. Its `Display` shows them side
+/// by side.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Comparison {
+    pub tokenizers: Vec<TokenizerStats>,
+}
+
+/// Compute the statistics of each of the named tokenizers over the corpus
+pub fn compare<S: AsRef<str> + Sync>(
+    tokenizers: &[(&str, &Tokenizer)],
+    corpus: &[S],
+    config: &AnalysisConfig,
+) -> Result<Comparison> {
+    let tokenizers = tokenizers
+        .iter()
+        .map(|(name, tokenizer)| {
+            let mut analyzer = Analyzer::new(*name, tokenizer, config.clone());
+            analyzer.update(corpus)?;
+            Ok(analyzer.finish())
+        })
+        .collect::<Result<_>>()?;
+    Ok(Comparison { tokenizers })
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rows: Vec<(String, Vec<String>)> = vec![(
+            String::new(),
+            self.tokenizers.iter().map(|t| t.name.clone()).collect(),
+        )];
+        let mut row = |label: &str, value: &dyn Fn(&TokenizerStats) -> String| {
+            rows.push((
+                label.to_owned(),
+                self.tokenizers.iter().map(value).collect(),
+            ));
+        };
+        row("sequences", &|t| t.sequences.to_string());
+        row("words", &|t| t.overall.words.to_string());
+        row("chars", &|t| t.overall.chars.to_string());
+        row("tokens", &|t| t.overall.tokens.to_string());
+        row("tokens/word", &|t| {
+            format!("{:.3}", t.overall.tokens_per_word)
+        });
+        row("tokens/char", &|t| {
+            format!("{:.3}", t.overall.tokens_per_char)
+        });
+        row("bytes/token", &|t| {
+            format!("{:.3}", t.overall.bytes_per_token)
+        });
+        row("unknown rate", &|t| {
+            format!("{:.4}", t.overall.unknown_rate)
+        });
+        row("vocab size", &|t| t.vocab_size.to_string());
+        row("used tokens", &|t| t.used_tokens.to_string());
+        row("never used", &|t| t.never_used_count.to_string());
+
+        let scripts = self
+            .tokenizers
+            .iter()
+            .flat_map(|t| t.scripts.keys())
+            .collect::<BTreeSet<_>>();
+        for script in scripts {
+            let stat = |value: fn(&Fertility) -> String| {
+                move |t: &TokenizerStats| t.scripts.get(script).map_or("-".into(), value)
+            };
+            row(
+                &format!("tokens/word ({})", script),
+                &stat(|s| format!("{:.3}", s.tokens_per_word)),
+            );
+            row(
+                &format!("unknown rate ({})", script),
+                &stat(|s| format!("{:.4}", s.unknown_rate)),
+            );
+        }
+
+        let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let widths = (0..self.tokenizers.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|(_, values)| values[i].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        for (label, values) in &rows {
+            write!(f, "{:<width$}", label, width = label_width)?;
+            for (value, width) in values.iter().zip(&widths) {
+                write!(f, "  {:>width$}", value, width = width)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::{AddedToken, PaddingParams, PaddingStrategy, TruncationParams};
+
+    fn tokenizer(words: &[&str]) -> Tokenizer {
+        let vocab = ["[UNK]"]
+            .iter()
+            .chain(words)
+            .enumerate()
+            .map(|(id, token)| (token.to_string(), id as u32))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer
+    }
+
+    const CORPUS: [&str; 3] = ["hello there", "hello stranger", "привет hello"];
+
+    #[test]
+    fn analyzer() {
+        let mut tokenizer = tokenizer(&["hello", "there", "you"]);
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+        let mut analyzer = Analyzer::new(
+            "wordlevel",
+            &tokenizer,
+            AnalysisConfig {
+                by_script: true,
+                max_tokens: 2,
+            },
+        );
+        analyzer.update(&CORPUS[..2]).unwrap();
+        analyzer.update(&CORPUS[2..]).unwrap();
+        let stats = analyzer.finish();
+
+        assert_eq!(stats.sequences, 3);
+        assert_eq!(
+            (
+                stats.overall.words,
+                stats.overall.chars,
+                stats.overall.bytes,
+                stats.overall.tokens,
+                stats.overall.unknown_tokens
+            ),
+            (6, 37, 43, 6, 2)
+        );
+        assert_eq!(stats.overall.tokens_per_word, 1.0);
+        assert_eq!(stats.overall.unknown_rate, 2.0 / 6.0);
+
+        assert_eq!(
+            stats.scripts.keys().collect::<Vec<_>>(),
+            ["Cyrillic", "Latin"]
+        );
+        assert_eq!(stats.scripts["Cyrillic"].unknown_rate, 1.0);
+        assert_eq!(stats.scripts["Latin"].tokens, 5);
+        assert_eq!(stats.scripts["Latin"].unknown_tokens, 1);
+
+        // The special tokens are never expected in the corpus
+        assert_eq!(stats.vocab_size, 5);
+        assert_eq!(stats.used_tokens, 3);
+        assert_eq!(stats.never_used_count, 1);
+        assert_eq!(stats.never_used, ["you"]);
+        assert_eq!(
+            stats
+                .usage_histogram
+                .iter()
+                .map(|bucket| (bucket.min, bucket.max, bucket.tokens))
+                .collect::<Vec<_>>(),
+            [(0, 0, 2), (1, 1, 1), (2, 3, 2)]
+        );
+        assert_eq!(stats.longest_tokens, ["[CLS]", "[UNK]"]);
+
+        // The whole texts are counted, whatever the truncation and padding of the tokenizer
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 1,
+                ..Default::default()
+            }))
+            .unwrap()
+            .with_padding(Some(PaddingParams {
+                strategy: PaddingStrategy::Fixed(4),
+                ..Default::default()
+            }));
+        let mut analyzer = Analyzer::new("truncated", &tokenizer, AnalysisConfig::default());
+        analyzer.update(&CORPUS).unwrap();
+        let truncated = analyzer.finish();
+        assert_eq!(truncated.overall, stats.overall);
+    }
+
+    #[test]
+    fn compare_tokenizers() {
+        let small = tokenizer(&["hello", "there"]);
+        let large = tokenizer(&["hello", "there", "stranger", "привет"]);
+        let comparison = compare(
+            &[("small", &small), ("large", &large)],
+            &CORPUS,
+            &AnalysisConfig {
+                by_script: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(comparison.tokenizers[0].overall.unknown_tokens, 2);
+        assert_eq!(comparison.tokenizers[1].overall.unknown_tokens, 0);
+
+        let table = comparison.to_string();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["small", "large"]
+        );
+        assert!(lines.contains(&"unknown rate             0.3333  0.0000"));
+        assert!(lines.contains(&"unknown rate (Cyrillic)  1.0000  0.0000"));
+
+        let serialized = serde_json::to_string(&comparison).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Comparison>(&serialized).unwrap(),
+            comparison
+        );
+    }
+}
@@ -1,23 +1,200 @@
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::RwLock;
 
 /// The default capacity for a `BPE`'s internal cache.
 pub static DEFAULT_CACHE_CAPACITY: usize = 10_000;
 
+/// The maximum number of shards used by a `Cache`. Each shard has its own lock, which
+/// reduces the contention when tokenizing in parallel.
+static MAX_CACHE_SHARDS: usize = 16;
+
+/// The policy used by a cache to choose which entry to evict when it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CachePolicy {
+    /// Evict the least recently used entry.
+    #[default]
+    Lru,
+    /// Approximate LRU using the CLOCK algorithm. Reading an entry only needs to set its
+    /// reference bit, which makes hits cheaper than with `Lru`.
+    Clock,
+}
+
+/// A snapshot of the statistics of a cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct CacheStats {
+    /// The number of lookups that found their entry
+    pub hits: u64,
+    /// The number of lookups that didn't find their entry
+    pub misses: u64,
+    /// The number of entries evicted to make room for new ones
+    pub evictions: u64,
+}
+
+/// No entry, used to terminate the linked list of the LRU
+const NIL: usize = usize::MAX;
+
+#[derive(Debug)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    /// LRU: the previous (more recently used) entry
+    prev: usize,
+    /// LRU: the next (less recently used) entry
+    next: usize,
+    /// CLOCK: whether the entry has been used since the hand last passed it
+    referenced: AtomicBool,
+}
+
+/// A single shard of the cache. Entries are stored in a `Vec` and linked together by their
+/// index, which avoids any allocation once the shard is full.
+#[derive(Debug)]
+struct Shard<K, V> {
+    map: HashMap<K, usize>,
+    entries: Vec<Entry<K, V>>,
+    capacity: usize,
+    /// LRU: the most recently used entry
+    head: usize,
+    /// LRU: the least recently used entry
+    tail: usize,
+    /// CLOCK: the next entry to consider for eviction
+    hand: usize,
+}
+
+impl<K, V> Shard<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    fn new(capacity: usize) -> Self {
+        Self {
+            map: HashMap::with_capacity(capacity),
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            head: NIL,
+            tail: NIL,
+            hand: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.map.clear();
+        self.entries.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.hand = 0;
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = (self.entries[index].prev, self.entries[index].next);
+        match prev {
+            NIL => self.head = next,
+            prev => self.entries[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.entries[next].prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        self.entries[index].prev = NIL;
+        self.entries[index].next = self.head;
+        match self.head {
+            NIL => self.tail = index,
+            head => self.entries[head].prev = index,
+        }
+        self.head = index;
+    }
+
+    /// Mark the entry at the given index as the most recently used one
+    fn promote(&mut self, index: usize) {
+        if self.head != index {
+            self.unlink(index);
+            self.push_front(index);
+        }
+    }
+
+    /// Find the index of the entry to replace, according to the policy
+    fn victim(&mut self, policy: CachePolicy) -> usize {
+        match policy {
+            CachePolicy::Lru => self.tail,
+            CachePolicy::Clock => loop {
+                let index = self.hand;
+                self.hand = (self.hand + 1) % self.entries.len();
+                if !self.entries[index]
+                    .referenced
+                    .swap(false, Ordering::Relaxed)
+                {
+                    break index;
+                }
+            },
+        }
+    }
+
+    /// Insert the given entry, and return whether another one had to be evicted
+    fn insert(&mut self, key: K, value: V, policy: CachePolicy) -> bool {
+        if let Some(&index) = self.map.get(&key) {
+            self.entries[index].value = value;
+            if policy == CachePolicy::Lru {
+                self.promote(index);
+            }
+            return false;
+        }
+        if self.capacity == 0 {
+            return false;
+        }
+
+        let entry = Entry {
+            key: key.clone(),
+            value,
+            prev: NIL,
+            next: NIL,
+            referenced: AtomicBool::new(false),
+        };
+        let (index, evicted) = if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+            (self.entries.len() - 1, false)
+        } else {
+            let index = self.victim(policy);
+            if policy == CachePolicy::Lru {
+                self.unlink(index);
+            }
+            let old = std::mem::replace(&mut self.entries[index], entry);
+            self.map.remove(&old.key);
+            (index, true)
+        };
+        if policy == CachePolicy::Lru {
+            self.push_front(index);
+        }
+        self.map.insert(key, index);
+        evicted
+    }
+}
+
 /// Provides a simple multithread cache to speed up BPE tokenization that will try to read values
 /// concurrently but won't block if another thread is writing.
 /// The goal is clearly not the accuracy of the content, both get and set
 /// are not guaranteed to actually get or set.
+///
+/// The cache is split in multiple shards, each with its own lock, and once full it evicts
+/// entries according to its `CachePolicy`.
 #[derive(Debug)]
 pub(crate) struct Cache<K, V>
 where
     K: Eq + Hash + Clone,
     V: Clone,
 {
-    map: RwLock<HashMap<K, V>>,
+    shards: Vec<RwLock<Shard<K, V>>>,
+    hasher: RandomState,
     pub capacity: usize,
+    pub policy: CachePolicy,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
 }
 
 // We dont really care about Cache comparison, so let's make them always equal
@@ -48,18 +225,56 @@ where
 {
     /// Create new `Cache` with the given capacity.
     pub(crate) fn new(capacity: usize) -> Self {
-        let map = RwLock::new(HashMap::with_capacity(capacity));
-        Cache { map, capacity }
+        Self::with_policy(capacity, CachePolicy::default())
+    }
+
+    /// Create new `Cache` with the given capacity and eviction policy.
+    pub(crate) fn with_policy(capacity: usize, policy: CachePolicy) -> Self {
+        let n_shards = MAX_CACHE_SHARDS.min(capacity).max(1);
+        let shards = (0..n_shards)
+            .map(|i| {
+                // Spread the capacity as evenly as possible
+                let shard_capacity = capacity / n_shards + usize::from(i < capacity % n_shards);
+                RwLock::new(Shard::new(shard_capacity))
+            })
+            .collect();
+        Cache {
+            shards,
+            hasher: RandomState::new(),
+            capacity,
+            policy,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
     }
 
     /// Create a fresh `Cache` with the same configuration.
     pub(crate) fn fresh(&self) -> Self {
-        Self::new(self.capacity)
+        Self::with_policy(self.capacity, self.policy)
     }
 
     /// Clear the cache.
     pub(crate) fn clear(&self) {
-        self.map.write().unwrap().clear();
+        for shard in &self.shards {
+            shard.write().unwrap().clear();
+        }
+    }
+
+    /// Get the statistics of the cache since its creation.
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn shard<Q>(&self, key: &Q) -> &RwLock<Shard<K, V>>
+    where
+        Q: Hash + ?Sized,
+    {
+        &self.shards[self.hasher.hash_one(key) as usize % self.shards.len()]
     }
 
     #[allow(dead_code)]
@@ -69,11 +284,7 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized + 'a,
     {
-        if let Ok(ref mut cache) = self.map.try_read() {
-            Some(keys_iter.map(|k| cache.get(k).cloned()).collect())
-        } else {
-            None
-        }
+        Some(keys_iter.map(|k| self.get(k)).collect())
     }
 
     pub(crate) fn get<Q>(&self, key: &Q) -> Option<V>
@@ -81,34 +292,52 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        if let Ok(ref mut cache) = self.map.try_read() {
-            cache.get(key).cloned()
+        let shard = self.shard(key);
+        let (index, value) = if let Ok(shard) = shard.try_read() {
+            match shard.map.get(key) {
+                Some(&index) => {
+                    let entry = &shard.entries[index];
+                    entry.referenced.store(true, Ordering::Relaxed);
+                    (index, Some(entry.value.clone()))
+                }
+                None => (NIL, None),
+            }
+        } else {
+            (NIL, None)
+        };
+
+        if value.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            // Promoting the entry needs a write handle, but we don't want to block
+            // just for this, so it is only done if nobody else holds the lock.
+            if self.policy == CachePolicy::Lru {
+                if let Ok(mut shard) = shard.try_write() {
+                    if index < shard.entries.len() && shard.entries[index].key.borrow() == key {
+                        shard.promote(index);
+                    }
+                }
+            }
         } else {
-            None
+            self.misses.fetch_add(1, Ordering::Relaxed);
         }
+        value
     }
 
     pub(crate) fn set_values<I>(&self, entries: I)
     where
         I: IntoIterator<Item = (K, V)>,
     {
-        // Before trying to acquire a write lock, we check if we are already at
-        // capacity with a read handler.
-        if let Ok(cache) = self.map.try_read() {
-            if cache.len() >= self.capacity {
-                // At capacity, so do nothing.
-                return;
-            }
-        } else {
-            // If we couldn't acquire a read handle then we probably won't be able to acquire
-            // a write handle one quadrillionth of a second later.
+        if self.capacity == 0 {
             return;
         }
-
-        // Not at capacity, so try acquiring a write handle.
-        if let Ok(mut cache) = self.map.try_write() {
-            let free = self.capacity - cache.len();
-            cache.extend(entries.into_iter().take(free));
+        for (key, value) in entries {
+            // If we couldn't acquire a write handle, someone else is using this shard and
+            // we'd rather skip caching this entry than wait.
+            if let Ok(mut shard) = self.shard(&key).try_write() {
+                if shard.insert(key, value, self.policy) {
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
         }
     }
 
@@ -116,3 +345,81 @@ where
         self.set_values(std::iter::once((key, value)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let cache: Cache<String, u32> = Cache::with_policy(2, CachePolicy::Lru);
+        // A capacity of 2 means we have 2 shards of 1 entry each, use a single shard instead
+        let cache = Cache {
+            shards: vec![RwLock::new(Shard::new(2))],
+            ..cache
+        };
+        cache.set("a".into(), 1);
+        cache.set("b".into(), 2);
+        assert_eq!(cache.get("a"), Some(1));
+        cache.set("c".into(), 3);
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 3,
+                misses: 1,
+                evictions: 1
+            }
+        );
+    }
+
+    #[test]
+    fn clock_evicts_unreferenced() {
+        let cache: Cache<String, u32> = Cache::with_policy(3, CachePolicy::Clock);
+        let cache = Cache {
+            shards: vec![RwLock::new(Shard::new(3))],
+            ..cache
+        };
+        cache.set("a".into(), 1);
+        cache.set("b".into(), 2);
+        cache.set("c".into(), 3);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+        cache.set("d".into(), 4);
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+        assert_eq!(cache.get("d"), Some(4));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn keeps_inserting_when_full() {
+        for &policy in &[CachePolicy::Lru, CachePolicy::Clock] {
+            let cache: Cache<u32, u32> = Cache::with_policy(100, policy);
+            for i in 0..1_000 {
+                cache.set(i, i);
+            }
+            let len: usize = cache
+                .shards
+                .iter()
+                .map(|s| s.read().unwrap().map.len())
+                .sum();
+            assert_eq!(len, 100);
+            // The last inserted entry must always be available
+            assert_eq!(cache.get(&999), Some(999));
+            assert_eq!(cache.stats().evictions, 900);
+        }
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let cache: Cache<u32, u32> = Cache::new(0);
+        cache.set(1, 1);
+        assert_eq!(cache.get(&1), None);
+    }
+}
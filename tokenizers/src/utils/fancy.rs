@@ -1,5 +1,4 @@
 use fancy_regex::Regex;
-use std::error::Error;
 
 #[derive(Debug)]
 pub struct SysRegex {
@@ -11,7 +10,7 @@ impl SysRegex {
         Matches(self.regex.find_iter(inside))
     }
 
-    pub fn new(regex_str: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+    pub fn new(regex_str: &str) -> crate::Result<Self> {
         Ok(Self {
             regex: Regex::new(regex_str)?,
         })
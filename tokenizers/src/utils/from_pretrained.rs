@@ -1,7 +1,48 @@
-use crate::Result;
-use hf_hub::{api::sync::ApiBuilder, Repo, RepoType};
+use crate::models::bpe::BPE;
+use crate::models::wordpiece::WordPiece;
+use crate::normalizers::bert::BertNormalizer;
+use crate::pre_tokenizers::bert::BertPreTokenizer;
+use crate::pre_tokenizers::byte_level::ByteLevel;
+use crate::processors::bert::BertProcessing;
+use crate::{AddedToken, Model, Result, SpecialTokenRole, Tokenizer};
+use hf_hub::{Cache, Repo, RepoType};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// The errors of `from_pretrained`, when the files of a pretrained tokenizer can't be found
+#[derive(thiserror::Error, Debug)]
+pub enum PretrainedError {
+    #[error(
+        "Model \"{0}\" contains invalid characters, expected only alphanumeric or '-', '_', \
+         '.', '/'"
+    )]
+    InvalidIdentifier(String),
+    #[error(
+        "Revision \"{0}\" contains invalid characters, expected only alphanumeric or '-', \
+         '_', '.', '/'"
+    )]
+    InvalidRevision(String),
+    /// A file is missing from the local directory of the tokenizer
+    #[error("File {} not found", .0.display())]
+    FileNotFound(PathBuf),
+    /// A file is missing from the cache, and can't be downloaded in offline mode
+    #[error(
+        "File {filename} of {repo} not found in the cache at {}, and offline mode is enabled",
+        .cache.display()
+    )]
+    NotCached {
+        filename: String,
+        repo: String,
+        cache: PathBuf,
+    },
+    /// Only the files of a legacy tokenizer were found
+    #[error("No tokenizer.json file found")]
+    NoTokenizerFile,
+    #[error("Invalid path {}", .0.display())]
+    InvalidPath(PathBuf),
+}
 
 /// Defines the aditional parameters available for the `from_pretrained` function
 #[derive(Debug, Clone)]
@@ -9,16 +50,215 @@ pub struct FromPretrainedParameters {
     pub revision: String,
     pub user_agent: HashMap<String, String>,
     pub auth_token: Option<String>,
+    /// The hub-style cache directory to look into, and to download to. Defaults to the
+    /// `hf_hub` default (`$HF_HOME/hub` or `~/.cache/huggingface/hub`).
+    pub cache_dir: Option<PathBuf>,
+    /// The endpoint of a mirror of the Hub to download from, like `https://hf-mirror.local`
+    pub endpoint: Option<String>,
+    /// Never access the network, and only use the files already available locally. Defaults
+    /// to `true` when the `HF_HUB_OFFLINE` environment variable is set.
+    pub offline: bool,
 }
 
 impl Default for FromPretrainedParameters {
     fn default() -> Self {
+        let offline = std::env::var("HF_HUB_OFFLINE")
+            .map(|v| matches!(v.to_uppercase().as_str(), "1" | "ON" | "YES" | "TRUE"))
+            .unwrap_or(false);
         Self {
             revision: "main".into(),
             user_agent: HashMap::new(),
             auth_token: None,
+            cache_dir: None,
+            endpoint: None,
+            offline,
+        }
+    }
+}
+
+/// The files a pretrained tokenizer can be loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PretrainedFiles {
+    /// A `tokenizer.json` file
+    Tokenizer(PathBuf),
+    /// The files used by the legacy tokenizers: a `vocab.json` and `merges.txt` for BPE, or
+    /// a `vocab.txt` for WordPiece, with an optional `special_tokens_map.json` and
+    /// `tokenizer_config.json`.
+    Legacy {
+        vocab: PathBuf,
+        merges: Option<PathBuf>,
+        special_tokens_map: Option<PathBuf>,
+        tokenizer_config: Option<PathBuf>,
+    },
+}
+
+/// Where the files of a pretrained tokenizer are looked for
+struct Resolver {
+    /// A local directory holding the files directly
+    directory: Option<PathBuf>,
+    repo: Repo,
+    cache: Cache,
+    params: FromPretrainedParameters,
+}
+
+impl Resolver {
+    /// Returns the local path to the given file, downloading it if needed and allowed
+    fn get(&self, filename: &str) -> Result<PathBuf> {
+        if let Some(directory) = &self.directory {
+            let path = directory.join(filename);
+            return if path.is_file() {
+                Ok(path)
+            } else {
+                Err(PretrainedError::FileNotFound(path).into())
+            };
+        }
+
+        if let Some(path) = self.cache.repo(self.repo.clone()).get(filename) {
+            return Ok(path);
+        }
+        if self.params.offline {
+            return Err(PretrainedError::NotCached {
+                filename: filename.to_owned(),
+                repo: self.repo.url(),
+                cache: self.cache.path().clone(),
+            }
+            .into());
+        }
+
+        let endpoint = self
+            .params
+            .endpoint
+            .as_deref()
+            .unwrap_or("https://huggingface.co");
+        self.download(endpoint, filename)
+    }
+
+    /// Download the given file from the Hub, or one of its mirrors, and save it in the cache
+    /// as part of the snapshot of the commit the revision resolves to.
+    fn download(&self, endpoint: &str, filename: &str) -> Result<PathBuf> {
+        let url = format!(
+            "{}/{}/resolve/{}/{}",
+            endpoint.trim_end_matches('/'),
+            self.repo.url(),
+            self.repo.url_revision(),
+            filename
+        );
+        let user_agent = self
+            .params
+            .user_agent
+            .iter()
+            .map(|(k, v)| format!("{}/{}", k, v))
+            .collect::<Vec<_>>()
+            .join("; ");
+        // Like `hf_hub`, use the token saved by `huggingface-cli login` by default
+        let token = self
+            .params
+            .auth_token
+            .clone()
+            .or_else(|| self.cache.token());
+        let request = |agent: &ureq::Agent| {
+            let mut request = agent.get(&url).set(
+                "User-Agent",
+                &format!("tokenizers/{}; {}", env!("CARGO_PKG_VERSION"), user_agent),
+            );
+            if let Some(token) = &token {
+                request = request.set("Authorization", &format!("Bearer {}", token));
+            }
+            request
+        };
+
+        // The files stored with LFS redirect to a CDN which doesn't give the commit, so like
+        // `hf_hub`, we read it before following any redirect
+        let response = request(&ureq::AgentBuilder::new().redirects(0).build()).call()?;
+        // Mirrors that don't give the commit can only be cached under the revision name
+        let commit = response
+            .header("x-repo-commit")
+            .unwrap_or_else(|| self.repo.revision())
+            .to_owned();
+        let response = if (300..400).contains(&response.status()) {
+            request(&ureq::agent()).call()?
+        } else {
+            response
+        };
+
+        let repo_dir = self.cache.path().join(self.repo.folder_name());
+        let path = repo_dir.join("snapshots").join(&commit).join(filename);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        // Download to a temporary file first, so that an interrupted download never looks
+        // like a cached file
+        let mut incomplete = path.file_name().unwrap().to_owned();
+        incomplete.push(".incomplete");
+        let incomplete = path.with_file_name(incomplete);
+        let downloaded = File::create(&incomplete)
+            .and_then(|mut file| std::io::copy(&mut response.into_reader(), &mut file))
+            .and_then(|_| std::fs::rename(&incomplete, &path));
+        if downloaded.is_err() {
+            let _ = std::fs::remove_file(&incomplete);
+        }
+        downloaded?;
+        self.cache.repo(self.repo.clone()).create_ref(&commit)?;
+        Ok(path)
+    }
+}
+
+/// Find the files of the identified tokenizer, and returns their local paths.
+///
+/// The `identifier` can be a local directory, or the id of a model on the Hugging Face Hub.
+/// In this case, the files are first looked for in the cache, and only downloaded (from the
+/// Hub or the configured mirror) if missing and not in offline mode. When there is no
+/// `tokenizer.json` file, the files of the legacy tokenizers are used instead.
+pub fn resolve_pretrained<S: AsRef<str>>(
+    identifier: S,
+    params: Option<FromPretrainedParameters>,
+) -> Result<PretrainedFiles> {
+    let identifier: String = identifier.as_ref().to_string();
+    let params = params.unwrap_or_default();
+
+    let directory = Some(PathBuf::from(&identifier)).filter(|path| path.is_dir());
+    if directory.is_none() {
+        let valid_chars = ['-', '_', '.', '/'];
+        let is_valid_char = |x: char| x.is_alphanumeric() || valid_chars.contains(&x);
+
+        if !identifier.chars().all(is_valid_char) {
+            return Err(PretrainedError::InvalidIdentifier(identifier).into());
+        }
+        if !params.revision.chars().all(is_valid_char) {
+            return Err(PretrainedError::InvalidRevision(params.revision).into());
+        }
+    }
+
+    let resolver = Resolver {
+        directory,
+        repo: Repo::with_revision(identifier, RepoType::Model, params.revision.clone()),
+        cache: params.cache_dir.clone().map(Cache::new).unwrap_or_default(),
+        params,
+    };
+
+    let error = match resolver.get("tokenizer.json") {
+        Ok(path) => return Ok(PretrainedFiles::Tokenizer(path)),
+        Err(e) => e,
+    };
+    let special_tokens_map = resolver.get("special_tokens_map.json").ok();
+    let tokenizer_config = resolver.get("tokenizer_config.json").ok();
+    if let Ok(vocab) = resolver.get("vocab.json") {
+        if let Ok(merges) = resolver.get("merges.txt") {
+            return Ok(PretrainedFiles::Legacy {
+                vocab,
+                merges: Some(merges),
+                special_tokens_map,
+                tokenizer_config,
+            });
         }
     }
+    if let Ok(vocab) = resolver.get("vocab.txt") {
+        return Ok(PretrainedFiles::Legacy {
+            vocab,
+            merges: None,
+            special_tokens_map,
+            tokenizer_config,
+        });
+    }
+    Err(error)
 }
 
 /// Downloads and cache the identified tokenizer if it exists on
@@ -27,44 +267,364 @@ pub fn from_pretrained<S: AsRef<str>>(
     identifier: S,
     params: Option<FromPretrainedParameters>,
 ) -> Result<PathBuf> {
-    let identifier: String = identifier.as_ref().to_string();
+    match resolve_pretrained(identifier, params)? {
+        PretrainedFiles::Tokenizer(path) => Ok(path),
+        PretrainedFiles::Legacy { .. } => Err(PretrainedError::NoTokenizerFile.into()),
+    }
+}
+
+/// Read the special tokens of a `special_tokens_map.json` file, by name. The values can be
+/// either the content of the token, or an object with a `content`, and
+/// `additional_special_tokens` is a list of these.
+fn read_special_tokens_map(path: &Path) -> Result<Vec<(String, String)>> {
+    let map: HashMap<String, serde_json::Value> =
+        serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let content = |value: &serde_json::Value| match value {
+        serde_json::Value::String(content) => Some(content.clone()),
+        value => value
+            .get("content")
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string()),
+    };
 
-    let valid_chars = ['-', '_', '.', '/'];
-    let is_valid_char = |x: char| x.is_alphanumeric() || valid_chars.contains(&x);
+    let mut tokens = vec![];
+    for (name, value) in &map {
+        match value {
+            serde_json::Value::Array(values) => {
+                tokens.extend(values.iter().filter_map(content).map(|c| (name.clone(), c)))
+            }
+            value => tokens.extend(content(value).map(|c| (name.clone(), c))),
+        }
+    }
+    tokens.sort();
+    Ok(tokens)
+}
+
+/// The options of the `BertNormalizer` given in a `tokenizer_config.json` file, with the
+/// defaults of the `BertTokenizer` of transformers
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+struct BertConfig {
+    do_lower_case: bool,
+    tokenize_chinese_chars: bool,
+    strip_accents: Option<bool>,
+}
 
-    let valid = identifier.chars().all(is_valid_char);
-    let valid_chars_stringified = valid_chars
-        .iter()
-        .fold(vec![], |mut buf, x| {
-            buf.push(format!("'{}'", x));
-            buf
+impl Default for BertConfig {
+    fn default() -> Self {
+        Self {
+            do_lower_case: true,
+            tokenize_chinese_chars: true,
+            strip_accents: None,
+        }
+    }
+}
+
+/// Assemble a tokenizer from the files of a legacy tokenizer: a byte-level BPE like GPT-2
+/// when there are merges, or a WordPiece like BERT otherwise, normalized as configured in
+/// the `tokenizer_config.json` file.
+pub(crate) fn legacy_tokenizer(
+    vocab: &Path,
+    merges: Option<&Path>,
+    special_tokens_map: Option<&Path>,
+    tokenizer_config: Option<&Path>,
+) -> Result<Tokenizer> {
+    let special_tokens = special_tokens_map
+        .map(read_special_tokens_map)
+        .transpose()?
+        .unwrap_or_default();
+    let special_token = |name: &str| {
+        special_tokens
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, content)| content.clone())
+    };
+    let path_str = |path: &Path| {
+        path.to_str()
+            .map(|p| p.to_string())
+            .ok_or_else(|| PretrainedError::InvalidPath(path.to_owned()))
+    };
+
+    let mut tokenizer = match merges {
+        Some(merges) => {
+            let mut builder = BPE::from_file(&path_str(vocab)?, &path_str(merges)?);
+            if let Some(unk) = special_token("unk_token") {
+                builder = builder.unk_token(unk);
+            }
+            let mut tokenizer = Tokenizer::new(builder.build()?);
+            tokenizer.with_pre_tokenizer(ByteLevel::default().add_prefix_space(false));
+            tokenizer.with_decoder(ByteLevel::default());
+            tokenizer.with_post_processor(ByteLevel::default().trim_offsets(false));
+            tokenizer
+        }
+        None => {
+            let mut builder = WordPiece::from_file(&path_str(vocab)?);
+            if let Some(unk) = special_token("unk_token") {
+                builder = builder.unk_token(unk);
+            }
+            let model = builder.build()?;
+            let cls =
+                special_token("cls_token").and_then(|t| Some((t.clone(), model.token_to_id(&t)?)));
+            let sep =
+                special_token("sep_token").and_then(|t| Some((t.clone(), model.token_to_id(&t)?)));
+
+            let config: BertConfig = match tokenizer_config {
+                Some(path) => serde_json::from_reader(BufReader::new(File::open(path)?))?,
+                None => BertConfig::default(),
+            };
+
+            let mut tokenizer = Tokenizer::new(model);
+            tokenizer.with_normalizer(BertNormalizer::new(
+                true,
+                config.tokenize_chinese_chars,
+                config.strip_accents,
+                config.do_lower_case,
+            ));
+            tokenizer.with_pre_tokenizer(BertPreTokenizer);
+            tokenizer.with_decoder(crate::decoders::wordpiece::WordPiece::default());
+            if let (Some(cls), Some(sep)) = (cls, sep) {
+                tokenizer.with_post_processor(BertProcessing::new(sep, cls));
+            }
+            tokenizer
+        }
+    };
+
+    tokenizer.add_special_tokens(
+        &special_tokens
+            .iter()
+            .map(|(_, content)| AddedToken::from(content.clone(), true))
+            .collect::<Vec<_>>(),
+    );
+    for (name, content) in special_tokens {
+        let role = match name.as_ref() {
+            "bos_token" => SpecialTokenRole::Bos,
+            "eos_token" => SpecialTokenRole::Eos,
+            "pad_token" => SpecialTokenRole::Pad,
+            "unk_token" => SpecialTokenRole::Unk,
+            "mask_token" => SpecialTokenRole::Mask,
+            "cls_token" => SpecialTokenRole::Cls,
+            "sep_token" => SpecialTokenRole::Sep,
+            _ => continue,
+        };
+        tokenizer.with_special_token_role(role, content)?;
+    }
+    Ok(tokenizer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+    use tempfile::TempDir;
+
+    const TOKENIZER: &str = r#"{"version":"1.0","truncation":null,"padding":null,"added_tokens":[],"normalizer":null,"pre_tokenizer":null,"post_processor":null,"decoder":null,"model":{"type":"WordLevel","vocab":{"hey":0},"unk_token":"hey"}}"#;
+
+    fn offline(cache_dir: &Path) -> Option<FromPretrainedParameters> {
+        Some(FromPretrainedParameters {
+            cache_dir: Some(cache_dir.to_path_buf()),
+            offline: true,
+            ..Default::default()
         })
-        .join(", "); // "'/', '-', '_', '.'"
-    if !valid {
-        return Err(format!(
-            "Model \"{}\" contains invalid characters, expected only alphanumeric or {valid_chars_stringified}",
-            identifier
+    }
+
+    #[test]
+    fn local_directory() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path().join("tokenizer.json"), TOKENIZER).unwrap();
+        let identifier = dir.path().to_str().unwrap();
+        assert_eq!(
+            resolve_pretrained(identifier, offline(dir.path())).unwrap(),
+            PretrainedFiles::Tokenizer(dir.path().join("tokenizer.json"))
+        );
+    }
+
+    #[test]
+    fn hub_cache() {
+        let cache = TempDir::new().unwrap();
+        let repo = cache.path().join("models--org--model");
+        create_dir_all(repo.join("refs")).unwrap();
+        write(repo.join("refs/main"), "abcdef").unwrap();
+        create_dir_all(repo.join("snapshots/abcdef")).unwrap();
+        write(repo.join("snapshots/abcdef/tokenizer.json"), TOKENIZER).unwrap();
+
+        let path = from_pretrained("org/model", offline(cache.path())).unwrap();
+        assert_eq!(path, repo.join("snapshots/abcdef/tokenizer.json"));
+        let tokenizer = Tokenizer::from_file(path).unwrap();
+        assert_eq!(tokenizer.get_vocab_size(false), 1);
+
+        // Other revisions are not available offline
+        let err = from_pretrained(
+            "org/model",
+            Some(FromPretrainedParameters {
+                revision: "v2".into(),
+                ..offline(cache.path()).unwrap()
+            }),
         )
-        .into());
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::Model(crate::models::ModelError::Pretrained(
+                PretrainedError::NotCached { .. }
+            ))
+        ));
+        assert!(err.to_string().contains("offline mode is enabled"));
     }
-    let params = params.unwrap_or_default();
 
-    let revision = &params.revision;
-    let valid_revision = revision.chars().all(is_valid_char);
-    if !valid_revision {
-        return Err(format!(
-            "Revision \"{}\" contains invalid characters, expected only alphanumeric or {valid_chars_stringified}",
-            revision
+    #[test]
+    fn download() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        // Like the Hub with LFS files, the commit is only given before the redirect to the CDN
+        let server = std::thread::spawn(move || {
+            let mut requests = vec![];
+            loop {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![0; 4096];
+                let n = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..n]).into_owned();
+                let to_cdn = request.starts_with("GET /cdn/");
+                if to_cdn {
+                    write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                        TOKENIZER.len(),
+                        TOKENIZER
+                    )
+                } else {
+                    write!(
+                        stream,
+                        "HTTP/1.1 302 Found\r\nConnection: close\r\nx-repo-commit: abcdef\r\n\
+                         Location: /cdn/tokenizer.json\r\nContent-Length: 0\r\n\r\n"
+                    )
+                }
+                .unwrap();
+                requests.push(request);
+                if to_cdn {
+                    return requests;
+                }
+            }
+        });
+
+        let cache = TempDir::new().unwrap();
+        let path = from_pretrained(
+            "org/model",
+            Some(FromPretrainedParameters {
+                cache_dir: Some(cache.path().to_path_buf()),
+                endpoint: Some(endpoint),
+                user_agent: [("app".to_string(), "1.0".to_string())].into(),
+                offline: false,
+                ..Default::default()
+            }),
         )
-        .into());
+        .unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /org/model/resolve/main/tokenizer.json"));
+        assert!(requests[0].contains("app/1.0"));
+        // Saved in the snapshot of the commit, with the revision pointing to it
+        let repo = cache.path().join("models--org--model");
+        assert_eq!(path, repo.join("snapshots/abcdef/tokenizer.json"));
+        assert_eq!(
+            std::fs::read_to_string(repo.join("refs/main")).unwrap(),
+            "abcdef"
+        );
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        // Now available offline
+        assert_eq!(
+            from_pretrained("org/model", offline(cache.path())).unwrap(),
+            path
+        );
     }
 
-    let mut builder = ApiBuilder::new();
-    if let Some(token) = params.auth_token {
-        builder = builder.with_token(Some(token));
+    #[test]
+    fn legacy_wordpiece() {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path().join("vocab.txt"),
+            "[UNK]\n[CLS]\n[SEP]\nhey\nthere\n##s\n",
+        )
+        .unwrap();
+        write(
+            dir.path().join("special_tokens_map.json"),
+            r#"{"unk_token": "[UNK]", "cls_token": {"content": "[CLS]"}, "sep_token": "[SEP]"}"#,
+        )
+        .unwrap();
+
+        let files = resolve_pretrained(dir.path().to_str().unwrap(), offline(dir.path())).unwrap();
+        let (vocab, merges, special_tokens_map, tokenizer_config) = match files {
+            PretrainedFiles::Legacy {
+                vocab,
+                merges,
+                special_tokens_map,
+                tokenizer_config,
+            } => (vocab, merges, special_tokens_map, tokenizer_config),
+            files => panic!("Unexpected files {:?}", files),
+        };
+        assert_eq!(merges, None);
+        assert_eq!(tokenizer_config, None);
+
+        let tokenizer =
+            legacy_tokenizer(&vocab, None, special_tokens_map.as_deref(), None).unwrap();
+        let encoding = tokenizer.encode("Hey theres [SEP] you", true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["[CLS]", "hey", "there", "##s", "[SEP]", "[UNK]", "[SEP]"]
+        );
+    }
+
+    #[test]
+    fn legacy_wordpiece_cased() {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path().join("vocab.txt"),
+            "[UNK]\n[CLS]\n[SEP]\nHey\nhey\nthére\n",
+        )
+        .unwrap();
+        write(
+            dir.path().join("tokenizer_config.json"),
+            r#"{"do_lower_case": false, "tokenize_chinese_chars": true}"#,
+        )
+        .unwrap();
+
+        let tokenizer = Tokenizer::from_pretrained(dir.path().to_str().unwrap(), None).unwrap();
+        let encoding = tokenizer.encode("Hey hey thére", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["Hey", "hey", "thére"]);
+    }
+
+    #[test]
+    fn legacy_bpe() {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path().join("vocab.json"),
+            r#"{"h": 0, "e": 1, "y": 2, "he": 3, "hey": 4, "Ġ": 5, "Ġhey": 6, "<|endoftext|>": 7}"#,
+        )
+        .unwrap();
+        write(
+            dir.path().join("merges.txt"),
+            "#version: 0.2\nh e\nhe y\nĠ hey\n",
+        )
+        .unwrap();
+        write(
+            dir.path().join("special_tokens_map.json"),
+            r#"{"eos_token": "<|endoftext|>"}"#,
+        )
+        .unwrap();
+
+        let tokenizer = Tokenizer::from_pretrained(dir.path().to_str().unwrap(), None).unwrap();
+        let encoding = tokenizer.encode("hey hey<|endoftext|>", true).unwrap();
+        assert_eq!(encoding.get_ids(), &[4, 6, 7]);
+        assert_eq!(
+            tokenizer.decode(encoding.get_ids(), true).unwrap(),
+            "hey hey"
+        );
+        assert_eq!(tokenizer.eos_token_id(), Some(7));
+        assert_eq!(tokenizer.bos_token_id(), None);
     }
-    let api = builder.build()?;
-    let repo = Repo::with_revision(identifier, RepoType::Model, params.revision);
-    let api = api.repo(repo);
-    Ok(api.get("tokenizer.json")?)
 }
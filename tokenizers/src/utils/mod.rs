@@ -1,4 +1,5 @@
-pub(crate) mod cache;
+pub mod analysis;
+pub mod cache;
 #[cfg(feature = "http")]
 pub(crate) mod from_pretrained;
 
@@ -12,10 +13,13 @@ mod onig;
 pub use crate::utils::onig::SysRegex;
 
 pub mod iter;
+pub mod packing;
 pub mod padding;
 pub mod parallelism;
 pub(crate) mod progress;
+pub mod sentencepiece;
 pub mod truncation;
+pub mod word_counts;
 
 use serde::{Serialize, Serializer};
 use std::collections::{BTreeMap, HashMap};
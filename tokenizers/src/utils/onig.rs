@@ -1,7 +1,6 @@
 use crate::tokenizer::pattern::Pattern;
 use crate::{Offsets, Result};
 use onig::Regex;
-use std::error::Error;
 
 #[derive(Debug)]
 pub struct SysRegex {
@@ -13,9 +12,7 @@ impl SysRegex {
         self.regex.find_iter(inside)
     }
 
-    pub fn new(
-        regex_str: &str,
-    ) -> std::result::Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+    pub fn new(regex_str: &str) -> Result<Self> {
         Ok(Self {
             regex: Regex::new(regex_str)?,
         })
@@ -0,0 +1,412 @@
+use crate::tokenizer::{Encoding, Result};
+use crate::utils::padding::PaddingDirection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PackingError {
+    /// The rows used to pack the encodings can't be empty
+    #[error("The row length must be greater than 0")]
+    ZeroRowLength,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackingParams {
+    /// The length of each packed row
+    pub row_length: usize,
+    /// The id of the token inserted after each document, if any
+    pub separator_id: Option<u32>,
+    /// The content of the separator token
+    pub separator_token: String,
+    /// Whether the documents that do not fit in the current row can be continued on the
+    /// next one. Otherwise they start a new row, and are truncated if longer than a row.
+    pub split_documents: bool,
+    /// The id of the token used to pad the last rows
+    pub pad_id: u32,
+    /// The type id of the padding tokens
+    pub pad_type_id: u32,
+    /// The content of the padding token
+    pub pad_token: String,
+}
+
+impl Default for PackingParams {
+    fn default() -> Self {
+        Self {
+            row_length: 512,
+            separator_id: None,
+            separator_token: String::from("[SEP]"),
+            split_documents: true,
+            pad_id: 0,
+            pad_type_id: 0,
+            pad_token: String::from("[PAD]"),
+        }
+    }
+}
+
+/// A row of packed documents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackedRow {
+    /// The packed tokens, padded up to the row length
+    pub encoding: Encoding,
+    /// The index of the document each token belongs to, starting at 1 in each row.
+    /// Padding tokens have a segment id of 0.
+    pub segment_ids: Vec<u32>,
+    /// The position of each token in its document, restarting at 0 for each segment
+    pub position_ids: Vec<u32>,
+}
+
+impl PackedRow {
+    /// The range of tokens covered by each segment of this row
+    pub fn segment_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        let mut previous = 0;
+        for (i, segment) in self.segment_ids.iter().enumerate() {
+            if *segment != 0 {
+                match ranges.last_mut() {
+                    Some(range) if *segment == previous => range.end = i + 1,
+                    _ => ranges.push(i..i + 1),
+                }
+            }
+            previous = *segment;
+        }
+        ranges
+    }
+
+    /// A `row_length x row_length` attention mask where each token only attends to the
+    /// tokens of its own document
+    pub fn document_attention_mask(&self) -> Vec<Vec<u32>> {
+        self.segment_ids
+            .iter()
+            .map(|i| {
+                self.segment_ids
+                    .iter()
+                    .map(|j| u32::from(*i != 0 && i == j))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackingStats {
+    /// The number of documents that were packed
+    pub documents: usize,
+    /// The number of documents spread over multiple rows
+    pub split_documents: usize,
+    /// The number of documents that were truncated because longer than a row
+    pub truncated_documents: usize,
+    /// The number of tokens dropped by truncation
+    pub truncated_tokens: usize,
+    /// The number of packed tokens, separators included
+    pub tokens: usize,
+    /// The number of padding tokens
+    pub padding: usize,
+}
+
+impl PackingStats {
+    /// The proportion of the packed rows filled with actual tokens
+    pub fn efficiency(&self) -> f64 {
+        let total = self.tokens + self.padding;
+        if total == 0 {
+            0.0
+        } else {
+            self.tokens as f64 / total as f64
+        }
+    }
+}
+
+/// The rows produced by [`pack_encodings`], along with some statistics about them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Packing {
+    pub rows: Vec<PackedRow>,
+    pub stats: PackingStats,
+}
+
+struct RowBuilder<'a> {
+    params: &'a PackingParams,
+    encodings: Vec<Encoding>,
+    segment_ids: Vec<u32>,
+    position_ids: Vec<u32>,
+}
+
+impl<'a> RowBuilder<'a> {
+    fn new(params: &'a PackingParams) -> Self {
+        Self {
+            params,
+            encodings: vec![],
+            segment_ids: Vec::with_capacity(params.row_length),
+            position_ids: Vec::with_capacity(params.row_length),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.params.row_length - self.segment_ids.len()
+    }
+
+    fn push(&mut self, encoding: Encoding) {
+        let segment = self.encodings.len() as u32 + 1;
+        self.segment_ids
+            .resize(self.segment_ids.len() + encoding.len(), segment);
+        self.position_ids.extend(0..encoding.len() as u32);
+        self.encodings.push(encoding);
+    }
+
+    fn finish(&mut self, packing: &mut Packing) {
+        if self.encodings.is_empty() {
+            return;
+        }
+        let padding = self.remaining();
+        let mut encoding = Encoding::merge(self.encodings.drain(..), false);
+        encoding.pad(
+            self.params.row_length,
+            self.params.pad_id,
+            self.params.pad_type_id,
+            &self.params.pad_token,
+            PaddingDirection::Right,
+        );
+        let mut segment_ids = std::mem::take(&mut self.segment_ids);
+        let mut position_ids = std::mem::take(&mut self.position_ids);
+        segment_ids.resize(self.params.row_length, 0);
+        position_ids.resize(self.params.row_length, 0);
+
+        packing.stats.padding += padding;
+        packing.rows.push(PackedRow {
+            encoding,
+            segment_ids,
+            position_ids,
+        });
+    }
+}
+
+/// Pack the given documents into rows of `params.row_length` tokens.
+///
+/// The documents are packed in order, a separator being added after each of them when
+/// `params.separator_id` is set, unless it would be the first token of a row. The empty
+/// documents and the overflowing parts of the given encodings are ignored.
+pub fn pack_encodings<I>(encodings: I, params: &PackingParams) -> Result<Packing>
+where
+    I: IntoIterator<Item = Encoding>,
+{
+    if params.row_length == 0 {
+        return Err(PackingError::ZeroRowLength.into());
+    }
+
+    let mut packing = Packing::default();
+    let mut row = RowBuilder::new(params);
+    let separator = usize::from(params.separator_id.is_some());
+    for encoding in encodings {
+        if encoding.is_empty() {
+            continue;
+        }
+        let mut document = encoding.slice(0, encoding.len());
+        if let Some(id) = params.separator_id {
+            let type_id = document.get_type_ids().last().copied().unwrap_or(0);
+            document.merge_with(
+                Encoding::new(
+                    vec![id],
+                    vec![type_id],
+                    vec![params.separator_token.clone()],
+                    vec![None],
+                    vec![(0, 0)],
+                    vec![1],
+                    vec![1],
+                    vec![],
+                    HashMap::new(),
+                ),
+                false,
+            );
+        }
+        packing.stats.documents += 1;
+        packing.stats.tokens += document.len();
+
+        if params.split_documents {
+            let mut len = document.len();
+            let mut start = 0;
+            while start < len {
+                if row.remaining() == 0 {
+                    row.finish(&mut packing);
+                }
+                let stop = len.min(start + row.remaining());
+                if stop + separator == len && stop < len {
+                    // The document fills the row, without its separator
+                    len = stop;
+                    packing.stats.tokens -= separator;
+                }
+                if start > 0 && stop == len {
+                    packing.stats.split_documents += 1;
+                }
+                row.push(document.slice(start, stop));
+                start = stop;
+            }
+        } else {
+            if document.len() - separator > row.remaining() {
+                row.finish(&mut packing);
+            }
+            if document.len() > row.remaining() {
+                // Without its separator, the document either fills the row or is truncated
+                let kept = row.remaining();
+                let dropped = document.len() - separator - kept;
+                if dropped > 0 {
+                    packing.stats.truncated_documents += 1;
+                    packing.stats.truncated_tokens += dropped;
+                }
+                packing.stats.tokens -= document.len() - kept;
+                document = document.slice(0, kept);
+            }
+            row.push(document);
+        }
+    }
+    row.finish(&mut packing);
+
+    Ok(packing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(ids: &[u32]) -> Encoding {
+        Encoding::new(
+            ids.to_vec(),
+            vec![0; ids.len()],
+            ids.iter().map(|id| format!("t{}", id)).collect(),
+            ids.iter().map(|id| Some(*id)).collect(),
+            ids.iter()
+                .map(|id| (*id as usize, *id as usize + 1))
+                .collect(),
+            vec![0; ids.len()],
+            vec![1; ids.len()],
+            vec![],
+            HashMap::new(),
+        )
+    }
+
+    #[test]
+    fn pack_with_split() {
+        let params = PackingParams {
+            row_length: 4,
+            separator_id: Some(0),
+            ..Default::default()
+        };
+        let documents = vec![
+            document(&[1, 2]),
+            document(&[3, 4, 5]),
+            document(&[6]),
+            document(&[]),
+            document(&[7, 8, 9, 10]),
+            document(&[11]),
+        ];
+        let packing = pack_encodings(documents, &params).unwrap();
+
+        assert_eq!(packing.rows.len(), 4);
+        let ids = packing
+            .rows
+            .iter()
+            .map(|row| row.encoding.get_ids().to_vec())
+            .collect::<Vec<_>>();
+        // The separators of [6] and [7, 8, 9, 10] are dropped, as they fill their row
+        assert_eq!(
+            ids,
+            vec![[1, 2, 0, 3], [4, 5, 0, 6], [7, 8, 9, 10], [11, 0, 0, 0]]
+        );
+        assert_eq!(packing.rows[0].segment_ids, [1, 1, 1, 2]);
+        assert_eq!(packing.rows[0].position_ids, [0, 1, 2, 0]);
+        assert_eq!(packing.rows[1].segment_ids, [1, 1, 1, 2]);
+        assert_eq!(packing.rows[2].segment_ids, [1, 1, 1, 1]);
+        assert_eq!(packing.rows[3].segment_ids, [1, 1, 0, 0]);
+        assert_eq!(packing.rows[3].encoding.get_attention_mask(), [1, 1, 0, 0]);
+        assert_eq!(packing.rows[0].segment_ranges(), vec![0..3, 3..4]);
+        assert_eq!(
+            packing.rows[0].document_attention_mask(),
+            vec![
+                vec![1, 1, 1, 0],
+                vec![1, 1, 1, 0],
+                vec![1, 1, 1, 0],
+                vec![0, 0, 0, 1]
+            ]
+        );
+
+        assert_eq!(
+            packing.stats,
+            PackingStats {
+                documents: 5,
+                split_documents: 1,
+                tokens: 14,
+                padding: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(packing.stats.efficiency(), 0.875);
+    }
+
+    #[test]
+    fn pack_without_split() {
+        let params = PackingParams {
+            row_length: 4,
+            split_documents: false,
+            ..Default::default()
+        };
+        let documents = vec![
+            document(&[1, 2]),
+            document(&[3, 4, 5]),
+            document(&[6, 7, 8, 9, 10]),
+            document(&[11]),
+        ];
+        let packing = pack_encodings(documents, &params).unwrap();
+
+        let ids = packing
+            .rows
+            .iter()
+            .map(|row| row.encoding.get_ids().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![[1, 2, 0, 0], [3, 4, 5, 0], [6, 7, 8, 9], [11, 0, 0, 0]]
+        );
+        assert_eq!(packing.rows[1].segment_ids, [1, 1, 1, 0]);
+        assert_eq!(packing.stats.truncated_documents, 1);
+        assert_eq!(packing.stats.truncated_tokens, 1);
+        assert_eq!(packing.stats.tokens, 10);
+        assert_eq!(packing.stats.padding, 6);
+
+        assert!(matches!(
+            pack_encodings(
+                vec![],
+                &PackingParams {
+                    row_length: 0,
+                    ..params
+                }
+            ),
+            Err(crate::Error::Packing(PackingError::ZeroRowLength))
+        ));
+    }
+
+    #[test]
+    fn pack_without_split_with_separator() {
+        let params = PackingParams {
+            row_length: 4,
+            separator_id: Some(0),
+            split_documents: false,
+            ..Default::default()
+        };
+        let documents = vec![
+            document(&[]),
+            document(&[1, 2]),
+            document(&[3]),
+            document(&[4, 5, 6, 7]),
+        ];
+        let packing = pack_encodings(documents, &params).unwrap();
+
+        let ids = packing
+            .rows
+            .iter()
+            .map(|row| row.encoding.get_ids().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![[1, 2, 0, 3], [4, 5, 6, 7]]);
+        assert_eq!(packing.rows[0].segment_ranges(), vec![0..3, 3..4]);
+        assert_eq!(packing.stats.documents, 3);
+        assert_eq!(packing.stats.truncated_documents, 0);
+        assert_eq!(packing.stats.tokens, 8);
+    }
+}
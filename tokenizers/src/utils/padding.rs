@@ -1,7 +1,17 @@
 use crate::parallelism::*;
-use crate::tokenizer::{Encoding, Result};
+use crate::tokenizer::{Encoding, Result, SpecialTokenRoleError};
 use serde::{Deserialize, Serialize};
 
+#[derive(thiserror::Error, Debug)]
+pub enum PaddingError {
+    /// Padding to buckets needs at least one of them
+    #[error("Padding error: there are no buckets to pad to")]
+    NoBuckets,
+    /// No token can be used for the padding
+    #[error(transparent)]
+    PadToken(#[from] SpecialTokenRoleError),
+}
+
 /// The various possible padding directions.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum PaddingDirection {
@@ -45,6 +55,25 @@ impl Default for PaddingParams {
 pub enum PaddingStrategy {
     BatchLongest,
     Fixed(usize),
+    /// Pad each encoding to the smallest of these lengths that fits it. The encodings
+    /// longer than all the buckets are left as is.
+    Buckets(Vec<usize>),
+}
+
+impl PaddingStrategy {
+    /// The length at which an encoding of the given length gets padded, when using
+    /// `Buckets`, or the given length otherwise
+    fn bucket_length(&self, length: usize) -> usize {
+        match self {
+            PaddingStrategy::Buckets(buckets) => buckets
+                .iter()
+                .filter(|bucket| **bucket >= length)
+                .min()
+                .copied()
+                .unwrap_or(length),
+            _ => length,
+        }
+    }
 }
 
 pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Result<()> {
@@ -52,24 +81,31 @@ pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Resu
         return Ok(());
     }
 
-    let mut pad_length = match params.strategy {
-        PaddingStrategy::Fixed(size) => size,
-        PaddingStrategy::BatchLongest => encodings
-            .maybe_par_iter()
-            .map(|e| e.get_ids().len())
-            .max()
-            .unwrap(),
+    let pad_length = match &params.strategy {
+        PaddingStrategy::Fixed(size) => Some(*size),
+        PaddingStrategy::BatchLongest => {
+            encodings.maybe_par_iter().map(|e| e.get_ids().len()).max()
+        }
+        PaddingStrategy::Buckets(buckets) if buckets.is_empty() => {
+            return Err(PaddingError::NoBuckets.into())
+        }
+        PaddingStrategy::Buckets(_) => None,
     };
 
-    if let Some(multiple) = params.pad_to_multiple_of {
-        if multiple > 0 && pad_length % multiple > 0 {
-            pad_length += multiple - pad_length % multiple;
+    let to_multiple = |mut length: usize| {
+        if let Some(multiple) = params.pad_to_multiple_of.filter(|m| *m > 0) {
+            let remainder = length % multiple;
+            if remainder > 0 {
+                length += multiple - remainder;
+            }
         }
-    }
+        length
+    };
 
     encodings.maybe_par_iter_mut().for_each(|encoding| {
+        let length = pad_length.unwrap_or_else(|| params.strategy.bucket_length(encoding.len()));
         encoding.pad(
-            pad_length,
+            to_multiple(length),
             params.pad_id,
             params.pad_type_id,
             &params.pad_token,
@@ -80,6 +116,26 @@ pub fn pad_encodings(encodings: &mut [Encoding], params: &PaddingParams) -> Resu
     Ok(())
 }
 
+/// Return the permutation that sorts the given encodings by increasing length.
+///
+/// Encodings of the same length keep their relative order.
+pub fn sort_by_length(encodings: &[Encoding]) -> Vec<usize> {
+    let mut permutation = (0..encodings.len()).collect::<Vec<_>>();
+    permutation.sort_by_key(|i| encodings[*i].len());
+    permutation
+}
+
+/// Group the given encodings in batches of at most `batch_size` encodings of similar
+/// lengths, to minimize the padding needed by each batch.
+///
+/// Returns the indices of the encodings in each batch.
+pub fn group_by_length(encodings: &[Encoding], batch_size: usize) -> Vec<Vec<usize>> {
+    sort_by_length(encodings)
+        .chunks(batch_size.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,4 +195,53 @@ mod tests {
         params.pad_to_multiple_of = Some(0);
         pad_encodings(&mut encodings, &params).unwrap();
     }
+
+    fn encoding(len: usize) -> Encoding {
+        Encoding::new(
+            vec![1; len],
+            vec![0; len],
+            vec![String::from("a"); len],
+            vec![None; len],
+            vec![(0, 0); len],
+            vec![0; len],
+            vec![1; len],
+            vec![],
+            HashMap::new(),
+        )
+    }
+
+    #[test]
+    fn pad_to_buckets() {
+        let mut encodings = [encoding(3), encoding(9), encoding(20), encoding(8)];
+        let mut params = PaddingParams {
+            strategy: PaddingStrategy::Buckets(vec![16, 8]),
+            ..Default::default()
+        };
+        pad_encodings(&mut encodings, &params).unwrap();
+        let lengths = encodings.iter().map(|e| e.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, [8, 16, 20, 8]);
+
+        let mut encodings = [encoding(3), encoding(9), encoding(20), encoding(8)];
+        params.pad_to_multiple_of = Some(6);
+        pad_encodings(&mut encodings, &params).unwrap();
+        let lengths = encodings.iter().map(|e| e.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, [12, 18, 24, 12]);
+    }
+
+    #[test]
+    fn group_by_length() {
+        let encodings = [
+            encoding(5),
+            encoding(2),
+            encoding(9),
+            encoding(2),
+            encoding(4),
+        ];
+        assert_eq!(sort_by_length(&encodings), [1, 3, 4, 0, 2]);
+        assert_eq!(
+            super::group_by_length(&encodings, 2),
+            vec![vec![1, 3], vec![4, 0], vec![2]]
+        );
+        assert!(super::group_by_length(&[], 2).is_empty());
+    }
 }
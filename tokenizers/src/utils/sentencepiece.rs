@@ -0,0 +1,514 @@
+//! Import of SentencePiece `.model` files.
+//!
+//! These files are serialized `ModelProto` protobuf messages. We only need a handful of
+//! fields, so instead of depending on a protobuf runtime, we read the wire format directly
+//! and skip everything we don't know about.
+use crate::decoders::byte_fallback::ByteFallback;
+use crate::decoders::sequence::Sequence as DecoderSequence;
+use crate::models::bpe::BPE;
+use crate::models::unigram::Unigram;
+use crate::normalizers::precompiled::Precompiled;
+use crate::normalizers::replace::{Replace, ReplacePattern};
+use crate::normalizers::strip::Strip;
+use crate::normalizers::utils::Sequence as NormalizerSequence;
+use crate::normalizers::NormalizerWrapper;
+use crate::pre_tokenizers::metaspace::Metaspace;
+use crate::tokenizer::{AddedToken, Result, SpecialTokenRole, Tokenizer};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::Path;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SentencePieceError {
+    #[error("Truncated protobuf message")]
+    Truncated,
+    #[error("Unsupported protobuf wire type {0}")]
+    InvalidWireType(u64),
+    #[error("Invalid UTF-8 in piece")]
+    InvalidUtf8,
+    #[error("Unsupported SentencePiece model type {0}, only Unigram and BPE are supported")]
+    UnsupportedModelType(u64),
+}
+
+/// A field of a protobuf message, as read from the wire
+enum Field<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Iterates over the fields of a protobuf message
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or(SentencePieceError::Truncated)?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SentencePieceError::Truncated.into())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(SentencePieceError::Truncated)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Read the next field with its number, if any
+    fn next_field(&mut self) -> Result<Option<(u64, Field<'a>)>> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = match key & 0x7 {
+            0 => Field::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Field::Fixed64
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Field::Bytes(self.take(len)?)
+            }
+            5 => Field::Fixed32(u32::from_le_bytes(self.take(4)?.try_into()?)),
+            wire_type => return Err(SentencePieceError::InvalidWireType(wire_type).into()),
+        };
+        Ok(Some((key >> 3, field)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PieceType {
+    Normal,
+    Unknown,
+    Control,
+    UserDefined,
+    Unused,
+    Byte,
+}
+
+impl From<u64> for PieceType {
+    fn from(value: u64) -> Self {
+        match value {
+            2 => Self::Unknown,
+            3 => Self::Control,
+            4 => Self::UserDefined,
+            5 => Self::Unused,
+            6 => Self::Byte,
+            _ => Self::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    piece: String,
+    score: f32,
+    kind: PieceType,
+}
+
+impl Piece {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut piece = Piece {
+            piece: String::new(),
+            score: 0.0,
+            kind: PieceType::Normal,
+        };
+        let mut reader = ProtoReader::new(data);
+        while let Some((number, field)) = reader.next_field()? {
+            match (number, field) {
+                (1, Field::Bytes(bytes)) => {
+                    piece.piece = String::from_utf8(bytes.to_vec())
+                        .map_err(|_| SentencePieceError::InvalidUtf8)?
+                }
+                (2, Field::Fixed32(bits)) => piece.score = f32::from_bits(bits),
+                (3, Field::Varint(kind)) => piece.kind = kind.into(),
+                _ => {}
+            }
+        }
+        Ok(piece)
+    }
+}
+
+/// The parts of a SentencePiece `ModelProto` needed to build a `Tokenizer`
+#[derive(Debug, Clone, PartialEq)]
+struct ModelProto {
+    pieces: Vec<Piece>,
+    /// `TrainerSpec.model_type`: 1 for Unigram, 2 for BPE
+    model_type: u64,
+    byte_fallback: bool,
+    unk_id: usize,
+    bos_id: usize,
+    eos_id: usize,
+    pad_id: usize,
+    precompiled_charsmap: Vec<u8>,
+    add_dummy_prefix: bool,
+    remove_extra_whitespaces: bool,
+}
+
+impl ModelProto {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut proto = ModelProto {
+            pieces: vec![],
+            model_type: 1,
+            byte_fallback: false,
+            unk_id: 0,
+            bos_id: 1,
+            eos_id: 2,
+            // -1, disabled
+            pad_id: usize::MAX,
+            precompiled_charsmap: vec![],
+            add_dummy_prefix: true,
+            remove_extra_whitespaces: true,
+        };
+
+        let mut reader = ProtoReader::new(data);
+        while let Some((number, field)) = reader.next_field()? {
+            match (number, field) {
+                (1, Field::Bytes(piece)) => proto.pieces.push(Piece::parse(piece)?),
+                (2, Field::Bytes(trainer_spec)) => {
+                    let mut reader = ProtoReader::new(trainer_spec);
+                    while let Some((number, field)) = reader.next_field()? {
+                        match (number, field) {
+                            (3, Field::Varint(model_type)) => proto.model_type = model_type,
+                            (35, Field::Varint(byte_fallback)) => {
+                                proto.byte_fallback = byte_fallback != 0
+                            }
+                            (40, Field::Varint(unk_id)) => proto.unk_id = unk_id as usize,
+                            (41, Field::Varint(bos_id)) => proto.bos_id = bos_id as usize,
+                            (42, Field::Varint(eos_id)) => proto.eos_id = eos_id as usize,
+                            (43, Field::Varint(pad_id)) => proto.pad_id = pad_id as usize,
+                            _ => {}
+                        }
+                    }
+                }
+                (3, Field::Bytes(normalizer_spec)) => {
+                    let mut reader = ProtoReader::new(normalizer_spec);
+                    while let Some((number, field)) = reader.next_field()? {
+                        match (number, field) {
+                            (2, Field::Bytes(charsmap)) => {
+                                proto.precompiled_charsmap = charsmap.to_vec()
+                            }
+                            (3, Field::Varint(v)) => proto.add_dummy_prefix = v != 0,
+                            (4, Field::Varint(v)) => proto.remove_extra_whitespaces = v != 0,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(proto)
+    }
+
+    /// Build the merges of a BPE model. SentencePiece merges the pair producing the piece
+    /// with the lowest id first, so we order them the same way.
+    fn bpe_merges(&self, vocab: &HashMap<String, u32>) -> Vec<(String, String)> {
+        let mut merges = vec![];
+        for (id, piece) in self.pieces.iter().enumerate() {
+            if !matches!(piece.kind, PieceType::Normal | PieceType::UserDefined) {
+                continue;
+            }
+            for (split, _) in piece.piece.char_indices().skip(1) {
+                let (left, right) = piece.piece.split_at(split);
+                if let (Some(l), Some(r)) = (vocab.get(left), vocab.get(right)) {
+                    merges.push(((id, *l, *r), (left.to_string(), right.to_string())));
+                }
+            }
+        }
+        merges.sort_by_key(|(rank, _)| *rank);
+        merges.into_iter().map(|(_, merge)| merge).collect()
+    }
+
+    fn into_tokenizer(self) -> Result<Tokenizer> {
+        let unk_id = Some(self.unk_id).filter(|id| *id < self.pieces.len());
+        let mut tokenizer = match self.model_type {
+            1 => Tokenizer::new(Unigram::from(
+                self.pieces
+                    .iter()
+                    .map(|p| (p.piece.clone(), p.score as f64))
+                    .collect(),
+                unk_id,
+                self.byte_fallback,
+            )?),
+            2 => {
+                let vocab = self
+                    .pieces
+                    .iter()
+                    .enumerate()
+                    .map(|(id, p)| (p.piece.clone(), id as u32))
+                    .collect::<HashMap<_, _>>();
+                let merges = self.bpe_merges(&vocab);
+                let mut builder = BPE::builder()
+                    .vocab_and_merges(vocab, merges)
+                    .fuse_unk(true)
+                    .byte_fallback(self.byte_fallback);
+                if let Some(unk_id) = unk_id {
+                    builder = builder.unk_token(self.pieces[unk_id].piece.clone());
+                }
+                Tokenizer::new(builder.build()?)
+            }
+            model_type => return Err(SentencePieceError::UnsupportedModelType(model_type).into()),
+        };
+
+        let mut normalizers: Vec<NormalizerWrapper> = vec![];
+        if !self.precompiled_charsmap.is_empty() {
+            normalizers.push(Precompiled::from(&self.precompiled_charsmap)?.into());
+        }
+        if self.remove_extra_whitespaces {
+            normalizers.push(Strip::new(true, true).into());
+            normalizers.push(Replace::new(ReplacePattern::Regex(" {2,}".into()), " ")?.into());
+        }
+        match normalizers.len() {
+            0 => {}
+            1 => {
+                tokenizer.with_normalizer(normalizers.remove(0));
+            }
+            _ => {
+                tokenizer.with_normalizer(NormalizerSequence::new(normalizers));
+            }
+        }
+
+        let metaspace = Metaspace::new('▁', self.add_dummy_prefix);
+        tokenizer.with_pre_tokenizer(metaspace.clone());
+        if self.byte_fallback {
+            tokenizer.with_decoder(DecoderSequence::new(vec![
+                ByteFallback::new().into(),
+                metaspace.into(),
+            ]));
+        } else {
+            tokenizer.with_decoder(metaspace);
+        }
+
+        // Unlike SentencePiece, which never extracts control and unknown pieces from the text,
+        // we register them as special tokens so that they can be skipped when decoding. They
+        // are thus matched in the input by default, which can be disabled when encoding with
+        // `SpecialTokensMatching::None`. User defined pieces are always extracted as is.
+        let special_tokens = self
+            .pieces
+            .iter()
+            .filter(|p| matches!(p.kind, PieceType::Control | PieceType::Unknown))
+            .map(|p| AddedToken::from(p.piece.clone(), true))
+            .collect::<Vec<_>>();
+        tokenizer.add_special_tokens(&special_tokens);
+        let user_defined = self
+            .pieces
+            .iter()
+            .filter(|p| p.kind == PieceType::UserDefined)
+            .map(|p| AddedToken::from(p.piece.clone(), false))
+            .collect::<Vec<_>>();
+        tokenizer.add_tokens(&user_defined);
+
+        for (role, id) in [
+            (SpecialTokenRole::Unk, self.unk_id),
+            (SpecialTokenRole::Bos, self.bos_id),
+            (SpecialTokenRole::Eos, self.eos_id),
+            (SpecialTokenRole::Pad, self.pad_id),
+        ] {
+            // The defaults ids may point to regular pieces when the roles are disabled
+            let piece = self
+                .pieces
+                .get(id)
+                .filter(|p| matches!(p.kind, PieceType::Control | PieceType::Unknown));
+            if let Some(piece) = piece {
+                tokenizer.with_special_token_role(role, piece.piece.clone())?;
+            }
+        }
+
+        Ok(tokenizer)
+    }
+}
+
+/// Build a `Tokenizer` from the given SentencePiece `.model` file
+pub(crate) fn from_sentencepiece<P: AsRef<Path>>(path: P) -> Result<Tokenizer> {
+    let data = std::fs::read(path)?;
+    ModelProto::parse(&data)?.into_tokenizer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpecialTokensMatching;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn bytes_field(number: u64, bytes: &[u8], out: &mut Vec<u8>) {
+        varint(number << 3 | 2, out);
+        varint(bytes.len() as u64, out);
+        out.extend(bytes);
+    }
+
+    fn varint_field(number: u64, value: u64, out: &mut Vec<u8>) {
+        varint(number << 3, out);
+        varint(value, out);
+    }
+
+    /// Serialize a `ModelProto` with the given pieces (piece, score, type)
+    fn model_proto(pieces: &[(&str, f32, u64)], model_type: u64, byte_fallback: bool) -> Vec<u8> {
+        let mut proto = vec![];
+        for (piece, score, kind) in pieces {
+            let mut message = vec![];
+            bytes_field(1, piece.as_bytes(), &mut message);
+            varint(2 << 3 | 5, &mut message);
+            message.extend(score.to_bits().to_le_bytes());
+            varint_field(3, *kind, &mut message);
+            bytes_field(1, &message, &mut proto);
+        }
+
+        let mut trainer_spec = vec![];
+        bytes_field(1, b"ignored", &mut trainer_spec);
+        varint_field(3, model_type, &mut trainer_spec);
+        varint_field(35, byte_fallback as u64, &mut trainer_spec);
+        varint_field(40, 0, &mut trainer_spec);
+        // A negative int32, encoded over 10 bytes
+        varint_field(43, u64::MAX, &mut trainer_spec);
+        bytes_field(2, &trainer_spec, &mut proto);
+
+        let mut normalizer_spec = vec![];
+        bytes_field(1, b"identity", &mut normalizer_spec);
+        varint_field(3, 1, &mut normalizer_spec);
+        bytes_field(3, &normalizer_spec, &mut proto);
+        proto
+    }
+
+    #[test]
+    fn parse() {
+        let data = model_proto(&[("<unk>", 0.0, 2), ("▁a", -1.5, 1)], 2, true);
+        let proto = ModelProto::parse(&data).unwrap();
+        assert_eq!(
+            proto.pieces[1],
+            Piece {
+                piece: "▁a".into(),
+                score: -1.5,
+                kind: PieceType::Normal
+            }
+        );
+        assert_eq!(proto.model_type, 2);
+        assert!(proto.byte_fallback);
+        assert!(proto.add_dummy_prefix);
+        assert!(proto.precompiled_charsmap.is_empty());
+
+        assert!(ModelProto::parse(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn unigram() {
+        let data = model_proto(
+            &[
+                ("<unk>", 0.0, 2),
+                ("<s>", 0.0, 3),
+                ("</s>", 0.0, 3),
+                ("<0x21>", 0.0, 6),
+                ("▁hello", -1.0, 1),
+                ("▁world", -1.0, 1),
+                ("▁", -2.0, 1),
+                ("w", -3.0, 1),
+                ("orld", -3.0, 1),
+            ],
+            1,
+            true,
+        );
+        let tokenizer = ModelProto::parse(&data).unwrap().into_tokenizer().unwrap();
+
+        let encoding = tokenizer.encode("  hello   world!", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["▁hello", "▁world", "<0x21>"]);
+        assert_eq!(encoding.get_ids(), &[4, 5, 3]);
+        assert_eq!(
+            tokenizer.decode(encoding.get_ids(), false).unwrap(),
+            "hello world!"
+        );
+
+        // The control pieces are matched in the text, unless disabled to behave like
+        // SentencePiece
+        let encoding = tokenizer.encode("hello</s>", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[4, 2]);
+        assert_eq!(tokenizer.decode(encoding.get_ids(), true).unwrap(), "hello");
+        let encoding = tokenizer
+            .encode_with_matching("hello</s>", false, &SpecialTokensMatching::None)
+            .unwrap();
+        assert_eq!(encoding.get_ids()[0], 4);
+        assert!(!encoding.get_ids().contains(&2));
+
+        assert_eq!(tokenizer.unk_token_id(), Some(0));
+        assert_eq!(tokenizer.bos_token_id(), Some(1));
+        assert_eq!(tokenizer.eos_token_id(), Some(2));
+        assert_eq!(tokenizer.pad_token_id(), None);
+    }
+
+    #[test]
+    fn bpe() {
+        let data = model_proto(
+            &[
+                ("<unk>", 0.0, 2),
+                ("▁h", 0.0, 1),
+                ("▁hi", -1.0, 1),
+                ("hi", -2.0, 1),
+                ("▁", -3.0, 1),
+                ("h", -4.0, 1),
+                ("i", -5.0, 1),
+            ],
+            2,
+            false,
+        );
+        let proto = ModelProto::parse(&data).unwrap();
+        let vocab = proto
+            .pieces
+            .iter()
+            .enumerate()
+            .map(|(id, p)| (p.piece.clone(), id as u32))
+            .collect();
+        assert_eq!(
+            proto.bpe_merges(&vocab),
+            vec![
+                ("▁".to_string(), "h".to_string()),
+                ("▁h".to_string(), "i".to_string()),
+                ("▁".to_string(), "hi".to_string()),
+                ("h".to_string(), "i".to_string()),
+            ]
+        );
+
+        let tokenizer = proto.into_tokenizer().unwrap();
+        let encoding = tokenizer.encode("hi hix", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["▁hi", "▁hi", "<unk>"]);
+        assert_eq!(tokenizer.decode(&[2, 3], false).unwrap(), "hihi");
+        // Only the `<unk>` piece has a role, the default ids point to regular pieces
+        assert_eq!(tokenizer.unk_token_id(), Some(0));
+        assert_eq!(tokenizer.bos_token_id(), None);
+        assert_eq!(tokenizer.eos_token_id(), None);
+    }
+
+    #[test]
+    fn unsupported_model_type() {
+        let data = model_proto(&[("<unk>", 0.0, 2)], 3, false);
+        let proto = ModelProto::parse(&data).unwrap();
+        assert!(proto.into_tokenizer().is_err());
+    }
+}
@@ -8,6 +8,8 @@ pub enum TruncationDirection {
     Left,
     #[default]
     Right,
+    /// Keep the head and the tail, and drop the middle
+    Middle,
 }
 
 impl std::convert::AsRef<str> for TruncationDirection {
@@ -15,10 +17,37 @@ impl std::convert::AsRef<str> for TruncationDirection {
         match self {
             TruncationDirection::Left => "left",
             TruncationDirection::Right => "right",
+            TruncationDirection::Middle => "middle",
         }
     }
 }
 
+/// Where an `Encoding` can be cut when truncating it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Eq, Default)]
+pub enum TruncationBoundary {
+    /// Anywhere
+    #[default]
+    Token,
+    /// Between two words, as given by [`Encoding::get_word_ids`]
+    Word,
+    /// After the end of a sentence, or between two words if there is none
+    Sentence,
+}
+
+impl std::convert::AsRef<str> for TruncationBoundary {
+    fn as_ref(&self) -> &str {
+        match self {
+            TruncationBoundary::Token => "token",
+            TruncationBoundary::Word => "word",
+            TruncationBoundary::Sentence => "sentence",
+        }
+    }
+}
+
+fn default_head_ratio() -> f32 {
+    0.5
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TruncationParams {
     #[serde(default)]
@@ -26,6 +55,12 @@ pub struct TruncationParams {
     pub max_length: usize,
     pub strategy: TruncationStrategy,
     pub stride: usize,
+    /// The proportion of the kept tokens that come from the head of the sequence, when
+    /// truncating with `TruncationDirection::Middle`
+    #[serde(default = "default_head_ratio")]
+    pub head_ratio: f32,
+    #[serde(default)]
+    pub boundary: TruncationBoundary,
 }
 
 impl Default for TruncationParams {
@@ -35,6 +70,8 @@ impl Default for TruncationParams {
             strategy: TruncationStrategy::default(),
             stride: 0,
             direction: TruncationDirection::default(),
+            head_ratio: default_head_ratio(),
+            boundary: TruncationBoundary::default(),
         }
     }
 }
@@ -47,6 +84,9 @@ pub enum TruncationError {
     /// We cannot truncate the target sequence enough to respect the provided max length.
     #[error("Truncation error: Sequence to truncate too short to respect the provided max_length")]
     SequenceTooShort,
+    /// The truncation parameters can't be used, like a stride too high for the max length.
+    #[error("Truncation error: {0}")]
+    InvalidParams(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Eq)]
@@ -72,6 +112,84 @@ impl std::convert::AsRef<str> for TruncationStrategy {
     }
 }
 
+/// The longest length, at most `max_len`, of a part that can be kept while respecting the
+/// given boundary, according to `is_boundary`. The boundary gets relaxed when there is no such
+/// part, for example when a single word is longer than `max_len`.
+fn boundary_len<F>(max_len: usize, boundary: TruncationBoundary, is_boundary: F) -> usize
+where
+    F: Fn(usize, TruncationBoundary) -> bool,
+{
+    let relaxed = match boundary {
+        TruncationBoundary::Token => return max_len,
+        TruncationBoundary::Word => TruncationBoundary::Token,
+        TruncationBoundary::Sentence => TruncationBoundary::Word,
+    };
+    (1..=max_len)
+        .rev()
+        .find(|len| is_boundary(*len, boundary))
+        .unwrap_or_else(|| boundary_len(max_len, relaxed, is_boundary))
+}
+
+/// Truncate the given encoding to `max_len` tokens, following the direction and boundary
+/// of the params.
+///
+/// Fails if the `stride` can't be used, because the direction is `Middle` or because the part
+/// kept is not longer than it.
+fn truncate_encoding(
+    encoding: &mut Encoding,
+    max_len: usize,
+    params: &TruncationParams,
+) -> Result<()> {
+    if params.direction == TruncationDirection::Middle && params.stride > 0 {
+        return Err(TruncationError::InvalidParams(format!(
+            "stride set to {}, but it can't be used when truncating the middle",
+            params.stride
+        ))
+        .into());
+    }
+
+    let len = encoding.len();
+    if max_len >= len || max_len == 0 {
+        encoding.truncate(max_len, params.stride, params.direction);
+        return Ok(());
+    }
+
+    match params.direction {
+        TruncationDirection::Right | TruncationDirection::Left => {
+            let kept = boundary_len(max_len, params.boundary, |kept, boundary| {
+                let cut = match params.direction {
+                    TruncationDirection::Left => len - kept,
+                    _ => kept,
+                };
+                encoding.is_boundary(cut, boundary)
+            });
+            if kept <= params.stride {
+                return Err(TruncationError::InvalidParams(format!(
+                    "stride set to {}, which is greater than or equal to the {} tokens kept on a {} boundary",
+                    params.stride,
+                    kept,
+                    params.boundary.as_ref()
+                ))
+                .into());
+            }
+            encoding.truncate(kept, params.stride, params.direction);
+        }
+        TruncationDirection::Middle => {
+            let head_ratio = params.head_ratio.clamp(0.0, 1.0);
+            let head = cmp::min((max_len as f32 * head_ratio).round() as usize, max_len);
+            let tail = max_len - head;
+            let head = boundary_len(head, params.boundary, |kept, boundary| {
+                encoding.is_boundary(kept, boundary)
+            });
+            let tail = boundary_len(tail, params.boundary, |kept, boundary| {
+                encoding.is_boundary(len - kept, boundary)
+            });
+            encoding.truncate_middle(head, tail);
+        }
+    }
+    Ok(())
+}
+
 pub fn truncate_encodings(
     mut encoding: Encoding,
     mut pair_encoding: Option<Encoding>,
@@ -140,10 +258,10 @@ pub fn truncate_encodings(
                 if swap {
                     mem::swap(&mut n1, &mut n2);
                 }
-                encoding.truncate(n1, params.stride, params.direction);
-                other_encoding.truncate(n2, params.stride, params.direction);
+                truncate_encoding(&mut encoding, n1, params)?;
+                truncate_encoding(other_encoding, n2, params)?;
             } else {
-                encoding.truncate(total_length - to_remove, params.stride, params.direction);
+                truncate_encoding(&mut encoding, total_length - to_remove, params)?;
             }
         }
         TruncationStrategy::OnlyFirst | TruncationStrategy::OnlySecond => {
@@ -152,20 +270,108 @@ pub fn truncate_encodings(
             } else if let Some(encoding) = pair_encoding.as_mut() {
                 Ok(encoding)
             } else {
-                Err(Box::new(TruncationError::SecondSequenceNotProvided))
+                Err(TruncationError::SecondSequenceNotProvided)
             }?;
 
             let target_len = target.get_ids().len();
             if target_len > to_remove {
-                target.truncate(target_len - to_remove, params.stride, params.direction);
+                truncate_encoding(target, target_len - to_remove, params)?;
             } else {
-                return Err(Box::new(TruncationError::SequenceTooShort));
+                return Err(TruncationError::SequenceTooShort.into());
             }
         }
     }
     Ok((encoding, pair_encoding))
 }
 
+/// The length to keep for each of the sequences with the given `lengths`, so that they fit
+/// in `max_length` tokens, shortening the longest ones first.
+fn longest_first_lengths(lengths: &[usize], max_length: usize) -> Vec<usize> {
+    // The largest length such that all the sequences, capped to it, fit
+    let fits = |cap: usize| lengths.iter().map(|l| cmp::min(*l, cap)).sum::<usize>() <= max_length;
+    let (mut low, mut high) = (0, max_length);
+    while low < high {
+        let mid = high - (high - low) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    let mut kept = lengths
+        .iter()
+        .map(|l| cmp::min(*l, low))
+        .collect::<Vec<_>>();
+    // The tokens left are given to the longest sequences, one each
+    let left = max_length - kept.iter().sum::<usize>();
+    let mut longest = (0..lengths.len())
+        .filter(|i| lengths[*i] > low)
+        .collect::<Vec<_>>();
+    longest.sort_by_key(|i| cmp::Reverse((lengths[*i], *i)));
+    for i in longest.into_iter().take(left) {
+        kept[i] += 1;
+    }
+    kept
+}
+
+/// Truncate the encodings of any number of sequences. With one or two sequences, this is
+/// the same as [`truncate_encodings`]. With more, `LongestFirst` removes the tokens from the
+/// longest sequences first, until they all fit.
+pub fn truncate_multi_encodings(
+    mut encodings: Vec<Encoding>,
+    params: &TruncationParams,
+) -> Result<Vec<Encoding>> {
+    if encodings.len() <= 2 {
+        let mut encodings = encodings.into_iter();
+        let encoding = match encodings.next() {
+            Some(encoding) => encoding,
+            None => return Ok(vec![]),
+        };
+        let (encoding, pair_encoding) = truncate_encodings(encoding, encodings.next(), params)?;
+        return Ok(std::iter::once(encoding).chain(pair_encoding).collect());
+    }
+
+    if params.max_length == 0 {
+        for encoding in encodings.iter_mut() {
+            encoding.truncate(0, params.stride, params.direction);
+        }
+        return Ok(encodings);
+    }
+
+    let lengths = encodings.iter().map(|e| e.len()).collect::<Vec<_>>();
+    let total_length = lengths.iter().sum::<usize>();
+    let to_remove = if total_length > params.max_length {
+        total_length - params.max_length
+    } else {
+        return Ok(encodings);
+    };
+
+    match params.strategy {
+        TruncationStrategy::LongestFirst => {
+            let kept = longest_first_lengths(&lengths, params.max_length);
+            for (encoding, kept) in encodings.iter_mut().zip(kept) {
+                truncate_encoding(encoding, kept, params)?;
+            }
+        }
+        TruncationStrategy::OnlyFirst | TruncationStrategy::OnlySecond => {
+            let target = if params.strategy == TruncationStrategy::OnlyFirst {
+                &mut encodings[0]
+            } else {
+                &mut encodings[1]
+            };
+
+            let target_len = target.get_ids().len();
+            if target_len > to_remove {
+                truncate_encoding(target, target_len - to_remove, params)?;
+            } else {
+                return Err(TruncationError::SequenceTooShort.into());
+            }
+        }
+    }
+    Ok(encodings)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,6 +489,7 @@ mod tests {
             strategy: TruncationStrategy::LongestFirst,
             stride: 0,
             direction: TruncationDirection::Right,
+            ..Default::default()
         };
 
         truncate_and_assert(get_empty(), get_empty(), &params, 0, 0);
@@ -306,6 +513,54 @@ mod tests {
         truncate_and_assert(get_long(), get_long(), &params, 3, 4);
     }
 
+    #[test]
+    fn truncate_multi_encodings_longest_first() {
+        let params = TruncationParams {
+            max_length: 9,
+            ..Default::default()
+        };
+        let lengths = |encodings: Vec<Encoding>| {
+            truncate_multi_encodings(encodings, &params)
+                .unwrap()
+                .iter()
+                .map(|e| e.len())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lengths(vec![get_short(), get_medium(), get_long()]),
+            [2, 3, 4]
+        );
+        assert_eq!(lengths(vec![get_long(), get_long(), get_long()]), [3, 3, 3]);
+        assert_eq!(
+            lengths(vec![get_empty(), get_long(), get_short()]),
+            [0, 7, 2]
+        );
+        assert_eq!(
+            lengths(vec![get_short(), get_short(), get_medium()]),
+            [2, 2, 4]
+        );
+    }
+
+    #[test]
+    fn truncate_multi_encodings_only_second() {
+        let params = TruncationParams {
+            max_length: 9,
+            strategy: TruncationStrategy::OnlySecond,
+            ..Default::default()
+        };
+
+        let encodings =
+            truncate_multi_encodings(vec![get_short(), get_long(), get_short()], &params).unwrap();
+        assert_eq!(
+            encodings.iter().map(|e| e.len()).collect::<Vec<_>>(),
+            [2, 5, 2]
+        );
+        assert!(
+            truncate_multi_encodings(vec![get_long(), get_short(), get_short()], &params).is_err()
+        );
+    }
+
     #[test]
     fn truncate_encodings_empty() {
         let params = TruncationParams {
@@ -313,6 +568,7 @@ mod tests {
             strategy: TruncationStrategy::LongestFirst,
             stride: 0,
             direction: TruncationDirection::Right,
+            ..Default::default()
         };
 
         truncate_and_assert(get_empty(), get_short(), &params, 0, 0);
@@ -327,5 +583,127 @@ mod tests {
         let params: TruncationParams = serde_json::from_str(old_truncation_params).unwrap();
 
         assert_eq!(params.direction, TruncationDirection::Right);
+        assert_eq!(params.head_ratio, 0.5);
+        assert_eq!(params.boundary, TruncationBoundary::Token);
+    }
+
+    #[test]
+    fn serialization() {
+        let params = TruncationParams {
+            direction: TruncationDirection::Middle,
+            head_ratio: 0.25,
+            boundary: TruncationBoundary::Sentence,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(
+            json,
+            r#"{"direction":"Middle","max_length":512,"strategy":"LongestFirst","stride":0,"head_ratio":0.25,"boundary":"Sentence"}"#
+        );
+        let params: TruncationParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(params.direction, TruncationDirection::Middle);
+        assert_eq!(params.head_ratio, 0.25);
+        assert_eq!(params.boundary, TruncationBoundary::Sentence);
+    }
+
+    /// "Hi there. How are you?" split in tokens, with some words made of two tokens
+    fn get_sentences() -> Encoding {
+        let tokens = ["Hi", "the", "re", ".", "How", "are", "yo", "u", "?"];
+        let words = [0, 1, 1, 2, 3, 4, 5, 5, 6];
+        Encoding::new(
+            (0..tokens.len() as u32).collect(),
+            vec![0; tokens.len()],
+            tokens.iter().map(|t| t.to_string()).collect(),
+            words.iter().map(|w| Some(*w)).collect(),
+            vec![(0, 0); tokens.len()],
+            vec![0; tokens.len()],
+            vec![1; tokens.len()],
+            vec![],
+            HashMap::new(),
+        )
+    }
+
+    fn truncated_tokens(params: &TruncationParams) -> Vec<String> {
+        let (encoding, _) = truncate_encodings(get_sentences(), None, params).unwrap();
+        encoding.get_tokens().to_vec()
+    }
+
+    #[test]
+    fn truncate_middle() {
+        let mut params = TruncationParams {
+            max_length: 4,
+            direction: TruncationDirection::Middle,
+            ..Default::default()
+        };
+        assert_eq!(truncated_tokens(&params), vec!["Hi", "the", "u", "?"]);
+
+        params.head_ratio = 0.75;
+        assert_eq!(truncated_tokens(&params), vec!["Hi", "the", "re", "?"]);
+
+        // The middle goes to the overflowing
+        let (encoding, _) = truncate_encodings(get_sentences(), None, &params).unwrap();
+        assert_eq!(encoding.get_overflowing().len(), 1);
+        assert_eq!(
+            encoding.get_overflowing()[0].get_tokens(),
+            &[".", "How", "are", "yo", "u"]
+        );
+
+        // The head can't end within "there", and the tail can't start within "you"
+        params.head_ratio = 0.5;
+        params.boundary = TruncationBoundary::Word;
+        assert_eq!(truncated_tokens(&params), vec!["Hi", "?"]);
+    }
+
+    #[test]
+    fn truncate_word_boundary() {
+        let mut params = TruncationParams {
+            max_length: 7,
+            ..Default::default()
+        };
+        assert_eq!(
+            truncated_tokens(&params),
+            vec!["Hi", "the", "re", ".", "How", "are", "yo"]
+        );
+
+        params.boundary = TruncationBoundary::Word;
+        assert_eq!(
+            truncated_tokens(&params),
+            vec!["Hi", "the", "re", ".", "How", "are"]
+        );
+
+        params.boundary = TruncationBoundary::Sentence;
+        assert_eq!(truncated_tokens(&params), vec!["Hi", "the", "re", "."]);
+
+        // Without any boundary that fits, the boundary is relaxed
+        params.max_length = 2;
+        assert_eq!(truncated_tokens(&params), vec!["Hi"]);
+        params.boundary = TruncationBoundary::Word;
+        params.max_length = 1;
+        assert_eq!(truncated_tokens(&params), vec!["Hi"]);
+
+        params.direction = TruncationDirection::Left;
+        params.max_length = 4;
+        assert_eq!(truncated_tokens(&params), vec!["are", "yo", "u", "?"]);
+        params.max_length = 3;
+        assert_eq!(truncated_tokens(&params), vec!["yo", "u", "?"]);
+    }
+
+    #[test]
+    fn truncate_invalid_stride() {
+        let mut params = TruncationParams {
+            max_length: 4,
+            stride: 1,
+            direction: TruncationDirection::Middle,
+            ..Default::default()
+        };
+        assert!(truncate_encodings(get_sentences(), None, &params).is_err());
+
+        // Only "Hi" can be kept on a word boundary, which leaves nothing past the stride
+        params.direction = TruncationDirection::Right;
+        params.max_length = 2;
+        params.boundary = TruncationBoundary::Word;
+        assert!(truncate_encodings(get_sentences(), None, &params).is_err());
+        params.boundary = TruncationBoundary::Token;
+        assert_eq!(truncated_tokens(&params), vec!["Hi", "the"]);
     }
 }
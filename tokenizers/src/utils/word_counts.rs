@@ -0,0 +1,461 @@
+//! Counting of the words used to train a model, with bounded memory.
+//!
+//! Trainers first need to count all the words of the corpus. Keeping all the distinct words
+//! in memory doesn't work for very large corpora, so the counts can be spilled to disk once
+//! there are too many of them, and get merged back while pruning the rare words at the end.
+//! Only the words kept after pruning are held in memory at the end, so without `min_count`
+//! or `max_words`, all the distinct words of the corpus still end up in memory.
+use crate::parallelism::*;
+use crate::tokenizer::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Configures how the words are counted before training
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WordCountsConfig {
+    /// The words seen fewer times than this are dropped once counting is done
+    pub min_count: u32,
+    /// Only keep the `max_words` most frequent words
+    pub max_words: Option<usize>,
+    /// The number of distinct words that can be held in memory by each counting shard. Once
+    /// reached, the counts get spilled to disk. Unbounded by default.
+    ///
+    /// This only bounds the memory used while counting: the words returned in the end are
+    /// all held in memory, so `min_count` or `max_words` should be set too.
+    pub max_words_in_memory: Option<usize>,
+    /// Where to spill the counts. Defaults to the system temporary directory.
+    pub spill_dir: Option<PathBuf>,
+}
+
+static SPILL_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum number of spills read at once. When there are more, they are merged in
+/// several passes, to stay below the limit of open files.
+const MAX_OPEN_SPILLS: usize = 64;
+
+/// A file holding counts sorted by word, removed once dropped
+#[derive(Debug)]
+struct Spill {
+    path: PathBuf,
+}
+
+impl Spill {
+    /// Write the given counts, sorted by word, as a sequence of
+    /// `(word length, word bytes, count)` entries
+    fn write(config: &WordCountsConfig, counts: HashMap<String, u32>) -> Result<Self> {
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_unstable();
+
+        let (spill, mut writer) = Spill::create(config)?;
+        for (word, count) in counts {
+            writer.write_entry(&word, count)?;
+        }
+        writer.finish()?;
+        Ok(spill)
+    }
+
+    /// Create a new empty spill, with the writer used to fill it in order
+    fn create(config: &WordCountsConfig) -> Result<(Self, SpillWriter)> {
+        let dir = config.spill_dir.clone().unwrap_or_else(std::env::temp_dir);
+        let path = dir.join(format!(
+            "tokenizers-word-counts-{}-{}.bin",
+            std::process::id(),
+            SPILL_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let spill = Spill { path };
+        let writer = SpillWriter(BufWriter::new(File::create(&spill.path)?));
+        Ok((spill, writer))
+    }
+
+    fn reader(&self) -> Result<SpillReader> {
+        Ok(SpillReader(BufReader::new(File::open(&self.path)?)))
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+struct SpillWriter(BufWriter<File>);
+
+impl SpillWriter {
+    fn write_entry(&mut self, word: &str, count: u32) -> Result<()> {
+        self.0.write_all(&(word.len() as u32).to_le_bytes())?;
+        self.0.write_all(word.as_bytes())?;
+        self.0.write_all(&count.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.0.flush()?;
+        Ok(())
+    }
+}
+
+struct SpillReader(BufReader<File>);
+
+impl SpillReader {
+    fn read_u32(&mut self) -> std::io::Result<Option<u32>> {
+        let mut bytes = [0u8; 4];
+        match self.0.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(u32::from_le_bytes(bytes))),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<(String, u32)>> {
+        let len = match self.read_u32()? {
+            Some(len) => len as usize,
+            None => return Ok(None),
+        };
+        let mut word = vec![0u8; len];
+        self.0.read_exact(&mut word)?;
+        let count = self.read_u32()?.ok_or("Truncated word counts file")?;
+        Ok(Some((String::from_utf8(word)?, count)))
+    }
+}
+
+/// Keeps the words that satisfy the pruning rules of the config
+struct Pruner<'a> {
+    config: &'a WordCountsConfig,
+    kept: HashMap<String, u32>,
+    /// The `max_words` most frequent words, with the least frequent on top. For the same
+    /// count, the words that come last alphabetically are dropped first.
+    top: BinaryHeap<Reverse<(u32, Reverse<String>)>>,
+}
+
+impl<'a> Pruner<'a> {
+    fn new(config: &'a WordCountsConfig) -> Self {
+        Self {
+            config,
+            kept: HashMap::new(),
+            top: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, word: String, count: u32) {
+        if count < self.config.min_count {
+            return;
+        }
+        match self.config.max_words {
+            Some(max_words) => {
+                self.top.push(Reverse((count, Reverse(word))));
+                if self.top.len() > max_words {
+                    self.top.pop();
+                }
+            }
+            None => {
+                self.kept.insert(word, count);
+            }
+        }
+    }
+
+    fn finish(mut self) -> HashMap<String, u32> {
+        self.kept.extend(
+            self.top
+                .into_iter()
+                .map(|Reverse((count, Reverse(word)))| (word, count)),
+        );
+        self.kept
+    }
+}
+
+/// Counts words, spilling the counts to disk when there are too many of them
+#[derive(Debug)]
+pub struct WordCounter<'a> {
+    config: &'a WordCountsConfig,
+    counts: HashMap<String, u32>,
+    spills: Vec<Spill>,
+}
+
+impl<'a> WordCounter<'a> {
+    pub fn new(config: &'a WordCountsConfig) -> Self {
+        Self {
+            config,
+            counts: HashMap::new(),
+            spills: vec![],
+        }
+    }
+
+    /// Add `count` occurrences of the given word
+    pub fn add(&mut self, word: String, count: u32) -> Result<()> {
+        let c = self.counts.entry(word).or_insert(0);
+        *c = c.saturating_add(count);
+        self.maybe_spill()
+    }
+
+    /// Merge the counts of another counter into this one
+    pub fn merge(mut self, other: WordCounter<'a>) -> Result<Self> {
+        let (mut small, large) = if self.counts.len() < other.counts.len() {
+            (self.counts, other.counts)
+        } else {
+            (other.counts, self.counts)
+        };
+        self.counts = large;
+        for (word, count) in small.drain() {
+            let c = self.counts.entry(word).or_insert(0);
+            *c = c.saturating_add(count);
+        }
+        self.spills.extend(other.spills);
+        self.maybe_spill()?;
+        Ok(self)
+    }
+
+    fn maybe_spill(&mut self) -> Result<()> {
+        if let Some(max) = self.config.max_words_in_memory {
+            if self.counts.len() > max {
+                let counts = std::mem::take(&mut self.counts);
+                self.spills.push(Spill::write(self.config, counts)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge all the counts, including the spilled ones, and return the words that are kept
+    /// after pruning.
+    pub fn finish(self) -> Result<HashMap<String, u32>> {
+        self.finish_with(MAX_OPEN_SPILLS)
+    }
+
+    /// Same as `finish`, reading at most `max_open_spills` spills at once
+    fn finish_with(mut self, max_open_spills: usize) -> Result<HashMap<String, u32>> {
+        let mut pruner = Pruner::new(self.config);
+        if self.spills.is_empty() {
+            for (word, count) in self.counts.drain() {
+                pruner.push(word, count);
+            }
+            return Ok(pruner.finish());
+        }
+
+        let counts = std::mem::take(&mut self.counts);
+        if !counts.is_empty() {
+            self.spills.push(Spill::write(self.config, counts)?);
+        }
+
+        // Merging the spills back into fewer ones until they can all be read at once. The
+        // merged spills are removed as soon as they are replaced.
+        let mut spills = std::mem::take(&mut self.spills);
+        while spills.len() > max_open_spills {
+            spills = spills
+                .chunks(max_open_spills)
+                .map(|chunk| {
+                    let (spill, mut writer) = Spill::create(self.config)?;
+                    merge_spills(chunk, |word, count| writer.write_entry(&word, count))?;
+                    writer.finish()?;
+                    Ok(spill)
+                })
+                .collect::<Result<Vec<_>>>()?;
+        }
+
+        merge_spills(&spills, |word, count| {
+            pruner.push(word, count);
+            Ok(())
+        })?;
+        Ok(pruner.finish())
+    }
+}
+
+/// Merge the given spills, calling `emit` with the total count of each word, in order.
+///
+/// Each spill is sorted by word, so we can merge them while only holding one entry of each
+/// in memory.
+fn merge_spills<F>(spills: &[Spill], mut emit: F) -> Result<()>
+where
+    F: FnMut(String, u32) -> Result<()>,
+{
+    let mut readers = spills
+        .iter()
+        .map(|spill| spill.reader())
+        .collect::<Result<Vec<_>>>()?;
+    let mut heads = BinaryHeap::new();
+    for (i, reader) in readers.iter_mut().enumerate() {
+        if let Some((word, count)) = reader.next_entry()? {
+            heads.push(Reverse((word, i, count)));
+        }
+    }
+
+    let mut current: Option<(String, u32)> = None;
+    while let Some(Reverse((word, i, count))) = heads.pop() {
+        if let Some((next_word, next_count)) = readers[i].next_entry()? {
+            heads.push(Reverse((next_word, i, next_count)));
+        }
+        match &mut current {
+            Some((w, c)) if *w == word => *c = c.saturating_add(count),
+            _ => {
+                if let Some((w, c)) = current.replace((word, count)) {
+                    emit(w, c)?;
+                }
+            }
+        }
+    }
+    if let Some((w, c)) = current {
+        emit(w, c)?;
+    }
+    Ok(())
+}
+
+/// Count the words of all the sequences, as extracted by `process`, in parallel.
+///
+/// This is the common implementation of [`Trainer::feed`](crate::Trainer::feed).
+pub fn count_words<I, S, F>(
+    iterator: I,
+    process: F,
+    config: &WordCountsConfig,
+) -> Result<HashMap<String, u32>>
+where
+    I: Iterator<Item = S> + Send,
+    S: AsRef<str> + Send,
+    F: Fn(&str) -> Result<Vec<String>> + Sync,
+{
+    iterator
+        .maybe_par_bridge()
+        .map(|sequence| {
+            let mut counter = WordCounter::new(config);
+            for word in process(sequence.as_ref())? {
+                counter.add(word, 1)?;
+            }
+            Ok(counter)
+        })
+        .reduce(
+            || Ok(WordCounter::new(config)),
+            |acc, counter| acc?.merge(counter?),
+        )?
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bpe::{BpeTrainerBuilder, BPE};
+    use crate::Trainer;
+
+    const CORPUS: [&str; 4] = [
+        "the cat sat on the mat",
+        "the dog sat on the log",
+        "a cat and a dog",
+        "the end",
+    ];
+
+    fn count(config: &WordCountsConfig) -> HashMap<String, u32> {
+        count_words(
+            CORPUS.iter(),
+            |s| Ok(s.split(' ').map(|w| w.to_owned()).collect()),
+            config,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn spilled_counts_are_exact() {
+        let in_memory = count(&WordCountsConfig::default());
+        assert_eq!(in_memory["the"], 5);
+        assert_eq!(in_memory["a"], 2);
+        assert_eq!(in_memory.len(), 10);
+
+        let dir = tempfile::tempdir().unwrap();
+        let spilled = count(&WordCountsConfig {
+            max_words_in_memory: Some(2),
+            spill_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        });
+        assert_eq!(spilled, in_memory);
+        // The spills are removed once merged
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn merge_in_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WordCountsConfig {
+            max_words_in_memory: Some(1),
+            spill_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let mut counter = WordCounter::new(&config);
+        for sentence in CORPUS.iter() {
+            for word in sentence.split(' ') {
+                counter.add(word.to_owned(), 1).unwrap();
+            }
+        }
+        assert!(counter.spills.len() > 4);
+
+        let counts = counter.finish_with(2).unwrap();
+        assert_eq!(counts, count(&WordCountsConfig::default()));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        for max_words_in_memory in [None, Some(1)] {
+            let config = WordCountsConfig {
+                min_count: 2,
+                max_words_in_memory,
+                spill_dir: Some(dir.path().to_path_buf()),
+                ..Default::default()
+            };
+            let mut words = count(&config).into_iter().collect::<Vec<_>>();
+            words.sort();
+            assert_eq!(
+                words,
+                vec![
+                    ("a".to_string(), 2),
+                    ("cat".to_string(), 2),
+                    ("dog".to_string(), 2),
+                    ("on".to_string(), 2),
+                    ("sat".to_string(), 2),
+                    ("the".to_string(), 5),
+                ]
+            );
+
+            // Ties are broken alphabetically
+            let config = WordCountsConfig {
+                max_words: Some(3),
+                ..config
+            };
+            let mut words = count(&config).into_iter().collect::<Vec<_>>();
+            words.sort();
+            assert_eq!(
+                words,
+                vec![
+                    ("a".to_string(), 2),
+                    ("cat".to_string(), 2),
+                    ("the".to_string(), 5),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn trainer_feed() {
+        let mut trainer = BpeTrainerBuilder::new()
+            .show_progress(false)
+            .word_counts(WordCountsConfig {
+                max_words: Some(1),
+                ..Default::default()
+            })
+            .build();
+        trainer
+            .feed(CORPUS.iter(), |s| {
+                Ok(s.split(' ').map(|w| w.to_owned()).collect())
+            })
+            .unwrap();
+        let mut model = BPE::default();
+        trainer.train(&mut model).unwrap();
+
+        let mut vocab = crate::Model::get_vocab(&model)
+            .into_keys()
+            .collect::<Vec<_>>();
+        vocab.sort();
+        assert_eq!(vocab, vec!["e", "h", "he", "t", "the"]);
+    }
+}
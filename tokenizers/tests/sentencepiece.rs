@@ -0,0 +1,44 @@
+use tokenizers::{SpecialTokensMatching, Tokenizer};
+
+// The expected ids are the ones given by `sentencepiece` itself with the same model files
+
+#[test]
+fn unigram_parity() {
+    let tokenizer = Tokenizer::from_sentencepiece("data/t5-spiece.model").unwrap();
+
+    let encoding = tokenizer.encode("Hello world", false).unwrap();
+    assert_eq!(encoding.get_ids(), &[8774, 296]);
+    assert_eq!(encoding.get_tokens(), &["▁Hello", "▁world"]);
+    // The extra whitespaces are removed by the normalizer
+    let encoding = tokenizer.encode("  Hello   world ", false).unwrap();
+    assert_eq!(encoding.get_ids(), &[8774, 296]);
+    assert_eq!(tokenizer.eos_token_id(), Some(1));
+}
+
+#[test]
+fn bpe_parity() {
+    let tokenizer = Tokenizer::from_sentencepiece("data/llama-tokenizer.model").unwrap();
+
+    let encoding = tokenizer.encode("Hello world", false).unwrap();
+    assert_eq!(encoding.get_ids(), &[15043, 3186]);
+    // Unknown chars fall back to their bytes
+    let encoding = tokenizer.encode("🦙", false).unwrap();
+    assert_eq!(encoding.get_ids(), &[29871, 243, 162, 169, 156]);
+    assert_eq!(tokenizer.decode(encoding.get_ids(), false).unwrap(), "🦙");
+
+    // SentencePiece never extracts the control pieces from the text, which needs to be
+    // asked for explicitly
+    let eos = tokenizer.eos_token_id().unwrap();
+    let ids = tokenizer
+        .encode_with_matching("Hello</s>", false, &SpecialTokensMatching::None)
+        .unwrap()
+        .get_ids()
+        .to_vec();
+    assert!(!ids.contains(&eos));
+    let ids = tokenizer
+        .encode("Hello</s>", false)
+        .unwrap()
+        .get_ids()
+        .to_vec();
+    assert_eq!(ids.last(), Some(&eos));
+}